@@ -0,0 +1,75 @@
+//! Compares allocations and duration of deserializing a large roster into the owned and borrowing models.
+//!
+//! Run with `cargo bench --bench roster_allocations`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use basispoort_sync_client::institutions::{borrowed, InstitutionStudents};
+
+const STUDENTS: usize = 10_000;
+const ITERATIONS: u32 = 20;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn roster() -> String {
+    let students: Vec<_> = (1..=STUDENTS)
+        .map(|id| {
+            format!(
+                r#"{{"id":{id},"eckid":"https://id.school/LL_{id:0>40}","lasKey":"{id}","persoonsgegevens":{{"achternaam":"Achternaam {id}","voornaam":"Voornaam","voorvoegsel":"van","voorletters":"V."}},"jaargroep":"{}","groep":"groep-{}","subgroepen":["sub-{}"]}}"#,
+                id % 8 + 1,
+                id % 30,
+                id % 60,
+            )
+        })
+        .collect();
+
+    format!(
+        r#"{{"leerlingen":[{}],"metaResult":{{"mutationTimestamp":"2024-05-01T12:00:00Z","generationTimestamp":"2024-05-01T12:00:00Z"}}}}"#,
+        students.join(",")
+    )
+}
+
+fn measure<T>(name: &str, deserialize: impl Fn() -> T) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let started = Instant::now();
+
+    for _ in 0..ITERATIONS {
+        black_box(deserialize());
+    }
+
+    let elapsed = started.elapsed() / ITERATIONS;
+    let allocations = (ALLOCATIONS.load(Ordering::Relaxed) - allocations) / ITERATIONS as usize;
+    println!(
+        "{name:>8}: {allocations:>8} allocations, {elapsed:?} per roster of {STUDENTS} students"
+    );
+}
+
+fn main() {
+    let body = roster();
+
+    measure("owned", || {
+        serde_json::from_str::<InstitutionStudents>(&body).expect("valid roster")
+    });
+    measure("borrowed", || {
+        borrowed::InstitutionStudents::from_slice(body.as_bytes()).expect("valid roster")
+    });
+}
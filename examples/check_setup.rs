@@ -0,0 +1,35 @@
+//! Validate the client setup, printing one pass/fail line per check.
+//!
+//! Usage: `cargo run --example check_setup --features config -- [config.toml]`
+//!
+//! Without a configuration file, the configuration is read from environment variables (see `.env.dist`).
+
+use std::process::ExitCode;
+
+use basispoort_sync_client::{config::Config, diagnostics::check_setup};
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    dotenvy::dotenv().ok();
+
+    let config = match std::env::args().nth(1) {
+        Some(path) => Config::load(path),
+        None => Config::from_env(),
+    };
+    let config = match config {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("[FAIL] configuration: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let report = check_setup(&config).await;
+    print!("{report}");
+
+    if report.is_ok() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
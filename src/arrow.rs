@@ -0,0 +1,205 @@
+//! Export of rosters and license assignments as Arrow record batches and Parquet files.
+//!
+//! Data teams can load the exports into their lakehouse directly, e.g. to analyze license coverage,
+//! instead of transforming the JSON responses.
+//!
+//! Roster exports contain IDs, LAS keys and group assignments, but no personal data such as names or chain IDs.
+//! They require the `institutions` feature; license assignment exports require the `hosted-license-provider` feature.
+
+// Without either service, only `write_parquet` remains.
+#![cfg_attr(
+    not(any(feature = "institutions", feature = "hosted-license-provider")),
+    allow(dead_code, unused_imports)
+)]
+
+use std::io::Write;
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+
+#[cfg(feature = "hosted-license-provider")]
+use crate::hosted_license_provider::LicenseAssignment;
+#[cfg(feature = "institutions")]
+use crate::institutions::InstitutionOverview;
+#[cfg(feature = "institutions")]
+use crate::BasispoortId;
+use crate::{error::ErrorKind, Result};
+
+/// The roster of `institution_id` as a record batch, with one row per student and staff member.
+///
+/// Columns: `institution_id`, `user_id`, `kind` (`student` or `staff`), `administrative_key`, `year_group`,
+/// and `group`. Staff members have one row per group, or a single row without group.
+#[cfg(feature = "institutions")]
+pub fn roster_record_batch(
+    institution_id: BasispoortId,
+    overview: &InstitutionOverview,
+) -> Result<RecordBatch> {
+    let mut rows = Vec::new();
+
+    for student in &overview.students {
+        rows.push(RosterRow {
+            user_id: student.id.get(),
+            kind: "student",
+            administrative_key: student.administrative_key.as_deref(),
+            year_group: student.year_group.as_deref(),
+            group: student.group.as_deref(),
+        });
+    }
+    for staff_member in &overview.staff {
+        let row = RosterRow {
+            user_id: staff_member.id.get(),
+            kind: "staff",
+            administrative_key: staff_member.administrative_key.as_deref(),
+            year_group: None,
+            group: None,
+        };
+        if staff_member.groups.is_empty() {
+            rows.push(row.clone());
+        }
+        for group in &staff_member.groups {
+            rows.push(RosterRow {
+                group: Some(group.as_str()),
+                ..row.clone()
+            });
+        }
+    }
+
+    record_batch(vec![
+        (
+            Field::new("institution_id", DataType::Int64, false),
+            Arc::new(Int64Array::from(vec![institution_id.get(); rows.len()])),
+        ),
+        (
+            Field::new("user_id", DataType::Int64, false),
+            Arc::new(Int64Array::from_iter_values(
+                rows.iter().map(|row| row.user_id),
+            )),
+        ),
+        (
+            Field::new("kind", DataType::Utf8, false),
+            Arc::new(StringArray::from_iter_values(
+                rows.iter().map(|row| row.kind),
+            )),
+        ),
+        (
+            Field::new("administrative_key", DataType::Utf8, true),
+            Arc::new(StringArray::from_iter(
+                rows.iter().map(|row| row.administrative_key),
+            )),
+        ),
+        (
+            Field::new("year_group", DataType::Utf8, true),
+            Arc::new(StringArray::from_iter(
+                rows.iter().map(|row| row.year_group),
+            )),
+        ),
+        (
+            Field::new("group", DataType::Utf8, true),
+            Arc::new(StringArray::from_iter(rows.iter().map(|row| row.group))),
+        ),
+    ])
+}
+
+#[cfg(feature = "institutions")]
+#[derive(Clone)]
+struct RosterRow<'o> {
+    user_id: i64,
+    kind: &'static str,
+    administrative_key: Option<&'o str>,
+    year_group: Option<&'o str>,
+    group: Option<&'o str>,
+}
+
+/// License assignments as a record batch, with the columns `method_id`, `product_id` and `user_id`.
+#[cfg(feature = "hosted-license-provider")]
+pub fn license_assignments_record_batch(assignments: &[LicenseAssignment]) -> Result<RecordBatch> {
+    record_batch(vec![
+        (
+            Field::new("method_id", DataType::Utf8, false),
+            Arc::new(StringArray::from_iter_values(
+                assignments.iter().map(|assignment| &assignment.method_id),
+            )),
+        ),
+        (
+            Field::new("product_id", DataType::Utf8, true),
+            Arc::new(StringArray::from_iter(
+                assignments
+                    .iter()
+                    .map(|assignment| assignment.product_id.as_deref()),
+            )),
+        ),
+        (
+            Field::new("user_id", DataType::Int64, false),
+            Arc::new(Int64Array::from_iter_values(
+                assignments
+                    .iter()
+                    .map(|assignment| assignment.user_id.get()),
+            )),
+        ),
+    ])
+}
+
+/// Write `batches`, which share a schema, to `writer` as a Parquet file.
+pub fn write_parquet<'b>(
+    writer: impl Write + Send,
+    batches: impl IntoIterator<Item = &'b RecordBatch>,
+) -> Result<()> {
+    let mut batches = batches.into_iter().peekable();
+    let Some(schema) = batches.peek().map(|batch| batch.schema()) else {
+        return Ok(());
+    };
+
+    let mut writer = ArrowWriter::try_new(writer, schema, None)
+        .map_err(|error| ErrorKind::ExportArrow(Box::new(error)))?;
+    for batch in batches {
+        writer
+            .write(batch)
+            .map_err(|error| ErrorKind::ExportArrow(Box::new(error)))?;
+    }
+    writer
+        .close()
+        .map_err(|error| ErrorKind::ExportArrow(Box::new(error)))?;
+
+    Ok(())
+}
+
+fn record_batch(columns: Vec<(Field, ArrayRef)>) -> Result<RecordBatch> {
+    let (fields, arrays): (Vec<_>, Vec<_>) = columns.into_iter().unzip();
+
+    Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), arrays)
+        .map_err(|error| ErrorKind::ExportArrow(Box::new(error)))?)
+}
+
+#[cfg(all(test, feature = "hosted-license-provider"))]
+mod tests {
+    use super::*;
+    use crate::BasispoortId;
+
+    #[test]
+    fn exports_license_assignments_to_parquet() -> Result<()> {
+        let assignments = [
+            LicenseAssignment {
+                method_id: "method".into(),
+                product_id: None,
+                user_id: BasispoortId::from_u32(1),
+            },
+            LicenseAssignment {
+                method_id: "method".into(),
+                product_id: Some("product".into()),
+                user_id: BasispoortId::from_u32(2),
+            },
+        ];
+
+        let batch = license_assignments_record_batch(&assignments)?;
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.column(1).null_count(), 1);
+
+        let mut parquet = Vec::new();
+        write_parquet(&mut parquet, [&batch])?;
+        assert!(parquet.starts_with(b"PAR1") && parquet.ends_with(b"PAR1"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+//! Assertions for integration tests against the hosted license provider service, reusable by downstream suites.
+//!
+//! Changes may take a moment to become visible. Each assertion therefore reads back the state repeatedly until it
+//! matches the expectation or the timeout elapses, and then panics with a description of the difference.
+//! Failing requests are returned as errors, rather than retried.
+//!
+//! ```no_run
+//! # async fn example(
+//! #     client: &basispoort_sync_client::hosted_license_provider::HostedLicenseProviderClient,
+//! # ) -> basispoort_sync_client::Result<()> {
+//! use std::time::Duration;
+//!
+//! use basispoort_sync_client::{assertions::assert_method_users_eventually, BasispoortId};
+//!
+//! let expected = [BasispoortId::from_u32(123), BasispoortId::from_u32(456)];
+//! assert_method_users_eventually(client, "my-method", &expected, Duration::from_secs(5)).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeSet;
+use std::fmt::{self, Debug};
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use crate::{hosted_license_provider::HostedLicenseProviderClient, runtime, BasispoortId, Result};
+
+/// The time between two reads of the state.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The difference between the expected and the actual users.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserIdDiff {
+    /// Expected, but without access.
+    pub missing: BTreeSet<BasispoortId>,
+    /// With access, but not expected.
+    pub unexpected: BTreeSet<BasispoortId>,
+}
+
+impl UserIdDiff {
+    /// Compare `actual` with `expected`, ignoring order and duplicates.
+    pub fn new(expected: &[BasispoortId], actual: &[BasispoortId]) -> Self {
+        let expected: BTreeSet<_> = expected.iter().copied().collect();
+        let actual: BTreeSet<_> = actual.iter().copied().collect();
+
+        Self {
+            missing: expected.difference(&actual).copied().collect(),
+            unexpected: actual.difference(&expected).copied().collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+impl fmt::Display for UserIdDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |ids: &BTreeSet<BasispoortId>| {
+            ids.iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        write!(
+            f,
+            "missing [{}], unexpected [{}]",
+            list(&self.missing),
+            list(&self.unexpected)
+        )
+    }
+}
+
+/// Assert that exactly the `expected` users have access to the method within `timeout`.
+///
+/// # Panics
+///
+/// If the users still differ when `timeout` has elapsed.
+pub async fn assert_method_users_eventually(
+    client: &HostedLicenseProviderClient,
+    method_id: &str,
+    expected: &[BasispoortId],
+    timeout: Duration,
+) -> Result<()> {
+    let diff = eventually(
+        timeout,
+        || async {
+            let users = client.get_method_user_ids(method_id).await?;
+            Ok(UserIdDiff::new(expected, &users.users))
+        },
+        UserIdDiff::is_empty,
+    )
+    .await?;
+
+    assert!(
+        diff.is_empty(),
+        "users of method '{method_id}' did not match within {timeout:?}: {diff}"
+    );
+    Ok(())
+}
+
+/// Assert that exactly the `expected` users have access to the product within `timeout`.
+///
+/// # Panics
+///
+/// If the users still differ when `timeout` has elapsed.
+pub async fn assert_product_users_eventually(
+    client: &HostedLicenseProviderClient,
+    method_id: &str,
+    product_id: &str,
+    expected: &[BasispoortId],
+    timeout: Duration,
+) -> Result<()> {
+    let diff = eventually(
+        timeout,
+        || async {
+            let users = client.get_product_user_ids(method_id, product_id).await?;
+            Ok(UserIdDiff::new(expected, &users.users))
+        },
+        UserIdDiff::is_empty,
+    )
+    .await?;
+
+    assert!(
+        diff.is_empty(),
+        "users of product '{product_id}' of method '{method_id}' did not match within {timeout:?}: {diff}"
+    );
+    Ok(())
+}
+
+/// Assert that the method is listed if `present`, or is not listed otherwise, within `timeout`.
+///
+/// # Panics
+///
+/// If the method is still (not) listed when `timeout` has elapsed.
+pub async fn assert_method_present_eventually(
+    client: &HostedLicenseProviderClient,
+    method_id: &str,
+    present: bool,
+    timeout: Duration,
+) -> Result<()> {
+    let listed = eventually(
+        timeout,
+        || async {
+            let methods = client.get_methods().await?;
+            Ok(methods.methods.iter().any(|method| method.id == method_id))
+        },
+        |listed| *listed == present,
+    )
+    .await?;
+
+    assert_eq!(
+        listed,
+        present,
+        "method '{method_id}' was {} listed within {timeout:?}",
+        if present { "not" } else { "still" }
+    );
+    Ok(())
+}
+
+/// Assert that the product is listed for its method if `present`, or is not listed otherwise, within `timeout`.
+///
+/// # Panics
+///
+/// If the product is still (not) listed when `timeout` has elapsed.
+pub async fn assert_product_present_eventually(
+    client: &HostedLicenseProviderClient,
+    method_id: &str,
+    product_id: &str,
+    present: bool,
+    timeout: Duration,
+) -> Result<()> {
+    let listed = eventually(
+        timeout,
+        || async {
+            let products = client.get_products(method_id).await?;
+            Ok(products
+                .products
+                .iter()
+                .any(|product| product.id == product_id))
+        },
+        |listed| *listed == present,
+    )
+    .await?;
+
+    assert_eq!(
+        listed,
+        present,
+        "product '{product_id}' of method '{method_id}' was {} listed within {timeout:?}",
+        if present { "not" } else { "still" }
+    );
+    Ok(())
+}
+
+/// Read the state with `read` until it is `done`, or `timeout` has elapsed, returning the last state read.
+async fn eventually<T, F, Fut>(timeout: Duration, read: F, done: impl Fn(&T) -> bool) -> Result<T>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let deadline = Instant::now() + timeout;
+    loop {
+        let state = read().await?;
+        if done(&state) || Instant::now() >= deadline {
+            return Ok(state);
+        }
+
+        runtime::sleep(POLL_INTERVAL.min(deadline.saturating_duration_since(Instant::now())))
+            .await?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_user_differences() {
+        let ids =
+            |ids: &[u32]| -> Vec<_> { ids.iter().copied().map(BasispoortId::from_u32).collect() };
+
+        let diff = UserIdDiff::new(&ids(&[1, 2, 3]), &ids(&[3, 4, 2, 4]));
+        assert!(!diff.is_empty());
+        assert_eq!(diff.to_string(), "missing [1], unexpected [4]");
+
+        assert!(UserIdDiff::new(&ids(&[2, 1]), &ids(&[1, 2])).is_empty());
+    }
+
+    #[tokio::test]
+    async fn returns_last_state_on_timeout() -> Result<()> {
+        let state = eventually(Duration::ZERO, || async { Ok(1) }, |state| *state == 2).await?;
+        assert_eq!(state, 1);
+        Ok(())
+    }
+}
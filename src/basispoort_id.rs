@@ -0,0 +1,106 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The ID of a Basispoort institution, user or other entity.
+///
+/// Basispoort defines IDs as signed `int64`, as OpenAPI knows no unsigned types. ¯\_(ツ)_/¯
+/// Negative IDs are rejected on construction and deserialization.
+///
+/// IDs are opaque: they can be compared, hashed and displayed, but not used in arithmetic.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(try_from = "i64", into = "i64")]
+pub struct BasispoortId(i64);
+
+/// [`BasispoortId`] construction or parse error.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseBasispoortIdError {
+    #[error("'{0}' is not a valid Basispoort ID, as it is negative")]
+    Negative(i64),
+
+    #[error("'{0}' is not a valid Basispoort ID string")]
+    InvalidIdString(String),
+}
+
+impl BasispoortId {
+    /// Create a `BasispoortId`, failing if `id` is negative.
+    pub const fn new(id: i64) -> Result<Self, ParseBasispoortIdError> {
+        if id < 0 {
+            return Err(ParseBasispoortIdError::Negative(id));
+        }
+
+        Ok(Self(id))
+    }
+
+    /// Create a `BasispoortId` from an unsigned 32-bit integer, which is always valid.
+    pub const fn from_u32(id: u32) -> Self {
+        Self(id as i64)
+    }
+
+    /// The ID as the signed integer used by the Basispoort API.
+    pub const fn get(self) -> i64 {
+        self.0
+    }
+}
+
+impl fmt::Display for BasispoortId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for BasispoortId {
+    type Err = ParseBasispoortIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let id = s
+            .parse()
+            .map_err(|_| ParseBasispoortIdError::InvalidIdString(s.into()))?;
+
+        Self::new(id)
+    }
+}
+
+impl TryFrom<i64> for BasispoortId {
+    type Error = ParseBasispoortIdError;
+
+    fn try_from(id: i64) -> Result<Self, Self::Error> {
+        Self::new(id)
+    }
+}
+
+impl From<u32> for BasispoortId {
+    fn from(id: u32) -> Self {
+        Self::from_u32(id)
+    }
+}
+
+impl From<BasispoortId> for i64 {
+    fn from(id: BasispoortId) -> Self {
+        id.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_negative_ids() {
+        assert_eq!(
+            BasispoortId::new(-1),
+            Err(ParseBasispoortIdError::Negative(-1))
+        );
+        assert_eq!(
+            "-1".parse::<BasispoortId>(),
+            Err(ParseBasispoortIdError::Negative(-1))
+        );
+        assert!(serde_json::from_str::<BasispoortId>("-1").is_err());
+
+        let id: BasispoortId = serde_json::from_str("128684").unwrap();
+        assert_eq!(id, BasispoortId::from_u32(128684));
+        assert_eq!(id.to_string(), "128684");
+        assert_eq!(serde_json::to_string(&id).unwrap(), "128684");
+    }
+}
@@ -0,0 +1,160 @@
+//! Serializable change sets, describing the difference between two collections of entities.
+//!
+//! Diff helpers and sync planners produce a [`ChangeSet`], so planned changes can be stored, reviewed
+//! and approved out-of-band, e.g. as JSON, before being applied.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// A single change to an entity.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change<T> {
+    /// The entity is new.
+    Added { after: T },
+    /// The entity no longer exists.
+    Removed { before: T },
+    /// The entity exists in both collections, but differs.
+    Modified { before: T, after: T },
+}
+
+impl<T> Change<T> {
+    /// The entity before the change, if it existed.
+    pub fn before(&self) -> Option<&T> {
+        match self {
+            Change::Added { .. } => None,
+            Change::Removed { before } | Change::Modified { before, .. } => Some(before),
+        }
+    }
+
+    /// The entity after the change, if it still exists.
+    pub fn after(&self) -> Option<&T> {
+        match self {
+            Change::Removed { .. } => None,
+            Change::Added { after } | Change::Modified { after, .. } => Some(after),
+        }
+    }
+}
+
+/// An ordered list of changes.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ChangeSet<T> {
+    pub changes: Vec<Change<T>>,
+}
+
+impl<T> Default for ChangeSet<T> {
+    fn default() -> Self {
+        Self {
+            changes: Vec::new(),
+        }
+    }
+}
+
+impl<T: PartialEq> ChangeSet<T> {
+    /// Compare `before` and `after`, matching entities by `key`.
+    ///
+    /// Additions and modifications are listed in the order of `after`, followed by removals ordered by key.
+    pub fn diff<K: Ord>(
+        before: impl IntoIterator<Item = T>,
+        after: impl IntoIterator<Item = T>,
+        key: impl Fn(&T) -> K,
+    ) -> Self {
+        let mut before: BTreeMap<K, T> =
+            before.into_iter().map(|item| (key(&item), item)).collect();
+
+        let mut changes: Vec<_> = after
+            .into_iter()
+            .filter_map(|after| match before.remove(&key(&after)) {
+                None => Some(Change::Added { after }),
+                Some(before) if before != after => Some(Change::Modified { before, after }),
+                Some(_) => None,
+            })
+            .collect();
+
+        changes.extend(
+            before
+                .into_values()
+                .map(|before| Change::Removed { before }),
+        );
+
+        Self { changes }
+    }
+}
+
+impl<T> ChangeSet<T> {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Entities to be added.
+    pub fn added(&self) -> impl Iterator<Item = &T> {
+        self.changes.iter().filter_map(|change| match change {
+            Change::Added { after } => Some(after),
+            _ => None,
+        })
+    }
+
+    /// Entities to be removed.
+    pub fn removed(&self) -> impl Iterator<Item = &T> {
+        self.changes.iter().filter_map(|change| match change {
+            Change::Removed { before } => Some(before),
+            _ => None,
+        })
+    }
+
+    /// Entities to be modified, as `(before, after)` pairs.
+    pub fn modified(&self) -> impl Iterator<Item = (&T, &T)> {
+        self.changes.iter().filter_map(|change| match change {
+            Change::Modified { before, after } => Some((before, after)),
+            _ => None,
+        })
+    }
+}
+
+impl<T> FromIterator<Change<T>> for ChangeSet<T> {
+    fn from_iter<I: IntoIterator<Item = Change<T>>>(iter: I) -> Self {
+        Self {
+            changes: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<T> IntoIterator for ChangeSet<T> {
+    type Item = Change<T>;
+    type IntoIter = std::vec::IntoIter<Change<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.changes.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffs_and_serializes() {
+        let changes = ChangeSet::diff(
+            [(1, "a"), (2, "b"), (3, "c")],
+            [(3, "c"), (2, "B"), (4, "d")],
+            |item| item.0,
+        );
+
+        assert_eq!(changes.added().collect::<Vec<_>>(), [&(4, "d")]);
+        assert_eq!(changes.removed().collect::<Vec<_>>(), [&(1, "a")]);
+        assert_eq!(
+            changes.modified().collect::<Vec<_>>(),
+            [(&(2, "b"), &(2, "B"))]
+        );
+
+        assert_eq!(
+            serde_json::to_string(&changes).unwrap(),
+            r#"{"changes":[{"change":"modified","before":[2,"b"],"after":[2,"B"]},{"change":"added","after":[4,"d"]},{"change":"removed","before":[1,"a"]}]}"#
+        );
+    }
+}
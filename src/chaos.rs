@@ -0,0 +1,142 @@
+//! Fault injection for resilience testing.
+//!
+//! Configure a [`FaultInjection`] on the [`RestClientBuilder`][crate::rest::RestClientBuilder::fault_injection]
+//! to make requests randomly fail with timeouts, `500 Internal Server Error` responses, or malformed response bodies.
+//! This lets you verify retry and error handling paths against realistic failure modes.
+//!
+//! Never enable fault injection in production.
+
+use std::sync::{Arc, Mutex};
+
+/// Probabilities of injecting each kind of fault into a request.
+#[derive(Debug, Clone)]
+pub struct FaultInjection {
+    timeout_probability: f64,
+    server_error_probability: f64,
+    malformed_body_probability: f64,
+    rng: Arc<Mutex<fastrand::Rng>>,
+}
+
+/// A fault injected into a request.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Fault {
+    /// Fail the request as if it timed out, without sending it.
+    Timeout,
+    /// Respond with `500 Internal Server Error`, without sending the request.
+    ServerError,
+    /// Send the request, but truncate the response body.
+    MalformedBody,
+}
+
+impl Default for FaultInjection {
+    fn default() -> Self {
+        Self {
+            timeout_probability: 0.0,
+            server_error_probability: 0.0,
+            malformed_body_probability: 0.0,
+            rng: Arc::new(Mutex::new(fastrand::Rng::new())),
+        }
+    }
+}
+
+impl FaultInjection {
+    /// Create a `FaultInjection` which injects no faults, until configured otherwise.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Return a new `FaultInjection` with a fixed random seed, for reproducible test runs.
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self {
+            rng: Arc::new(Mutex::new(fastrand::Rng::with_seed(seed))),
+            ..self
+        }
+    }
+
+    /// Return a new `FaultInjection` failing requests as timed out with the given probability (`0.0..=1.0`).
+    pub fn with_timeouts(self, probability: f64) -> Self {
+        Self {
+            timeout_probability: probability.clamp(0.0, 1.0),
+            ..self
+        }
+    }
+
+    /// Return a new `FaultInjection` responding with `500 Internal Server Error` with the given probability (`0.0..=1.0`).
+    pub fn with_server_errors(self, probability: f64) -> Self {
+        Self {
+            server_error_probability: probability.clamp(0.0, 1.0),
+            ..self
+        }
+    }
+
+    /// Return a new `FaultInjection` truncating response bodies with the given probability (`0.0..=1.0`).
+    pub fn with_malformed_bodies(self, probability: f64) -> Self {
+        Self {
+            malformed_body_probability: probability.clamp(0.0, 1.0),
+            ..self
+        }
+    }
+
+    /// Decide which fault, if any, to inject into the next request.
+    pub(crate) fn roll(&self) -> Option<Fault> {
+        let roll = self
+            .rng
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .f64();
+
+        let mut threshold = self.timeout_probability;
+        if roll < threshold {
+            return Some(Fault::Timeout);
+        }
+        threshold += self.server_error_probability;
+        if roll < threshold {
+            return Some(Fault::ServerError);
+        }
+        threshold += self.malformed_body_probability;
+        if roll < threshold {
+            return Some(Fault::MalformedBody);
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn injects_configured_faults() {
+        assert_eq!(FaultInjection::new().roll(), None);
+        assert_eq!(
+            FaultInjection::new().with_timeouts(1.0).roll(),
+            Some(Fault::Timeout)
+        );
+        assert_eq!(
+            FaultInjection::new().with_server_errors(1.0).roll(),
+            Some(Fault::ServerError)
+        );
+        assert_eq!(
+            FaultInjection::new().with_malformed_bodies(1.0).roll(),
+            Some(Fault::MalformedBody)
+        );
+    }
+
+    #[test]
+    fn is_reproducible_with_seed() {
+        let faults = || {
+            let chaos = FaultInjection::new()
+                .with_seed(42)
+                .with_timeouts(0.2)
+                .with_server_errors(0.2)
+                .with_malformed_bodies(0.2);
+            (0..100).map(|_| chaos.roll()).collect::<Vec<_>>()
+        };
+
+        let first = faults();
+        assert_eq!(first, faults());
+        assert!(first.contains(&None));
+        assert!(first.contains(&Some(Fault::Timeout)));
+    }
+}
@@ -0,0 +1,195 @@
+//! Checkpoints for resumable chunked operations.
+//!
+//! A [`Checkpoint`] records in a [`StateStore`] how many chunks of a job have been confirmed by the server.
+//! If the job is interrupted, e.g. by a crash or deployment, running it again under the same job ID
+//! skips the confirmed chunks, rather than resending everything.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+#[cfg(not(coverage))]
+use tracing::instrument;
+use tracing::{debug, info, warn};
+
+use crate::{
+    error::ErrorKind,
+    progress::{ProgressReporter, ProgressSender},
+    state::StateStore,
+    Result,
+};
+
+const KEY_PREFIX: &str = "checkpoint/";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+struct CheckpointRecord {
+    completed: usize,
+    total: usize,
+}
+
+/// The progress of a chunked job, recorded in a [`StateStore`].
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    store: Arc<dyn StateStore>,
+    key: String,
+}
+
+impl Checkpoint {
+    /// Create a checkpoint for the job identified by `job_id`, e.g. `"grant/method-1"`.
+    ///
+    /// The job ID must be stable across restarts, and unique among concurrently running jobs.
+    pub fn new(store: Arc<dyn StateStore>, job_id: &str) -> Self {
+        Self {
+            store,
+            key: format!("{KEY_PREFIX}{job_id}"),
+        }
+    }
+
+    /// The number of chunks confirmed so far, if the job has been started and not yet finished.
+    pub async fn completed_chunks(&self) -> Result<Option<usize>> {
+        Ok(self.record().await?.map(|record| record.completed))
+    }
+
+    /// Forget the job's progress, so it is run from the start next time.
+    pub async fn clear(&self) -> Result<()> {
+        self.store.delete(&self.key).await
+    }
+
+    /// Apply `apply` to each of `chunks` in order, skipping chunks confirmed by an earlier, interrupted run.
+    ///
+    /// Progress is recorded after each chunk, and cleared when all chunks are done. If the number of chunks
+    /// differs from the interrupted run, the input has changed, and the job is run from the start.
+    pub async fn run<'c, C, F, Fut>(&self, chunks: &'c [C], apply: F) -> Result<()>
+    where
+        F: FnMut(&'c C) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        self.run_with_progress(chunks, apply, None, "checkpoint.run")
+            .await
+    }
+
+    /// Apply `apply` to each of `chunks`, as [`Self::run`] does, reporting each chunk to `progress` as `operation`.
+    ///
+    /// Chunks confirmed by an earlier, interrupted run are reported as completed up front.
+    #[cfg_attr(
+        not(coverage),
+        instrument(skip(chunks, apply, progress), fields(chunks.len = chunks.len()))
+    )]
+    pub async fn run_with_progress<'c, C, F, Fut>(
+        &self,
+        chunks: &'c [C],
+        apply: F,
+        progress: Option<&ProgressSender>,
+        operation: &'static str,
+    ) -> Result<()>
+    where
+        F: FnMut(&'c C) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let mut reporter = ProgressReporter::start(progress, operation, Some(chunks.len()));
+        let result = self.apply_chunks(chunks, apply, &mut reporter).await;
+        reporter.finish();
+
+        result
+    }
+
+    async fn apply_chunks<'c, C, F, Fut>(
+        &self,
+        chunks: &'c [C],
+        mut apply: F,
+        reporter: &mut ProgressReporter,
+    ) -> Result<()>
+    where
+        F: FnMut(&'c C) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let total = chunks.len();
+        let completed = match self.record().await? {
+            Some(record) if record.total == total => record.completed,
+            Some(record) => {
+                warn!(
+                    "Checkpoint '{}' was recorded for {} chunks, but the job has {total}; starting over.",
+                    self.key, record.total
+                );
+                0
+            }
+            None => 0,
+        };
+        if completed > 0 {
+            info!(
+                "Resuming '{}' after {completed} of {total} chunks.",
+                self.key
+            );
+        }
+        reporter.items_completed(completed);
+
+        for (index, chunk) in chunks.iter().enumerate().skip(completed) {
+            reporter.record(apply(chunk).await)?;
+
+            let record = CheckpointRecord {
+                completed: index + 1,
+                total,
+            };
+            let value = serde_json::to_value(&record).map_err(ErrorKind::EncodePayload)?;
+            self.store.put(&self.key, value).await?;
+            debug!("Confirmed chunk {} of {total}.", index + 1);
+        }
+
+        self.clear().await
+    }
+
+    async fn record(&self) -> Result<Option<CheckpointRecord>> {
+        let Some(value) = self.store.get(&self.key).await? else {
+            return Ok(None);
+        };
+
+        Ok(Some(CheckpointRecord::deserialize(value).map_err(
+            |source| ErrorKind::StateRecord {
+                key: self.key.clone(),
+                source,
+            },
+        )?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{error::Error, state::MemoryStateStore};
+
+    #[tokio::test]
+    async fn resumes_after_last_confirmed_chunk() -> Result<()> {
+        let checkpoint = Checkpoint::new(Arc::new(MemoryStateStore::new()), "grant/method-1");
+        let chunks = [1, 2, 3, 4];
+        let sent = Mutex::new(Vec::new());
+
+        let interrupted = checkpoint
+            .run(&chunks, |chunk| {
+                let result = if *chunk == 3 {
+                    Err(Error::from(ErrorKind::UnknownEnvironment {
+                        label: "interrupted".into(),
+                    }))
+                } else {
+                    sent.lock().unwrap().push(*chunk);
+                    Ok(())
+                };
+                async { result }
+            })
+            .await;
+        assert!(interrupted.is_err());
+        assert_eq!(checkpoint.completed_chunks().await?, Some(2));
+
+        checkpoint
+            .run(&chunks, |chunk| {
+                sent.lock().unwrap().push(*chunk);
+                async { Ok(()) }
+            })
+            .await?;
+        assert_eq!(*sent.lock().unwrap(), [1, 2, 3, 4]);
+        assert_eq!(checkpoint.completed_chunks().await?, None);
+
+        Ok(())
+    }
+}
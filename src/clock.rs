@@ -0,0 +1,154 @@
+//! The source of the current time, and the mechanism for waiting, both replaceable in tests.
+//!
+//! Time-dependent logic, e.g. [`Lease`][crate::lease::Lease] expiry, rate limiting and date-range helpers,
+//! reads the time from a [`Clock`]. [`SystemClock`] is the default. [`ManualClock`] only advances when told to,
+//! so tests can fast-forward deterministically instead of sleeping.
+//!
+//! Waiting, e.g. by the [`RateLimiter`][crate::rate_limit::RateLimiter], goes through a [`Sleeper`].
+//! [`RuntimeSleeper`] is the default. [`RecordingSleeper`] returns immediately, recording the requested delays,
+//! so tests of back-off behavior run instantly and can assert the exact delays.
+
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+
+use crate::Result;
+
+/// A source of the current time.
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// The system's wall clock.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock standing still until advanced or set. Clones share their time.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<SystemTime>>,
+}
+
+impl ManualClock {
+    pub fn new(now: SystemTime) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(now)),
+        }
+    }
+
+    /// Move the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        *self.time() += duration;
+    }
+
+    pub fn set(&self, now: SystemTime) {
+        *self.time() = now;
+    }
+
+    fn time(&self) -> std::sync::MutexGuard<'_, SystemTime> {
+        self.now
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        *self.time()
+    }
+}
+
+/// A mechanism for waiting.
+#[async_trait]
+pub trait Sleeper: Debug + Send + Sync {
+    /// Wait until `duration` has elapsed.
+    async fn sleep(&self, duration: Duration) -> Result<()>;
+}
+
+/// Waits using the async runtime selected by the crate features.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct RuntimeSleeper;
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait]
+impl Sleeper for RuntimeSleeper {
+    async fn sleep(&self, duration: Duration) -> Result<()> {
+        crate::runtime::sleep(duration).await
+    }
+}
+
+/// Returns immediately, recording the requested delays. Clones share their records.
+///
+/// If created with [`RecordingSleeper::with_clock`], each delay also advances the clock,
+/// as if the time had passed.
+#[derive(Debug, Clone, Default)]
+pub struct RecordingSleeper {
+    delays: Arc<Mutex<Vec<Duration>>>,
+    clock: Option<ManualClock>,
+}
+
+impl RecordingSleeper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Advance `clock` by each requested delay.
+    pub fn with_clock(clock: ManualClock) -> Self {
+        Self {
+            delays: Arc::default(),
+            clock: Some(clock),
+        }
+    }
+
+    /// The requested delays, in order.
+    pub fn delays(&self) -> Vec<Duration> {
+        self.delays
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+}
+
+#[async_trait]
+impl Sleeper for RecordingSleeper {
+    async fn sleep(&self, duration: Duration) -> Result<()> {
+        self.delays
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(duration);
+        if let Some(clock) = &self.clock {
+            clock.advance(duration);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn records_delays_and_advances_clock() {
+        let clock = ManualClock::new(SystemTime::UNIX_EPOCH);
+        let sleeper = RecordingSleeper::with_clock(clock.clone());
+
+        sleeper.sleep(Duration::from_secs(1)).await.unwrap();
+        sleeper.clone().sleep(Duration::from_secs(2)).await.unwrap();
+
+        assert_eq!(
+            sleeper.delays(),
+            [Duration::from_secs(1), Duration::from_secs(2)]
+        );
+        assert_eq!(clock.now(), SystemTime::UNIX_EPOCH + Duration::from_secs(3));
+    }
+}
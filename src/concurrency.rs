@@ -0,0 +1,113 @@
+//! Runtime-agnostic helpers running futures concurrently within the calling task.
+
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::Poll;
+
+use futures_lite::future;
+
+/// Poll all `futures` concurrently, returning their outputs in order.
+pub(crate) async fn join_all<F: Future>(futures: Vec<F>) -> Vec<F::Output> {
+    let mut futures: Vec<Pin<Box<F>>> = futures.into_iter().map(Box::pin).collect();
+    let mut outputs: Vec<Option<F::Output>> = futures.iter().map(|_| None).collect();
+
+    future::poll_fn(|cx| {
+        let mut pending = false;
+        for (future, output) in futures.iter_mut().zip(outputs.iter_mut()) {
+            if output.is_none() {
+                match future.as_mut().poll(cx) {
+                    Poll::Ready(value) => *output = Some(value),
+                    Poll::Pending => pending = true,
+                }
+            }
+        }
+
+        if pending {
+            Poll::Pending
+        } else {
+            Poll::Ready(())
+        }
+    })
+    .await;
+
+    outputs.into_iter().flatten().collect()
+}
+
+/// Call `f` for each of the `items`, with at most `max_in_flight` calls running at a time,
+/// returning each item with its output, in the order the calls finished.
+#[cfg_attr(not(feature = "institutions"), allow(dead_code))]
+pub(crate) async fn map_bounded<I, T, F, Fut>(
+    items: I,
+    max_in_flight: NonZeroUsize,
+    f: F,
+) -> Vec<(I::Item, T)>
+where
+    I: IntoIterator,
+    I::Item: Copy,
+    F: Fn(I::Item) -> Fut,
+    Fut: Future<Output = T>,
+{
+    let queue = Mutex::new(items.into_iter());
+    let outputs = Mutex::new(Vec::new());
+
+    let workers = (0..max_in_flight.get())
+        .map(|_| async {
+            loop {
+                let next = queue
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .next();
+                let Some(item) = next else {
+                    break;
+                };
+
+                let output = f(item).await;
+                outputs
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .push((item, output));
+            }
+        })
+        .collect();
+    join_all(workers).await;
+
+    outputs
+        .into_inner()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn bounds_calls_in_flight() {
+        let in_flight = AtomicUsize::new(0);
+        let max_seen = AtomicUsize::new(0);
+
+        let mut outputs = map_bounded(1..=10u64, NonZeroUsize::new(3).unwrap(), |item| {
+            let in_flight = &in_flight;
+            let max_seen = &max_seen;
+            async move {
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_seen.fetch_max(current, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(item)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                item * 2
+            }
+        })
+        .await;
+
+        outputs.sort();
+        assert_eq!(
+            outputs,
+            (1..=10).map(|item| (item, item * 2)).collect::<Vec<_>>()
+        );
+        assert_eq!(max_seen.load(Ordering::SeqCst), 3);
+    }
+}
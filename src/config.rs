@@ -0,0 +1,307 @@
+//! Load client settings from a TOML file, with environment variable overrides.
+//!
+//! Each configuration key may be overridden by an environment variable of the same name in upper case,
+//! e.g. `identity_cert_file` by `IDENTITY_CERT_FILE`. These are the variables used in `.env.dist`.
+//!
+//! ```toml
+//! environment = "acceptance"
+//! identity_cert_file = "/etc/basispoort/identity.pem"
+//! connect_timeout_secs = 10
+//! timeout_secs = 30
+//! rate_limit_requests_per_second = 20
+//! rate_limit_burst = 50
+//! hosted_license_provider_identity_code = "my-publisher"
+//! institutions_api_version = "v2"
+//!
+//! # Only for gateway deployments remapping the service paths. Omitted paths keep their defaults.
+//! [service_paths]
+//! institutions = "basispoort/rest/"
+//! ```
+
+use std::{env, num::NonZeroU32, path::Path, str::FromStr, time::Duration};
+
+use serde::Deserialize;
+#[cfg(not(coverage))]
+use tracing::instrument;
+
+use crate::{
+    error::{Error, ErrorKind},
+    rest::{Environment, RestClientBuilder, ServicePaths},
+    Result,
+};
+
+/// Client settings, loaded from a TOML file and/or environment variables.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// The Basispoort environment to connect to.
+    pub environment: Option<Environment>,
+
+    /// Path to the identity certificate (PEM) file.
+    pub identity_cert_file: Option<String>,
+
+    /// Connect timeout in seconds.
+    pub connect_timeout_secs: Option<u64>,
+
+    /// Request-response timeout in seconds.
+    pub timeout_secs: Option<u64>,
+
+    /// The average number of requests per second to limit the client to. Unlimited if absent.
+    pub rate_limit_requests_per_second: Option<NonZeroU32>,
+
+    /// The number of requests allowed in a burst, if rate limited. Defaults to the requests per second.
+    pub rate_limit_burst: Option<NonZeroU32>,
+
+    /// Publisher code, used to identify "Hosted Lika"s.
+    pub hosted_license_provider_identity_code: Option<String>,
+
+    /// The institutions API version. Negotiated with the environment if absent.
+    #[cfg(feature = "institutions")]
+    pub institutions_api_version: Option<crate::institutions::InstitutionsApiVersion>,
+
+    /// The base paths of the services, if remapped. Not overridable by environment variables.
+    pub service_paths: Option<ServicePaths>,
+}
+
+impl Config {
+    /// Read the configuration from the TOML file at `path`, then apply environment variable overrides.
+    #[cfg_attr(not(coverage), instrument)]
+    pub fn load(path: impl AsRef<Path> + std::fmt::Debug) -> Result<Self> {
+        let path = path.as_ref();
+        let toml = std::fs::read_to_string(path).map_err(|source| ErrorKind::ReadConfigFile {
+            path: path.to_owned(),
+            source,
+        })?;
+
+        let config: Self = toml::from_str(&toml).map_err(|source| ErrorKind::ParseConfigFile {
+            path: path.to_owned(),
+            source,
+        })?;
+
+        config.with_overrides(|key| env::var(key).ok())
+    }
+
+    /// Read the configuration from environment variables only.
+    pub fn from_env() -> Result<Self> {
+        Self::default().with_overrides(|key| env::var(key).ok())
+    }
+
+    /// Parse the configuration from a TOML string, without applying environment variable overrides.
+    pub fn from_toml_str(toml: &str) -> Result<Self> {
+        Ok(toml::from_str(toml).map_err(ErrorKind::ParseConfig)?)
+    }
+
+    /// Override configuration values by those returned from `lookup`, called with the upper case key.
+    pub fn with_overrides(self, lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        Ok(Self {
+            environment: match lookup("ENVIRONMENT") {
+                Some(value) => Some(value.parse().map_err(|_| ErrorKind::InvalidConfigValue {
+                    key: "ENVIRONMENT".into(),
+                    value,
+                })?),
+                None => self.environment,
+            },
+            identity_cert_file: lookup("IDENTITY_CERT_FILE").or(self.identity_cert_file),
+            connect_timeout_secs: parse_override(&lookup, "CONNECT_TIMEOUT_SECS")?
+                .or(self.connect_timeout_secs),
+            timeout_secs: parse_override(&lookup, "TIMEOUT_SECS")?.or(self.timeout_secs),
+            rate_limit_requests_per_second: parse_override(
+                &lookup,
+                "RATE_LIMIT_REQUESTS_PER_SECOND",
+            )?
+            .or(self.rate_limit_requests_per_second),
+            rate_limit_burst: parse_override(&lookup, "RATE_LIMIT_BURST")?
+                .or(self.rate_limit_burst),
+            hosted_license_provider_identity_code: lookup("HOSTED_LICENSE_PROVIDER_IDENTITY_CODE")
+                .or(self.hosted_license_provider_identity_code),
+            #[cfg(feature = "institutions")]
+            institutions_api_version: match lookup("INSTITUTIONS_API_VERSION") {
+                Some(value) => Some(value.parse().map_err(|_| ErrorKind::InvalidConfigValue {
+                    key: "INSTITUTIONS_API_VERSION".into(),
+                    value,
+                })?),
+                None => self.institutions_api_version,
+            },
+            service_paths: self.service_paths,
+        })
+    }
+
+    /// Create a [`RestClientBuilder`] configured with these settings.
+    pub fn rest_client_builder(&self) -> Result<RestClientBuilder> {
+        let identity_cert_file = self
+            .identity_cert_file
+            .as_deref()
+            .ok_or_else(|| missing("identity_cert_file"))?;
+        let environment = self
+            .environment
+            .clone()
+            .ok_or_else(|| missing("environment"))?;
+
+        let mut builder = RestClientBuilder::new(identity_cert_file, environment)?;
+        if let Some(secs) = self.connect_timeout_secs {
+            builder.connect_timeout(Duration::from_secs(secs));
+        }
+        if let Some(secs) = self.timeout_secs {
+            builder.timeout(Duration::from_secs(secs));
+        }
+        if let Some(service_paths) = &self.service_paths {
+            builder.service_paths(service_paths.clone());
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(requests_per_second) = self.rate_limit_requests_per_second {
+            builder.rate_limiter(crate::rate_limit::RateLimiter::new(
+                requests_per_second,
+                self.rate_limit_burst.unwrap_or(requests_per_second),
+            ));
+        }
+
+        Ok(builder)
+    }
+
+    /// Create an [`InstitutionsServiceClient`][crate::institutions::InstitutionsServiceClient] for the configured
+    /// API version, or else the newest version the environment serves.
+    #[cfg(feature = "institutions")]
+    pub async fn institutions_client(
+        &self,
+        rest_client: &crate::rest::RestClient,
+    ) -> Result<crate::institutions::InstitutionsServiceClient> {
+        use crate::institutions::InstitutionsServiceClient;
+
+        match self.institutions_api_version {
+            Some(version) => Ok(InstitutionsServiceClient::with_version(
+                rest_client,
+                version,
+            )),
+            None => InstitutionsServiceClient::negotiate(rest_client).await,
+        }
+    }
+
+    /// Create a [`HostedLicenseProviderClient`][crate::hosted_license_provider::HostedLicenseProviderClient]
+    /// for the configured identity code.
+    #[cfg(feature = "hosted-license-provider")]
+    pub fn hosted_license_provider_client(
+        &self,
+        rest_client: &crate::rest::RestClient,
+    ) -> Result<crate::hosted_license_provider::HostedLicenseProviderClient> {
+        let identity_code = self
+            .hosted_license_provider_identity_code
+            .as_deref()
+            .ok_or_else(|| missing("hosted_license_provider_identity_code"))?;
+        if crate::hosted_license_provider::IdentityCode::new(identity_code).is_err() {
+            return Err(ErrorKind::InvalidConfigValue {
+                key: "hosted_license_provider_identity_code".into(),
+                value: identity_code.into(),
+            }
+            .into());
+        }
+
+        Ok(
+            crate::hosted_license_provider::HostedLicenseProviderClient::new(
+                rest_client,
+                identity_code,
+            ),
+        )
+    }
+}
+
+fn parse_override<T: FromStr>(
+    lookup: impl Fn(&str) -> Option<String>,
+    key: &str,
+) -> Result<Option<T>> {
+    lookup(key)
+        .map(|value| {
+            value.parse().map_err(|_| {
+                ErrorKind::InvalidConfigValue {
+                    key: key.into(),
+                    value,
+                }
+                .into()
+            })
+        })
+        .transpose()
+}
+
+fn missing(key: &str) -> Error {
+    ErrorKind::MissingConfigValue { key: key.into() }.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML: &str = r#"
+        environment = "acceptance"
+        identity_cert_file = "/etc/basispoort/identity.pem"
+        timeout_secs = 60
+    "#;
+
+    #[test]
+    fn parses_toml() -> Result<()> {
+        let config = Config::from_toml_str(TOML)?;
+
+        assert_eq!(config.environment, Some(Environment::Acceptance));
+        assert_eq!(
+            config.identity_cert_file.as_deref(),
+            Some("/etc/basispoort/identity.pem")
+        );
+        assert_eq!(config.connect_timeout_secs, None);
+        assert_eq!(config.timeout_secs, Some(60));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parses_partial_service_paths() -> Result<()> {
+        let config = Config::from_toml_str(&format!(
+            "{TOML}\n[service_paths]\ninstitutions = \"basispoort/rest/\"\n"
+        ))?;
+
+        let service_paths = config.service_paths.unwrap();
+        assert_eq!(service_paths.institutions, "basispoort/rest/");
+        assert_eq!(
+            service_paths.license_gateway,
+            ServicePaths::default().license_gateway
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn applies_overrides() -> Result<()> {
+        let config = Config::from_toml_str(TOML)?.with_overrides(|key| match key {
+            "ENVIRONMENT" => Some("test".into()),
+            "TIMEOUT_SECS" => Some("90".into()),
+            "RATE_LIMIT_REQUESTS_PER_SECOND" => Some("5".into()),
+            "HOSTED_LICENSE_PROVIDER_IDENTITY_CODE" => Some("publisher".into()),
+            _ => None,
+        })?;
+
+        assert_eq!(config.environment, Some(Environment::Test));
+        assert_eq!(
+            config.identity_cert_file.as_deref(),
+            Some("/etc/basispoort/identity.pem")
+        );
+        assert_eq!(config.timeout_secs, Some(90));
+        assert_eq!(config.rate_limit_requests_per_second, NonZeroU32::new(5));
+        assert_eq!(config.rate_limit_burst, None);
+        assert_eq!(
+            config.hosted_license_provider_identity_code.as_deref(),
+            Some("publisher")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_invalid_override() {
+        let result = Config::default().with_overrides(|key| match key {
+            "CONNECT_TIMEOUT_SECS" => Some("ten".into()),
+            _ => None,
+        });
+
+        assert!(matches!(
+            result.map_err(|error| error.into_kind()),
+            Err(ErrorKind::InvalidConfigValue { .. })
+        ));
+    }
+}
@@ -0,0 +1,124 @@
+//! Persistent collection of permanently failed sync items.
+//!
+//! Pass a [`DeadLetterQueue`] to [`Scheduler::dead_letters`][crate::scheduler::Scheduler::dead_letters] to record
+//! failed tasks in a [`StateStore`], rather than only logging their errors. The recorded items can be inspected
+//! with [`DeadLetterQueue::list`] and retried with
+//! [`Scheduler::retry_dead_letters`][crate::scheduler::Scheduler::retry_dead_letters].
+
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    error::{Error, ErrorKind},
+    state::StateStore,
+    Result,
+};
+
+const KEY_PREFIX: &str = "dead_letter/";
+
+/// A permanently failed item.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct DeadLetter<T> {
+    /// The key identifying the item within its queue.
+    pub key: String,
+    pub item: T,
+    /// The error message of the last failure.
+    pub error: String,
+    pub failed_at: SystemTime,
+}
+
+/// A named collection of [`DeadLetter`]s, recorded in a [`StateStore`].
+///
+/// An item is recorded at most once per queue; a repeated failure replaces the earlier record.
+#[derive(Debug, Clone)]
+pub struct DeadLetterQueue {
+    store: Arc<dyn StateStore>,
+    name: String,
+}
+
+impl DeadLetterQueue {
+    /// Create a queue named `name`. Queues with different names share a store without interfering.
+    pub fn new(store: Arc<dyn StateStore>, name: impl Into<String>) -> Self {
+        Self {
+            store,
+            name: name.into(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Record `item`, identified by `key`, as failed with `error`.
+    pub async fn record<T: Serialize>(&self, key: &str, item: T, error: &Error) -> Result<()> {
+        let dead_letter = DeadLetter {
+            key: key.to_owned(),
+            item,
+            error: error.to_string(),
+            failed_at: SystemTime::now(),
+        };
+        let value = serde_json::to_value(&dead_letter).map_err(ErrorKind::EncodePayload)?;
+
+        self.store.put(&self.record_key(key), value).await
+    }
+
+    /// Remove the record of the item identified by `key`, if any.
+    pub async fn remove(&self, key: &str) -> Result<()> {
+        self.store.delete(&self.record_key(key)).await
+    }
+
+    /// All recorded items, ordered by key.
+    pub async fn list<T: DeserializeOwned>(&self) -> Result<Vec<DeadLetter<T>>> {
+        let mut dead_letters = Vec::new();
+        for key in self.store.keys(&self.record_key("")).await? {
+            // Records may have been removed by a concurrent retry since listing the keys.
+            let Some(value) = self.store.get(&key).await? else {
+                continue;
+            };
+            let dead_letter =
+                DeadLetter::deserialize(value).map_err(|source| ErrorKind::StateRecord {
+                    key: key.clone(),
+                    source,
+                })?;
+            dead_letters.push(dead_letter);
+        }
+
+        Ok(dead_letters)
+    }
+
+    fn record_key(&self, key: &str) -> String {
+        format!("{KEY_PREFIX}{}/{key}", self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::MemoryStateStore;
+
+    #[tokio::test]
+    async fn records_failures_per_queue() -> Result<()> {
+        let store = Arc::new(MemoryStateStore::new());
+        let queue = DeadLetterQueue::new(store.clone(), "students");
+        let other = DeadLetterQueue::new(store, "students-archive");
+        let error = Error::from(ErrorKind::UnknownEnvironment {
+            label: "staging".into(),
+        });
+
+        queue.record("1", 10, &error).await?;
+        queue.record("2", 20, &error).await?;
+        queue.record("1", 11, &error).await?;
+        other.record("3", 30, &error).await?;
+        queue.remove("2").await?;
+
+        let dead_letters = queue.list::<u32>().await?;
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].key, "1");
+        assert_eq!(dead_letters[0].item, 11);
+        assert_eq!(dead_letters[0].error, error.to_string());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+//! Capture of the requests and responses of individual calls, for diagnosing a single failing endpoint.
+//!
+//! [`RestClient::debug_capture`][crate::rest::RestClient::debug_capture] records each request made within its
+//! closure, with the raw response, in a [`DebugCapture`], without enabling global trace logging.
+//! Sensitive headers are redacted, as are personal data fields in JSON bodies.
+
+use std::sync::{Arc, Mutex};
+
+use reqwest::{
+    header::{self, HeaderMap, HeaderValue},
+    Method, Request, Response, StatusCode, Url,
+};
+
+use crate::error::{Error, ErrorKind, ErrorResponse};
+use crate::redact::{redact_personal_data, RedactedBody, REDACTED};
+
+/// Headers which may carry credentials.
+const SENSITIVE_HEADERS: [header::HeaderName; 4] = [
+    header::AUTHORIZATION,
+    header::COOKIE,
+    header::PROXY_AUTHORIZATION,
+    header::SET_COOKIE,
+];
+
+/// The requests and responses captured by [`RestClient::debug_capture`][crate::rest::RestClient::debug_capture],
+/// in the order the requests were sent.
+#[derive(Debug, Clone, Default)]
+pub struct DebugCapture {
+    pub exchanges: Vec<CapturedExchange>,
+}
+
+/// A captured request, with its response if one was received.
+#[derive(Debug, Clone)]
+pub struct CapturedExchange {
+    pub method: Method,
+    pub url: Url,
+    pub request_headers: HeaderMap,
+    pub request_body: Option<String>,
+    pub status: Option<StatusCode>,
+    pub response_headers: HeaderMap,
+    /// The response body, if it was received.
+    pub response_body: Option<String>,
+    /// The error the request failed with, if any.
+    pub error: Option<String>,
+}
+
+/// Index of a response's [`CapturedExchange`], stored in the response extensions.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct CaptureIndex(pub(crate) usize);
+
+/// Records exchanges for a [`DebugCapture`]. Clones share their records.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Recorder(Arc<Mutex<Vec<CapturedExchange>>>);
+
+impl Recorder {
+    /// Record `request`, returning the index of its exchange.
+    pub(crate) fn record_request(&self, request: &Request) -> CaptureIndex {
+        let mut exchanges = self.exchanges();
+        exchanges.push(CapturedExchange {
+            method: request.method().clone(),
+            url: request.url().clone(),
+            request_headers: redact_headers(request.headers()),
+            request_body: request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(redact_body),
+            status: None,
+            response_headers: HeaderMap::new(),
+            response_body: None,
+            error: None,
+        });
+
+        CaptureIndex(exchanges.len() - 1)
+    }
+
+    pub(crate) fn record_response(&self, index: CaptureIndex, response: &Response) {
+        if let Some(exchange) = self.exchanges().get_mut(index.0) {
+            exchange.status = Some(response.status());
+            exchange.response_headers = redact_headers(response.headers());
+        }
+    }
+
+    pub(crate) fn record_response_body(&self, index: CaptureIndex, body: &[u8]) {
+        if let Some(exchange) = self.exchanges().get_mut(index.0) {
+            exchange.response_body = Some(redact_body(body));
+        }
+    }
+
+    /// Record the error of a failed request, including the body of an error response.
+    pub(crate) fn record_error(&self, index: CaptureIndex, error: &Error) {
+        if let Some(exchange) = self.exchanges().get_mut(index.0) {
+            if let ErrorKind::HttpResponse { error_response, .. } = error.kind() {
+                exchange.response_body = Some(match error_response {
+                    ErrorResponse::JSON(value) => redact_personal_data(value.clone()).to_string(),
+                    ErrorResponse::Plain(body) => body.clone(),
+                });
+            }
+            exchange.error = Some(error.to_string());
+        }
+    }
+
+    pub(crate) fn finish(&self) -> DebugCapture {
+        DebugCapture {
+            exchanges: std::mem::take(&mut *self.exchanges()),
+        }
+    }
+
+    fn exchanges(&self) -> std::sync::MutexGuard<'_, Vec<CapturedExchange>> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn redact_headers(headers: &HeaderMap) -> HeaderMap {
+    let mut headers = headers.clone();
+    for name in SENSITIVE_HEADERS {
+        if headers.contains_key(&name) {
+            headers.insert(name, HeaderValue::from_static(REDACTED));
+        }
+    }
+
+    headers
+}
+
+fn redact_body(body: &[u8]) -> String {
+    RedactedBody(body).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_personal_data_and_credentials() {
+        let body = br#"{"gebruikers":[{"instellingId":1,"eckId":"https://id.school/LL_abc"}],"voornaam":null}"#;
+        assert_eq!(
+            redact_body(body),
+            r#"{"gebruikers":[{"eckId":"[redacted]","instellingId":1}],"voornaam":null}"#
+        );
+        assert_eq!(redact_body(b"not json"), "not json");
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::COOKIE, HeaderValue::from_static("session=secret"));
+        headers.insert(header::ACCEPT, HeaderValue::from_static("application/json"));
+        let headers = redact_headers(&headers);
+        assert_eq!(headers[header::COOKIE], REDACTED);
+        assert_eq!(headers[header::ACCEPT], "application/json");
+    }
+}
@@ -0,0 +1,163 @@
+//! Detection of deprecated endpoints, from `Deprecation` and `Sunset` response headers.
+//!
+//! Basispoort may announce the removal of an endpoint in its responses, using the `Deprecation` (RFC 9745)
+//! and `Sunset` (RFC 8594) headers. [`RestClient`][crate::rest::RestClient] emits a `warn` event the first time
+//! an endpoint responds with either, and exposes the parsed [`Deprecation`] in
+//! [`ResponseMetadata::deprecation`][crate::rest::ResponseMetadata::deprecation].
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::header::{self, HeaderMap};
+use tracing::warn;
+
+use crate::Url;
+
+/// A deprecation announced in response headers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deprecation {
+    /// When the endpoint was or will be deprecated, if announced with a date rather than just `true`.
+    pub deprecated_at: Option<SystemTime>,
+    /// When the endpoint will be removed.
+    pub sunset: Option<SystemTime>,
+    /// Documentation of the deprecation, from a `Link` header with relation type `deprecation` or `sunset`.
+    pub link: Option<String>,
+}
+
+impl Deprecation {
+    /// Parse the deprecation headers, if any.
+    ///
+    /// Accepts the `Deprecation` header as a structured field date (`@1688169599`), as an HTTP date, or as `true`,
+    /// as used by earlier drafts of RFC 9745. Unparsable values still mark the endpoint as deprecated.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let value = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::trim)
+        };
+
+        let deprecation = value("deprecation");
+        let sunset = value("sunset");
+        if deprecation.is_none() && sunset.is_none() {
+            return None;
+        }
+
+        Some(Self {
+            deprecated_at: deprecation.and_then(parse_date),
+            sunset: sunset.and_then(parse_date),
+            link: headers
+                .get_all(header::LINK)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .find_map(deprecation_link),
+        })
+    }
+}
+
+/// Parse a structured field date (`@<seconds>`) or an HTTP date.
+fn parse_date(value: &str) -> Option<SystemTime> {
+    match value.strip_prefix('@') {
+        Some(seconds) => Some(UNIX_EPOCH + Duration::from_secs(seconds.parse().ok()?)),
+        None => httpdate::parse_http_date(value).ok(),
+    }
+}
+
+/// The target of a `Link` header value with relation type `deprecation` or `sunset`.
+fn deprecation_link(link: &str) -> Option<String> {
+    let (target, params) = link.trim().strip_prefix('<')?.split_once('>')?;
+    params
+        .split(';')
+        .filter_map(|param| param.trim().strip_prefix("rel="))
+        .flat_map(|rel| rel.trim_matches('"').split_whitespace())
+        .any(|rel| rel.eq_ignore_ascii_case("deprecation") || rel.eq_ignore_ascii_case("sunset"))
+        .then(|| target.to_owned())
+}
+
+/// Remembers the endpoints already warned about, shared by all its clones.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DeprecationWarnings {
+    warned: Arc<Mutex<HashSet<String>>>,
+}
+
+impl DeprecationWarnings {
+    /// Emit a `warn` event for `deprecation`, unless already done for the endpoint of `url`.
+    pub(crate) fn warn_once(&self, url: &Url, deprecation: &Deprecation) {
+        let endpoint = endpoint(url);
+        let first = self
+            .warned
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(endpoint.clone());
+        if !first {
+            return;
+        }
+
+        let date = |time: Option<SystemTime>| time.map(httpdate::fmt_http_date);
+        warn!(
+            endpoint,
+            deprecated_at = date(deprecation.deprecated_at),
+            sunset = date(deprecation.sunset),
+            link = deprecation.link,
+            "The Basispoort API endpoint is deprecated."
+        );
+    }
+}
+
+/// The URL path, with numeric (ID) segments replaced by `{id}`, so an endpoint's responses are counted together.
+pub(crate) fn endpoint(url: &Url) -> String {
+    url.path()
+        .split('/')
+        .map(|segment| match segment.parse::<i64>() {
+            Ok(_) => "{id}",
+            Err(_) => segment,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn parses_deprecation_headers() {
+        assert_eq!(Deprecation::from_headers(&HeaderMap::new()), None);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("deprecation", HeaderValue::from_static("@1688169599"));
+        headers.insert(
+            "sunset",
+            HeaderValue::from_static("Wed, 11 Nov 2026 23:59:59 GMT"),
+        );
+        headers.insert(
+            header::LINK,
+            HeaderValue::from_static(
+                r#"<https://example.com/next>; rel="next", <https://example.com/deprecation>; rel="deprecation""#,
+            ),
+        );
+
+        let deprecation = Deprecation::from_headers(&headers).unwrap();
+        assert_eq!(
+            deprecation.deprecated_at,
+            Some(UNIX_EPOCH + Duration::from_secs(1_688_169_599))
+        );
+        assert_eq!(
+            deprecation.sunset.map(httpdate::fmt_http_date).as_deref(),
+            Some("Wed, 11 Nov 2026 23:59:59 GMT")
+        );
+        assert_eq!(
+            deprecation.link.as_deref(),
+            Some("https://example.com/deprecation")
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert("deprecation", HeaderValue::from_static("true"));
+        let deprecation = Deprecation::from_headers(&headers).unwrap();
+        assert_eq!((deprecation.deprecated_at, deprecation.link), (None, None));
+    }
+}
@@ -0,0 +1,213 @@
+//! Setup diagnostics, explaining why the first requests fail.
+//!
+//! [`check_setup`] validates a [`Config`] step by step: the configuration values, the identity certificate,
+//! the reachability of the environment, and, if an identity code is configured, the permissions of the certificate
+//! for the hosted license provider service. A failing check skips the checks depending on it.
+//! The resulting [`SetupReport`] displays as one pass/fail line per check.
+//!
+//! Run `cargo run --example check_setup --features config -- <config.toml>` for a command-line version.
+
+use std::fmt;
+
+#[cfg(feature = "hosted-license-provider")]
+use reqwest::StatusCode;
+
+#[cfg(not(coverage))]
+use tracing::instrument;
+
+use crate::{
+    config::Config,
+    error::{Error, ErrorKind},
+    rest::RestClient,
+};
+
+/// The outcome of a single check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckOutcome {
+    Passed,
+    /// The check failed, for the given reason.
+    Failed(String),
+    /// The check was not run, for the given reason.
+    Skipped(String),
+}
+
+/// A named check and its outcome.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub outcome: CheckOutcome,
+}
+
+/// The outcomes of all checks, in the order they were run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SetupReport {
+    pub checks: Vec<Check>,
+}
+
+impl SetupReport {
+    /// Whether no check failed. Skipped checks do not count as failed.
+    pub fn is_ok(&self) -> bool {
+        !self
+            .checks
+            .iter()
+            .any(|check| matches!(check.outcome, CheckOutcome::Failed(_)))
+    }
+
+    fn push(&mut self, name: &'static str, outcome: CheckOutcome) {
+        self.checks.push(Check { name, outcome });
+    }
+
+    fn skip_all(&mut self, names: &[&'static str], reason: &str) {
+        for name in names {
+            self.push(name, CheckOutcome::Skipped(reason.into()));
+        }
+    }
+}
+
+impl fmt::Display for SetupReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            match &check.outcome {
+                CheckOutcome::Passed => writeln!(f, "[pass] {}", check.name)?,
+                CheckOutcome::Failed(reason) => writeln!(f, "[FAIL] {}: {reason}", check.name)?,
+                CheckOutcome::Skipped(reason) => writeln!(f, "[skip] {}: {reason}", check.name)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+const CONFIGURATION: &str = "configuration";
+const CERTIFICATE: &str = "identity certificate";
+const REACHABILITY: &str = "environment reachability";
+const PERMISSIONS: &str = "identity code and permissions";
+
+/// Validate `config`, returning a report of all checks.
+#[cfg_attr(not(coverage), instrument)]
+pub async fn check_setup(config: &Config) -> SetupReport {
+    let mut report = SetupReport::default();
+
+    let builder = match config.rest_client_builder() {
+        Ok(builder) => {
+            report.push(CONFIGURATION, CheckOutcome::Passed);
+            builder
+        }
+        Err(error) => {
+            report.push(CONFIGURATION, CheckOutcome::Failed(describe(&error)));
+            report.skip_all(
+                &[CERTIFICATE, REACHABILITY, PERMISSIONS],
+                "invalid configuration",
+            );
+            return report;
+        }
+    };
+
+    let rest_client = match builder.build().await {
+        Ok(rest_client) => {
+            report.push(CERTIFICATE, CheckOutcome::Passed);
+            rest_client
+        }
+        Err(error) => {
+            report.push(CERTIFICATE, CheckOutcome::Failed(describe(&error)));
+            report.skip_all(&[REACHABILITY, PERMISSIONS], "no usable certificate");
+            return report;
+        }
+    };
+
+    // Any HTTP response, even an error status, proves the TLS handshake and the connection succeeded.
+    match rest_client.get_bytes("").await {
+        Err(error) if matches!(error.kind(), ErrorKind::HttpRequest(_)) => {
+            report.push(
+                REACHABILITY,
+                CheckOutcome::Failed(format!(
+                    "{} (is the certificate registered for this environment?)",
+                    describe(&error)
+                )),
+            );
+            report.skip_all(&[PERMISSIONS], "environment not reachable");
+            return report;
+        }
+        _ => report.push(REACHABILITY, CheckOutcome::Passed),
+    }
+
+    report.push(PERMISSIONS, check_permissions(config, &rest_client).await);
+
+    report
+}
+
+#[cfg(feature = "hosted-license-provider")]
+async fn check_permissions(config: &Config, rest_client: &RestClient) -> CheckOutcome {
+    let client = match config.hosted_license_provider_client(rest_client) {
+        Ok(client) => client,
+        Err(_) => return CheckOutcome::Skipped("no identity code configured".into()),
+    };
+
+    match client.get_methods().await {
+        Ok(_) => CheckOutcome::Passed,
+        Err(error) => match error.kind() {
+            ErrorKind::HttpResponse { status, .. }
+                if *status == StatusCode::UNAUTHORIZED || *status == StatusCode::FORBIDDEN =>
+            {
+                CheckOutcome::Failed(format!(
+                    "the certificate is not authorized for identity code '{}' ({status})",
+                    config
+                        .hosted_license_provider_identity_code
+                        .as_deref()
+                        .unwrap_or_default()
+                ))
+            }
+            ErrorKind::HttpResponse { status, .. } if *status == StatusCode::NOT_FOUND => {
+                CheckOutcome::Failed(format!(
+                    "unknown identity code '{}' ({status})",
+                    config
+                        .hosted_license_provider_identity_code
+                        .as_deref()
+                        .unwrap_or_default()
+                ))
+            }
+            _ => CheckOutcome::Failed(describe(&error)),
+        },
+    }
+}
+
+#[cfg(not(feature = "hosted-license-provider"))]
+async fn check_permissions(_config: &Config, _rest_client: &RestClient) -> CheckOutcome {
+    CheckOutcome::Skipped("the `hosted-license-provider` feature is disabled".into())
+}
+
+/// The error message, including its sources.
+fn describe(error: &Error) -> String {
+    let mut description = error.to_string();
+    let mut source = std::error::Error::source(error);
+    while let Some(error) = source {
+        description.push_str(&format!(": {error}"));
+        source = error.source();
+    }
+
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn skips_checks_after_invalid_configuration() {
+        let report = check_setup(&Config::default()).await;
+
+        assert!(!report.is_ok());
+        assert!(matches!(
+            report.checks[0].outcome,
+            CheckOutcome::Failed(ref reason) if reason.contains("identity_cert_file")
+        ));
+        assert_eq!(
+            report.to_string().lines().skip(1).collect::<Vec<_>>(),
+            [
+                "[skip] identity certificate: invalid configuration",
+                "[skip] environment reachability: invalid configuration",
+                "[skip] identity code and permissions: invalid configuration",
+            ]
+        );
+    }
+}
@@ -0,0 +1,89 @@
+//! Dry runs of mutating requests, for validating a sync plan before applying it.
+//!
+//! After [`HostedLicenseProviderClient::dry_run`][crate::hosted_license_provider::HostedLicenseProviderClient::dry_run]
+//! or [`InstitutionsServiceClient::dry_run`][crate::institutions::InstitutionsServiceClient::dry_run], the client
+//! logs each mutating request and records it as a [`PlannedRequest`], instead of sending it. Reads are still sent,
+//! so operations comparing the current with the desired state, such as `sync_method_user_ids`, plan exactly
+//! the requests they would send.
+
+use std::sync::{Arc, Mutex};
+
+use reqwest::Method;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use tracing::info;
+
+use crate::{error::ErrorKind, Result};
+
+/// A mutating request not sent by a dry-running client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedRequest {
+    pub method: Method,
+    /// The path, relative to the base URL.
+    pub path: String,
+    /// The JSON payload, if any.
+    pub payload: Option<Value>,
+}
+
+/// Records the requests of a dry-running client, if enabled. Clones share their records.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DryRun(Option<Arc<Mutex<Vec<PlannedRequest>>>>);
+
+impl DryRun {
+    pub(crate) fn new(enabled: bool) -> Self {
+        Self(enabled.then(Default::default))
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.0.is_some()
+    }
+
+    pub(crate) fn planned_requests(&self) -> Vec<PlannedRequest> {
+        self.0
+            .as_ref()
+            .map(|planned| {
+                planned
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .clone()
+            })
+            .unwrap_or_default()
+    }
+
+    /// If enabled, record the request instead of sending it, returning the response to pretend it received.
+    pub(crate) fn intercept<P: Serialize + ?Sized, T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        payload: Option<&P>,
+    ) -> Option<Result<T>> {
+        let planned = self.0.as_ref()?;
+
+        Some(plan(planned, method, path, payload))
+    }
+}
+
+fn plan<P: Serialize + ?Sized, T: DeserializeOwned>(
+    planned: &Mutex<Vec<PlannedRequest>>,
+    method: Method,
+    path: &str,
+    payload: Option<&P>,
+) -> Result<T> {
+    let payload = payload
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(ErrorKind::EncodePayload)?;
+
+    info!("Dry run: not sending {method} request to '{path}'.");
+    planned
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .push(PlannedRequest {
+            method,
+            path: path.into(),
+            payload,
+        });
+
+    // Pretend to receive an empty response, as the mutating endpoints send, deserializable into `T = ()`.
+    Ok(serde_json::from_value(Value::Null).map_err(ErrorKind::DeserializeResponseBody)?)
+}
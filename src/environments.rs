@@ -0,0 +1,103 @@
+//! Several configured [`RestClient`]s, e.g. for test, acceptance and production, addressed by label.
+//!
+//! [`Environments::in_order`] applies the same change to several environments one after another,
+//! e.g. promoting a catalog change from acceptance to production once it succeeded on acceptance.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+
+use tracing::info;
+#[cfg(not(coverage))]
+use tracing::instrument;
+
+use crate::{error::ErrorKind, rest::RestClient, Result};
+
+/// A registry of [`RestClient`]s by label.
+#[derive(Debug, Clone, Default)]
+pub struct Environments {
+    clients: BTreeMap<String, RestClient>,
+}
+
+impl Environments {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `client` under `label`, e.g. an [`Environment`][crate::rest::Environment] name,
+    /// returning the client previously registered under the same label.
+    pub fn insert(&mut self, label: impl Into<String>, client: RestClient) -> Option<RestClient> {
+        self.clients.insert(label.into(), client)
+    }
+
+    /// Register `client` under `label`.
+    pub fn with(mut self, label: impl Into<String>, client: RestClient) -> Self {
+        self.insert(label, client);
+        self
+    }
+
+    /// The client registered under `label`.
+    ///
+    /// Fails with [`ErrorKind::UnknownEnvironment`] if no client is registered under `label`.
+    pub fn get(&self, label: &str) -> Result<&RestClient> {
+        Ok(self
+            .clients
+            .get(label)
+            .ok_or_else(|| ErrorKind::UnknownEnvironment {
+                label: label.to_owned(),
+            })?)
+    }
+
+    /// All registered labels, in ascending order.
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.clients.keys().map(String::as_str)
+    }
+
+    /// All registered clients with their labels, in ascending order of labels.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &RestClient)> {
+        self.clients
+            .iter()
+            .map(|(label, client)| (label.as_str(), client))
+    }
+
+    /// Call `apply` with the client of each of `labels`, in the given order, collecting the results.
+    ///
+    /// Stops at the first error, so later environments are only changed once earlier ones succeeded.
+    /// All labels are resolved before the first call.
+    #[cfg_attr(not(coverage), instrument(skip(self, apply)))]
+    pub async fn in_order<'e, T, F, Fut>(&'e self, labels: &[&str], mut apply: F) -> Result<Vec<T>>
+    where
+        F: FnMut(&'e RestClient) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let clients = labels
+            .iter()
+            .map(|label| Ok((*label, self.get(label)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut results = Vec::with_capacity(clients.len());
+        for (label, client) in clients {
+            info!("Applying to environment '{label}'.");
+            results.push(apply(client).await?);
+        }
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn rejects_unknown_labels() {
+        let environments = Environments::new();
+
+        let error = environments
+            .in_order(&["acceptance", "production"], |_| async { Ok(()) })
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(error.kind(), ErrorKind::UnknownEnvironment { label } if label == "acceptance")
+        );
+    }
+}
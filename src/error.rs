@@ -1,12 +1,154 @@
-use std::{io, path::PathBuf};
+use std::{
+    backtrace::Backtrace,
+    fmt, io,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
 
+use reqwest::StatusCode;
 use serde::Deserialize;
 use thiserror::Error;
+use tracing_error::SpanTrace;
 use url::Url;
 
+use crate::error_translation::ErrorTranslation;
+use crate::rest::{RateLimitHeaders, RetryAfter};
+
+/// An error, carrying the span trace and backtrace of where it was constructed.
+///
+/// The span trace is only captured if the subscriber includes a [`tracing_error::ErrorLayer`].
+/// The backtrace is only captured if enabled by `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE`,
+/// as described in [`Backtrace::capture`].
+///
+/// The error is boxed internally, keeping [`Result`][crate::Result] as small as a pointer plus its `Ok` value.
+#[derive(Debug)]
+pub struct Error(Box<ErrorInner>);
+
+#[derive(Debug)]
+struct ErrorInner {
+    kind: ErrorKind,
+    span_trace: SpanTrace,
+    backtrace: Backtrace,
+}
+
+impl Error {
+    /// The kind of error.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.0.kind
+    }
+
+    pub(crate) fn kind_mut(&mut self) -> &mut ErrorKind {
+        &mut self.0.kind
+    }
+
+    /// Consume the error, returning its kind.
+    pub fn into_kind(self) -> ErrorKind {
+        self.0.kind
+    }
+
+    /// The tracing spans entered when the error was constructed.
+    pub fn span_trace(&self) -> &SpanTrace {
+        &self.0.span_trace
+    }
+
+    /// The backtrace of the error's construction.
+    pub fn backtrace(&self) -> &Backtrace {
+        &self.0.backtrace
+    }
+
+    /// The HTTP status of the server's error response, if the server responded with one.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self.kind() {
+            ErrorKind::HttpResponse { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// Whether the error is caused by an outage, rather than the server rejecting the request,
+    /// so the request may succeed if retried later: connecting or sending the request failed or timed out,
+    /// or the server responded with `429 Too Many Requests` or a `5xx` server error.
+    ///
+    /// Requests which could not be built, e.g. due to an invalid header, are not retryable.
+    pub fn is_retryable(&self) -> bool {
+        match self.kind() {
+            ErrorKind::HttpRequest(error) => is_transient(error),
+            ErrorKind::HttpResponse { status, .. } => {
+                status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+            }
+            #[cfg(feature = "chaos")]
+            ErrorKind::InjectedTimeout { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the server responded with `404 Not Found`, e.g. for an unknown method, product or institution.
+    pub fn is_not_found(&self) -> bool {
+        self.status() == Some(StatusCode::NOT_FOUND)
+    }
+
+    /// Whether the server responded with `409 Conflict`, e.g. when creating a method or product which exists.
+    pub fn is_conflict(&self) -> bool {
+        self.status() == Some(StatusCode::CONFLICT)
+    }
+
+    /// Whether the server responded with `401 Unauthorized` or `403 Forbidden`,
+    /// rejecting the client identity or denying it access, e.g. to an institution without synchronization permission.
+    pub fn is_unauthorized(&self) -> bool {
+        matches!(
+            self.status(),
+            Some(StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)
+        )
+    }
+}
+
+/// Whether sending a request failed transiently: connecting or sending it failed or timed out.
+fn is_transient(error: &reqwest::Error) -> bool {
+    // Browsers do not report connect errors separately.
+    #[cfg(not(target_arch = "wasm32"))]
+    if error.is_connect() {
+        return true;
+    }
+
+    !error.is_builder() && (error.is_timeout() || error.is_request())
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0.kind, f)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.kind.source()
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Self(Box::new(ErrorInner {
+            kind,
+            span_trace: SpanTrace::capture(),
+            backtrace: Backtrace::capture(),
+        }))
+    }
+}
+
+/// Deprecated: kept for code migrating from `Result<T, Box<Error>>`, and to be removed in the next breaking release.
+/// [`Result`][crate::Result] no longer boxes [`Error`], which boxes its kind internally; convert into `Error` instead.
+///
+/// Rust does not support `#[deprecated]` on trait implementations, so using it does not warn.
+impl From<ErrorKind> for Box<Error> {
+    fn from(kind: ErrorKind) -> Self {
+        Box::new(kind.into())
+    }
+}
+
+// `Error` boxes its kind, so a large variant does not bloat results.
+#[allow(clippy::large_enum_variant)]
 #[non_exhaustive]
 #[derive(Error, Debug)]
-pub enum Error {
+pub enum ErrorKind {
     /// Failed to open identity certificate file at the specified path.
     #[error("failed to open identity certificate file at '{path}'")]
     OpenIdentityCertFile {
@@ -31,6 +173,14 @@ pub enum Error {
         source: reqwest::Error,
     },
 
+    /// Failed parsing an identity certificate held in memory.
+    #[error("failed parsing identity certificate")]
+    ParseIdentity(#[source] reqwest::Error),
+
+    /// Failed to fetch the client identity from a custom [`IdentityProvider`][crate::identity::IdentityProvider].
+    #[error("failed to fetch the client identity")]
+    FetchIdentity(#[source] Box<dyn std::error::Error + Send + Sync>),
+
     /// Failed building request client.
     #[error("failed building request client")]
     BuildRequestClient(#[source] reqwest::Error),
@@ -59,6 +209,28 @@ pub enum Error {
         source: io::Error,
     },
 
+    /// Failed to start the timer thread, used without a runtime feature.
+    #[cfg(not(any(feature = "tokio", feature = "async-io")))]
+    #[error("failed to start the timer thread")]
+    StartTimer(#[source] io::Error),
+
+    /// Failed to read icon from a reader.
+    #[error("failed to read icon")]
+    ReadIcon(#[source] io::Error),
+
+    /// Failed to download an icon from the specified URL.
+    #[error("failed to download icon from '{url}'")]
+    DownloadIcon {
+        url: Url,
+        #[source]
+        source: reqwest::Error,
+    },
+
+    /// Failed to decode or re-encode an icon while downscaling it.
+    #[cfg(feature = "image")]
+    #[error("failed to downscale icon")]
+    DownscaleIcon(#[source] image::ImageError),
+
     /// Failed to encode payload.
     #[error("failed to encode payload")]
     // TODO: Useful information to pass here?
@@ -68,12 +240,38 @@ pub enum Error {
     #[error("HTTP request error")]
     HttpRequest(#[source] reqwest::Error),
 
+    /// A timeout injected by [`FaultInjection`][crate::chaos::FaultInjection].
+    #[cfg(feature = "chaos")]
+    #[error("injected timeout for '{url}'")]
+    InjectedTimeout { url: Url },
+
     /// HTTP response error.
-    #[error("HTTP {status} error response for '{url}'")]
+    ///
+    /// If the server rejected the request payload with `400 Bad Request` or `422 Unprocessable Entity`,
+    /// `request_summary` holds a redacted summary of the payload, listing only entity IDs and list lengths.
+    ///
+    /// `retry_after` and `rate_limit` hold the server's requested back-off and reported rate limit state, if any.
+    ///
+    /// `translation` holds the English translation of the error response, if the client was configured with
+    /// [`ErrorTranslations`][crate::error_translation::ErrorTranslations] knowing it.
+    ///
+    /// `correlation_id` holds the ID sent with the request, see
+    /// [`RestClientBuilder::correlation_id_header`][crate::rest::RestClientBuilder::correlation_id_header].
+    #[error(
+        "HTTP {status} error response for '{url}'{}{}{}",
+        request_summary.as_ref().map(|summary| format!(" to request {summary}")).unwrap_or_default(),
+        correlation_id.as_ref().map(|correlation_id| format!(" (correlation ID {correlation_id})")).unwrap_or_default(),
+        translation.as_ref().map(|translation| format!(": {translation}")).unwrap_or_default()
+    )]
     HttpResponse {
         url: Url,
         status: reqwest::StatusCode,
         error_response: ErrorResponse,
+        request_summary: Option<String>,
+        translation: Option<ErrorTranslation>,
+        retry_after: Option<RetryAfter>,
+        rate_limit: Option<RateLimitHeaders>,
+        correlation_id: Option<String>,
         #[source]
         source: reqwest::Error,
     },
@@ -86,6 +284,130 @@ pub enum Error {
     #[error("failed decoding the server's response body")]
     DeserializeResponseBody(#[source] serde_json::Error),
 
+    /// Failed to read configuration file at the specified path.
+    #[cfg(feature = "config")]
+    #[error("failed to read configuration file at '{path}'")]
+    ReadConfigFile {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    /// Failed parsing configuration file at the specified path.
+    #[cfg(feature = "config")]
+    #[error("failed parsing configuration file at '{path}'")]
+    ParseConfigFile {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+
+    /// Failed parsing configuration.
+    #[cfg(feature = "config")]
+    #[error("failed parsing configuration")]
+    ParseConfig(#[source] toml::de::Error),
+
+    /// Failed parsing a LAS roster export.
+    #[cfg(feature = "las-import")]
+    #[error("failed parsing LAS roster export")]
+    ParseLasExport(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    /// Failed to export Arrow record batches or Parquet files.
+    #[cfg(feature = "arrow")]
+    #[error("failed to export Arrow data")]
+    ExportArrow(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    /// The environment serves none of the supported versions of the service's API.
+    #[error("the environment serves no supported {service} API version")]
+    NoSupportedApiVersion { service: &'static str },
+
+    /// A configuration value is invalid.
+    #[error("invalid value '{value}' for configuration key '{key}'")]
+    InvalidConfigValue { key: String, value: String },
+
+    /// A required configuration value is missing.
+    #[error("missing value for configuration key '{key}'")]
+    MissingConfigValue { key: String },
+
+    /// Another process holds the lease on the scope to be synced.
+    #[error(
+        "sync of {scope} is locked by '{holder}', lease expires in {:?}",
+        expires_at.duration_since(SystemTime::now()).unwrap_or(Duration::ZERO)
+    )]
+    SyncLocked {
+        scope: String,
+        holder: String,
+        expires_at: SystemTime,
+    },
+
+    /// The lease expired and was taken over by another holder.
+    #[error("lease '{key}' was lost to another holder")]
+    LeaseLost { key: String },
+
+    /// A state record could not be encoded or decoded.
+    #[error("failed to encode or decode state record '{key}'")]
+    StateRecord {
+        key: String,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    /// A custom [`StateStore`][crate::state::StateStore] failed.
+    #[error("state store error")]
+    StateStore(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    /// No client is registered under the label in [`Environments`][crate::environments::Environments].
+    #[error("no client registered for environment '{label}'")]
+    UnknownEnvironment { label: String },
+
+    /// A method ID, product ID or identity code would not form a valid URL path segment.
+    #[cfg(feature = "hosted-license-provider")]
+    #[error("invalid ID")]
+    InvalidId(#[source] crate::hosted_license_provider::ParseIdError),
+
+    /// User IDs or user chain IDs are duplicated. Lists the indices of the duplicates.
+    #[error("invalid user IDs: user IDs duplicated at indices {duplicated:?}, user chain IDs duplicated at indices {duplicated_chain_ids:?}")]
+    InvalidUserIds {
+        duplicated: Vec<usize>,
+        duplicated_chain_ids: Vec<usize>,
+    },
+
+    /// A method or product failed client-side validation. Lists each invalid field.
+    #[cfg(feature = "hosted-license-provider")]
+    #[error(
+        "validation failed: {}",
+        errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+    )]
+    Validation {
+        errors: Vec<crate::hosted_license_provider::FieldError>,
+    },
+
+    /// A mutation would have sent an empty list, which is almost always a caller bug.
+    #[error("refusing to send an empty payload to '{path}'")]
+    EmptyPayload { path: String },
+
+    /// A request exceeds a configured [`Limits`][crate::hosted_license_provider::Limits] value.
+    ///
+    /// For icons read from a stream, `actual` is the number of bytes read before giving up.
+    #[error("{limit} exceeded: {actual} is more than {max}")]
+    LimitExceeded {
+        limit: &'static str,
+        max: usize,
+        actual: usize,
+    },
+
+    /// A destructive operation in the production environment, without opting in.
+    #[error("refusing destructive operation '{operation}' in production without opting in")]
+    DestructiveOperationBlocked { operation: &'static str },
+
+    /// A request was made after [`RestClient::shutdown`][crate::rest::RestClient::shutdown] began.
+    #[error("the client is shutting down and accepts no new requests")]
+    ShuttingDown,
+
+    /// The shard index is not below the number of shards.
+    #[error("shard index {index} is out of range for {count} shards")]
+    InvalidShard { index: u32, count: u32 },
+
     /// Failed to url-encode the search predicate.
     #[error("failed to url-encode the search predicate")]
     SerializeSearchPredicate(#[source] serde_urlencoded::ser::Error),
@@ -96,3 +418,66 @@ pub enum ErrorResponse {
     JSON(serde_json::Value),
     Plain(String),
 }
+
+#[cfg(test)]
+mod tests {
+    use tracing::info_span;
+    use tracing_error::ErrorLayer;
+    use tracing_subscriber::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn captures_span_trace() {
+        let subscriber = tracing_subscriber::registry().with(ErrorLayer::default());
+
+        tracing::subscriber::with_default(subscriber, || {
+            let error: Error = info_span!("sync_institution").in_scope(|| {
+                ErrorKind::LeaseLost {
+                    key: "lease/institution/1".into(),
+                }
+                .into()
+            });
+
+            assert!(error.span_trace().to_string().contains("sync_institution"));
+            assert_eq!(
+                error.to_string(),
+                "lease 'lease/institution/1' was lost to another holder"
+            );
+        });
+    }
+
+    #[test]
+    fn classifies_errors() {
+        let error: Error = ErrorKind::LeaseLost {
+            key: "lease".into(),
+        }
+        .into();
+        assert_eq!(error.status(), None);
+        assert!(!error.is_retryable());
+        assert!(!error.is_not_found());
+    }
+
+    #[tokio::test]
+    async fn retries_connect_errors_but_not_build_errors() {
+        let client = reqwest::Client::new();
+
+        let build_error = client
+            .get("http://127.0.0.1:1/")
+            .header("invalid header", "value")
+            .build()
+            .unwrap_err();
+        assert!(!Error::from(ErrorKind::HttpRequest(build_error)).is_retryable());
+
+        let connect_error = client.get("http://127.0.0.1:1/").send().await.unwrap_err();
+        assert!(Error::from(ErrorKind::HttpRequest(connect_error)).is_retryable());
+    }
+
+    #[test]
+    fn is_pointer_sized() {
+        assert_eq!(
+            std::mem::size_of::<crate::Result<()>>(),
+            std::mem::size_of::<usize>()
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! English translations of Basispoort's Dutch error responses, for triage by international teams.
+//!
+//! Register the known error codes and messages in an [`ErrorTranslations`] table, and pass it to
+//! [`RestClientBuilder::error_translations`][crate::rest::RestClientBuilder::error_translations].
+//! HTTP response errors then carry the matching [`ErrorTranslation`] in
+//! [`ErrorKind::HttpResponse`][crate::error::ErrorKind::HttpResponse], and include it in their message.
+//!
+//! ```
+//! use basispoort_sync_client::error_translation::{ErrorClass, ErrorTranslations};
+//!
+//! let translations = ErrorTranslations::new()
+//!     .with_code("METHODE_NIET_GEVONDEN", "Method not found", ErrorClass::NotFound)
+//!     .with_message("ongeldige gebruiker", "Invalid user ID", ErrorClass::Validation);
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::ErrorResponse;
+
+/// Top-level fields of JSON error responses which may hold an error code.
+const CODE_FIELDS: [&str; 3] = ["code", "foutcode", "errorCode"];
+
+/// Top-level fields of JSON error responses which may hold an error message.
+const MESSAGE_FIELDS: [&str; 4] = ["melding", "foutmelding", "message", "error"];
+
+/// A coarse classification of errors, for routing them to whoever can fix them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    /// The request payload or parameters are invalid.
+    Validation,
+    /// The addressed entity does not exist.
+    NotFound,
+    /// The request conflicts with the current state, e.g. an entity already exists.
+    Conflict,
+    /// The identity certificate or identity code is not authorized.
+    Authorization,
+    /// Basispoort is unavailable or failed internally.
+    Unavailable,
+    Other,
+}
+
+/// The English translation and classification of a Basispoort error.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorTranslation {
+    pub english: String,
+    pub class: ErrorClass,
+}
+
+impl fmt::Display for ErrorTranslation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:?})", self.english, self.class)
+    }
+}
+
+/// A table of known error codes and messages with their translations.
+#[derive(Debug, Clone, Default)]
+pub struct ErrorTranslations {
+    codes: HashMap<String, ErrorTranslation>,
+    /// Lower-cased message fragments, matched in order of registration.
+    messages: Vec<(String, ErrorTranslation)>,
+}
+
+impl ErrorTranslations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Translate error responses carrying the error code `code`.
+    pub fn with_code(
+        mut self,
+        code: impl Into<String>,
+        english: impl Into<String>,
+        class: ErrorClass,
+    ) -> Self {
+        self.codes.insert(
+            code.into(),
+            ErrorTranslation {
+                english: english.into(),
+                class,
+            },
+        );
+        self
+    }
+
+    /// Translate error responses whose message contains `fragment`, ignoring case.
+    ///
+    /// Error codes take precedence over message fragments.
+    pub fn with_message(
+        mut self,
+        fragment: impl AsRef<str>,
+        english: impl Into<String>,
+        class: ErrorClass,
+    ) -> Self {
+        self.messages.push((
+            fragment.as_ref().to_lowercase(),
+            ErrorTranslation {
+                english: english.into(),
+                class,
+            },
+        ));
+        self
+    }
+
+    /// The translation of `error_response`, if its code or message is known.
+    pub fn translate(&self, error_response: &ErrorResponse) -> Option<&ErrorTranslation> {
+        let message = match error_response {
+            ErrorResponse::JSON(value) => {
+                let by_code = CODE_FIELDS
+                    .iter()
+                    .filter_map(|field| match value.get(field)? {
+                        Value::String(code) => Some(code.clone()),
+                        Value::Number(code) => Some(code.to_string()),
+                        _ => None,
+                    })
+                    .find_map(|code| self.codes.get(&code));
+                if by_code.is_some() {
+                    return by_code;
+                }
+
+                MESSAGE_FIELDS
+                    .iter()
+                    .find_map(|field| value.get(field)?.as_str())?
+                    .to_lowercase()
+            }
+            ErrorResponse::Plain(body) => body.to_lowercase(),
+        };
+
+        self.messages
+            .iter()
+            .find(|(fragment, _)| message.contains(fragment.as_str()))
+            .map(|(_, translation)| translation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn translates_codes_before_messages() {
+        let translations = ErrorTranslations::new()
+            .with_code("404-1", "Method not found", ErrorClass::NotFound)
+            .with_message("Ongeldige", "Invalid request", ErrorClass::Validation);
+
+        let english = |error_response: ErrorResponse| {
+            translations
+                .translate(&error_response)
+                .map(|translation| translation.english.clone())
+        };
+
+        assert_eq!(
+            english(ErrorResponse::JSON(
+                json!({"code": "404-1", "melding": "Ongeldige methode"})
+            ))
+            .as_deref(),
+            Some("Method not found")
+        );
+        assert_eq!(
+            english(ErrorResponse::JSON(json!({"melding": "ongeldige methode"}))).as_deref(),
+            Some("Invalid request")
+        );
+        assert_eq!(
+            english(ErrorResponse::Plain("Ongeldige gebruiker".into())).as_deref(),
+            Some("Invalid request")
+        );
+        assert_eq!(english(ErrorResponse::Plain("Onbekend".into())), None);
+    }
+}
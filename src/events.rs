@@ -0,0 +1,170 @@
+//! Structured events, for log pipelines ingesting JSON directly rather than through a `tracing` subscriber.
+//!
+//! Pass an [`EventSink`] to [`RestClientBuilder::event_sink`][crate::rest::RestClientBuilder::event_sink]
+//! and [`Scheduler::event_sink`][crate::scheduler::Scheduler::event_sink] to receive an [`Event`] for each request,
+//! each finished sync task and each error. [`NdjsonSink`] writes the events as newline-delimited JSON,
+//! e.g. to standard output or a log file:
+//!
+//! ```json
+//! {"timestamp_ms":1714564800000,"event":"request_finished","method":"GET","url":"https://…","status":200,"duration_ms":84}
+//! ```
+
+use std::fmt::{self, Debug};
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tracing::warn;
+
+use crate::BasispoortId;
+
+/// A structured event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// A request is about to be sent.
+    RequestStarted { method: String, url: String },
+    /// A request has finished. `status` is absent if no response was received.
+    RequestFinished {
+        method: String,
+        url: String,
+        status: Option<u16>,
+        duration_ms: u64,
+    },
+    /// A [`Scheduler`][crate::scheduler::Scheduler] task has finished. `error` is present if it failed.
+    SyncTaskFinished {
+        institution_id: BasispoortId,
+        worker: usize,
+        duration_ms: u64,
+        error: Option<String>,
+    },
+    /// A request failed.
+    Error { url: String, message: String },
+}
+
+impl Event {
+    pub(crate) fn duration_ms(duration: Duration) -> u64 {
+        duration.as_millis().try_into().unwrap_or(u64::MAX)
+    }
+}
+
+/// A receiver of [`Event`]s.
+///
+/// Events are emitted synchronously, so implementations should not block for long.
+pub trait EventSink: Debug + Send + Sync {
+    fn emit(&self, event: &Event);
+
+    /// Write out buffered events, e.g. on [`RestClient::shutdown`][crate::rest::RestClient::shutdown].
+    fn flush(&self) {}
+}
+
+/// An [`Event`] with the time it was emitted, as written by [`NdjsonSink`].
+#[derive(Serialize)]
+struct EventRecord<'a> {
+    timestamp_ms: u64,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+/// Writes each [`Event`] as a line of JSON to a writer.
+pub struct NdjsonSink {
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl NdjsonSink {
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            writer: Mutex::new(Box::new(writer)),
+        }
+    }
+}
+
+impl EventSink for NdjsonSink {
+    fn emit(&self, event: &Event) {
+        let record = EventRecord {
+            timestamp_ms: Event::duration_ms(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default(),
+            ),
+            event,
+        };
+
+        let mut writer = self
+            .writer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        // A failing log sink must not fail the sync.
+        let written = serde_json::to_writer(&mut *writer, &record)
+            .map_err(std::io::Error::from)
+            .and_then(|()| writer.write_all(b"\n"))
+            .and_then(|()| writer.flush());
+        if let Err(error) = written {
+            warn!("Failed to write event: {error}");
+        }
+    }
+
+    fn flush(&self) {
+        let flushed = self
+            .writer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .flush();
+        if let Err(error) = flushed {
+            warn!("Failed to flush events: {error}");
+        }
+    }
+}
+
+impl Debug for NdjsonSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NdjsonSink").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn writes_one_json_object_per_line() {
+        let buffer = SharedBuffer::default();
+        let sink = NdjsonSink::new(buffer.clone());
+
+        sink.emit(&Event::RequestStarted {
+            method: "GET".into(),
+            url: "https://example.com/".into(),
+        });
+        sink.emit(&Event::Error {
+            url: "https://example.com/".into(),
+            message: "HTTP request error".into(),
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["event"], "request_started");
+        assert_eq!(lines[0]["method"], "GET");
+        assert!(lines[0]["timestamp_ms"].is_u64());
+        assert_eq!(lines[1]["event"], "error");
+    }
+}
@@ -0,0 +1,165 @@
+//! Alternative spellings of response fields, accepted when deserializing.
+//!
+//! Basispoort is not consistent in the capitalization of compound Dutch field names: e.g. institution details contain
+//! `huisnummertoevoeging`, but search results `huisnummerToevoeging`. So that such minor naming differences
+//! do not break deserialization, every model field named after a spelling in [`FIELD_ALIASES`] accepts
+//! all spellings of its group, through `#[serde(alias)]` attributes.
+//!
+//! This table is the single reference for those attributes. When Basispoort starts using another spelling,
+//! add it here; the unit tests then point out each model field missing the alias.
+
+/// Groups of equivalent field name spellings.
+pub const FIELD_ALIASES: &[&[&str]] = &[
+    &["bestuurscode", "bestuursCode"],
+    &["brincode", "brinCode"],
+    &["eckid", "eckId"],
+    &["emailadres", "emailAdres"],
+    &["gefuseerdNaar", "gefuseerdnaar"],
+    &["huisnummertoevoeging", "huisnummerToevoeging"],
+    &["instellingId", "instellingid"],
+    &["lasKey", "laskey"],
+];
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use serde::de::DeserializeOwned;
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    /// Rename all object keys `from` in `value` to `to`.
+    fn rename_keys(value: &Value, from: &str, to: &str) -> Value {
+        match value {
+            Value::Object(object) => Value::Object(
+                object
+                    .iter()
+                    .map(|(key, value)| {
+                        let key = if key == from { to } else { key };
+                        (key.to_owned(), rename_keys(value, from, to))
+                    })
+                    .collect(),
+            ),
+            Value::Array(values) => Value::Array(
+                values
+                    .iter()
+                    .map(|value| rename_keys(value, from, to))
+                    .collect(),
+            ),
+            value => value.clone(),
+        }
+    }
+
+    /// Debug output of `json` deserialized as `T`.
+    fn owned<T: DeserializeOwned + Debug>(json: &str) -> Result<String, String> {
+        serde_json::from_str::<T>(json)
+            .map(|value| format!("{value:?}"))
+            .map_err(|error| error.to_string())
+    }
+
+    /// Check that `deserialize` deserializes `sample` identically with every alias of its field names.
+    fn assert_accepts_aliases(sample: Value, deserialize: impl Fn(&str) -> Result<String, String>) {
+        let expected = deserialize(&sample.to_string()).unwrap();
+
+        for spellings in FIELD_ALIASES {
+            for from in *spellings {
+                if rename_keys(&sample, from, "") == sample {
+                    continue;
+                }
+                for to in spellings.iter().filter(|to| *to != from) {
+                    let aliased = rename_keys(&sample, from, to);
+                    assert_eq!(
+                        deserialize(&aliased.to_string()).as_ref(),
+                        Ok(&expected),
+                        "'{to}' is not accepted for '{from}' in {sample}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "institutions")]
+    #[test]
+    fn institutions_models_accept_aliases() {
+        use crate::institutions::{
+            InstitutionDetails, InstitutionOverview, InstitutionSearchResult,
+        };
+
+        let metadata = json!({
+            "mutationTimestamp": "2024-05-01T00:00:00Z",
+            "generationTimestamp": "2024-05-01T00:00:00Z"
+        });
+        let personal_data = json!({"voornaam": "Anna", "achternaam": "Jansen"});
+
+        assert_accepts_aliases(
+            json!({
+                "groepen": [{"lasKey": "G3A", "naam": "Groep 3a"}],
+                "subgroepen": [],
+                "leerlingen": [{
+                    "id": 1, "eckid": "https://ketenid.nl/201703/abc", "lasKey": "L1",
+                    "persoonsgegevens": personal_data, "groep": "G3A", "subgroepen": []
+                }],
+                "medewerkers": [{
+                    "id": 2, "eckid": "https://ketenid.nl/201703/def", "lasKey": "M1",
+                    "persoonsgegevens": personal_data, "emailadres": "anna@school.example",
+                    "rollen": ["Leerkracht"], "groepen": ["G3A"], "subgroepen": []
+                }],
+                "actief": false,
+                "gefuseerdNaar": 3,
+                "metaResult": metadata
+            }),
+            owned::<InstitutionOverview>,
+        );
+        assert_accepts_aliases(
+            json!({
+                "huisnummertoevoeging": "a",
+                "brincode": "00AA",
+                "bestuurscode": "12345",
+                "actief": false,
+                "gefuseerdNaar": 3,
+                "metaResult": metadata
+            }),
+            owned::<InstitutionDetails>,
+        );
+        assert_accepts_aliases(
+            json!({
+                "id": 1,
+                "naam": "School",
+                "brincode": "00AA00",
+                "huisnummerToevoeging": "a",
+                "emailadres": "info@school.example",
+                "actief": true,
+                "bestuurscode": "12345"
+            }),
+            owned::<InstitutionSearchResult>,
+        );
+        assert_accepts_aliases(
+            json!({
+                "leerlingen": [{
+                    "id": 1, "eckid": "https://ketenid.nl/201703/abc", "lasKey": "L1",
+                    "persoonsgegevens": personal_data, "subgroepen": []
+                }],
+                "metaResult": metadata
+            }),
+            |json| {
+                crate::institutions::borrowed::InstitutionStudents::from_slice(json.as_bytes())
+                    .map(|students| format!("{students:?}"))
+                    .map_err(|error| error.to_string())
+            },
+        );
+    }
+
+    #[cfg(feature = "hosted-license-provider")]
+    #[test]
+    fn hosted_license_provider_models_accept_aliases() {
+        use crate::hosted_license_provider::UserChainIdList;
+
+        assert_accepts_aliases(
+            json!({
+                "gebruikers": [{"instellingId": 1, "eckId": "https://ketenid.nl/201703/abc"}]
+            }),
+            owned::<UserChainIdList>,
+        );
+    }
+}
@@ -0,0 +1,354 @@
+//! Synthetic institution rosters, for benchmarks and downstream unit tests.
+//!
+//! A [`RosterGenerator`] produces a realistic [`InstitutionOverview`]: year groups 1 to 8 with one or more groups each,
+//! sub groups, students with Dutch names and chain IDs, and a teacher per group. The same seed always produces
+//! the same roster.
+//!
+//! ```
+//! use basispoort_sync_client::fixtures::RosterGenerator;
+//!
+//! let overview = RosterGenerator::new(42)
+//!     .groups_per_year_group(2)
+//!     .students_per_group(25)
+//!     .generate();
+//! assert_eq!(overview.students.len(), 8 * 2 * 25);
+//! ```
+
+use std::collections::HashSet;
+
+use crate::institutions::{
+    date::Timestamp, Group, InstitutionOverview, PersonalData, ResultMetadata, StaffMember,
+    StaffMemberRole, Student,
+};
+use crate::BasispoortId;
+
+const FIRST_NAMES: [&str; 24] = [
+    "Anna", "Daan", "Emma", "Bram", "Julia", "Finn", "Sophie", "Luuk", "Tess", "Sem", "Lotte",
+    "Noah", "Fleur", "Jesse", "Sanne", "Thijs", "Noor", "Milan", "Evi", "Lars", "Isa", "Ruben",
+    "Femke", "Mohammed",
+];
+
+const LAST_NAMES: [&str; 24] = [
+    "Jansen", "Visser", "Smit", "Meijer", "Mulder", "Bos", "Vos", "Peters", "Hendriks", "Dekker",
+    "Brouwer", "Janssen", "Bakker", "Kok", "Willems", "Koster", "Huisman", "Schouten", "Yilmaz",
+    "Postma", "Kuipers", "Veenstra", "Bosman", "Prins",
+];
+
+/// Name prefixes ("tussenvoegsels"), most last names having none.
+const PREFIXES: [Option<&str>; 8] = [
+    None,
+    None,
+    None,
+    None,
+    Some("de"),
+    Some("van"),
+    Some("van der"),
+    Some("van den"),
+];
+
+const SUB_GROUP_SUBJECTS: [&str; 4] = ["Reken", "Taal", "Lees", "Spelling"];
+
+/// Generates synthetic [`InstitutionOverview`]s of configurable size.
+#[derive(Debug, Clone)]
+pub struct RosterGenerator {
+    seed: u64,
+    groups_per_year_group: usize,
+    students_per_group: usize,
+    sub_groups: usize,
+    chain_ids: bool,
+    first_id: u32,
+    generated_at: Option<Timestamp>,
+}
+
+impl RosterGenerator {
+    /// A generator of one group of 24 students per year group, and two sub groups, seeded with `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            groups_per_year_group: 1,
+            students_per_group: 24,
+            sub_groups: 2,
+            chain_ids: true,
+            first_id: 100_000,
+            generated_at: None,
+        }
+    }
+
+    /// Sets the number of groups per year group, e.g. groups 3a and 3b.
+    pub fn groups_per_year_group(mut self, groups_per_year_group: usize) -> Self {
+        self.groups_per_year_group = groups_per_year_group;
+        self
+    }
+
+    pub fn students_per_group(mut self, students_per_group: usize) -> Self {
+        self.students_per_group = students_per_group;
+        self
+    }
+
+    /// Sets the number of sub groups, e.g. reading groups, each containing students from all groups.
+    pub fn sub_groups(mut self, sub_groups: usize) -> Self {
+        self.sub_groups = sub_groups;
+        self
+    }
+
+    /// Sets whether students and staff have chain IDs. Defaults to `true`.
+    pub fn chain_ids(mut self, chain_ids: bool) -> Self {
+        self.chain_ids = chain_ids;
+        self
+    }
+
+    /// Sets the first user ID. User IDs are assigned sequentially, students first.
+    pub fn first_id(mut self, first_id: u32) -> Self {
+        self.first_id = first_id;
+        self
+    }
+
+    /// Sets the timestamps of the [`ResultMetadata`]. Defaults to 2024-08-01 00:00 UTC.
+    pub fn generated_at(mut self, generated_at: Timestamp) -> Self {
+        self.generated_at = Some(generated_at);
+        self
+    }
+
+    pub fn generate(&self) -> InstitutionOverview {
+        let mut rng = fastrand::Rng::with_seed(self.seed);
+        let mut next_id = self.first_id;
+        let mut next_user_id = || {
+            let id = BasispoortId::from_u32(next_id);
+            next_id += 1;
+            id
+        };
+
+        let groups: Vec<Group> = (1..=8)
+            .flat_map(|year_group| {
+                (0..self.groups_per_year_group).map(move |index| {
+                    let suffix = group_suffix(index, self.groups_per_year_group);
+                    Group {
+                        administrative_key: Some(format!("G{year_group}{suffix}")),
+                        name: Some(format!("Groep {year_group}{}", suffix.to_lowercase())),
+                        year_group: Some(year_group.to_string()),
+                        description: None,
+                    }
+                })
+            })
+            .collect();
+
+        let sub_groups: Vec<Group> = (0..self.sub_groups)
+            .map(|index| Group {
+                administrative_key: Some(format!("SG{}", index + 1)),
+                name: Some(format!(
+                    "{}groep {}",
+                    SUB_GROUP_SUBJECTS[index % SUB_GROUP_SUBJECTS.len()],
+                    index / SUB_GROUP_SUBJECTS.len() + 1
+                )),
+                year_group: None,
+                description: None,
+            })
+            .collect();
+
+        let mut students = Vec::with_capacity(groups.len() * self.students_per_group);
+        for group in &groups {
+            for _ in 0..self.students_per_group {
+                let sub_group = (!sub_groups.is_empty())
+                    .then(|| {
+                        sub_groups[rng.usize(..sub_groups.len())]
+                            .administrative_key
+                            .clone()
+                    })
+                    .flatten();
+                let id = next_user_id();
+                students.push(Student {
+                    id,
+                    chain_id: self.chain_id(&mut rng),
+                    administrative_key: Some(format!("LL{}", id.get())),
+                    personal_data: personal_data(&mut rng),
+                    year_group: group.year_group.clone(),
+                    group: group.administrative_key.clone(),
+                    sub_groups: sub_group.into_iter().collect(),
+                });
+            }
+        }
+
+        let mut staff: Vec<StaffMember> = groups
+            .iter()
+            .map(|group| {
+                let id = next_user_id();
+                let personal_data = personal_data(&mut rng);
+                StaffMember {
+                    id,
+                    chain_id: self.chain_id(&mut rng),
+                    administrative_key: Some(format!("MW{}", id.get())),
+                    email: Some(email(&personal_data)),
+                    personal_data,
+                    end_date: None,
+                    roles: HashSet::from([StaffMemberRole::Teacher]),
+                    groups: group.administrative_key.clone().into_iter().collect(),
+                    sub_groups: Vec::new(),
+                }
+            })
+            .collect();
+
+        let id = next_user_id();
+        let personal_data = personal_data(&mut rng);
+        staff.push(StaffMember {
+            id,
+            chain_id: self.chain_id(&mut rng),
+            administrative_key: Some(format!("MW{}", id.get())),
+            email: Some(email(&personal_data)),
+            personal_data,
+            end_date: None,
+            roles: HashSet::from([StaffMemberRole::ITCoordinator]),
+            groups: Vec::new(),
+            sub_groups: sub_groups
+                .iter()
+                .filter_map(|sub_group| sub_group.administrative_key.clone())
+                .collect(),
+        });
+
+        let generated_at = self.generated_at.unwrap_or_else(default_timestamp);
+
+        InstitutionOverview {
+            groups,
+            sub_groups,
+            students,
+            staff,
+            active: true,
+            merged_into: None,
+            result_metadata: ResultMetadata {
+                mutation_timestamp: generated_at,
+                generation_timestamp: generated_at,
+            },
+        }
+    }
+
+    /// A chain ID ("ECK iD") in the format `https://ketenid.nl/201703/<128 hex digits>`.
+    fn chain_id(&self, rng: &mut fastrand::Rng) -> Option<String> {
+        self.chain_ids.then(|| {
+            let mut chain_id = String::from("https://ketenid.nl/201703/");
+            for _ in 0..4 {
+                chain_id.push_str(&format!("{:032x}", rng.u128(..)));
+            }
+            chain_id
+        })
+    }
+}
+
+/// The letter distinguishing groups of the same year group, if there are several.
+fn group_suffix(index: usize, groups_per_year_group: usize) -> String {
+    if groups_per_year_group <= 1 {
+        return String::new();
+    }
+
+    let mut suffix = String::new();
+    let mut index = index;
+    loop {
+        suffix.insert(0, char::from(b'A' + (index % 26) as u8));
+        index /= 26;
+        if index == 0 {
+            return suffix;
+        }
+        index -= 1;
+    }
+}
+
+fn personal_data(rng: &mut fastrand::Rng) -> PersonalData {
+    let first_name = FIRST_NAMES[rng.usize(..FIRST_NAMES.len())];
+
+    PersonalData {
+        last_name: Some(LAST_NAMES[rng.usize(..LAST_NAMES.len())].into()),
+        first_name: Some(first_name.into()),
+        prefix: PREFIXES[rng.usize(..PREFIXES.len())].map(Into::into),
+        initials: first_name
+            .chars()
+            .next()
+            .map(|initial| format!("{initial}.")),
+    }
+}
+
+fn email(personal_data: &PersonalData) -> String {
+    let name = |part: &Option<String>| {
+        part.as_deref()
+            .unwrap_or_default()
+            .to_lowercase()
+            .replace(' ', "")
+    };
+
+    format!(
+        "{}.{}{}@school.example",
+        name(&personal_data.first_name),
+        name(&personal_data.prefix),
+        name(&personal_data.last_name)
+    )
+}
+
+#[cfg(feature = "chrono")]
+fn default_timestamp() -> Timestamp {
+    chrono::DateTime::from_timestamp(1_722_470_400, 0).unwrap()
+}
+
+#[cfg(not(feature = "chrono"))]
+fn default_timestamp() -> Timestamp {
+    time::OffsetDateTime::from_unix_timestamp(1_722_470_400).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_consistent_reproducible_rosters() {
+        let generator = RosterGenerator::new(7)
+            .groups_per_year_group(2)
+            .students_per_group(3)
+            .sub_groups(5);
+        let overview = generator.generate();
+
+        assert_eq!(overview.groups.len(), 16);
+        assert_eq!(overview.groups[1].name.as_deref(), Some("Groep 1b"));
+        assert_eq!(overview.sub_groups[4].name.as_deref(), Some("Rekengroep 2"));
+        assert_eq!(overview.students.len(), 48);
+        assert_eq!(overview.staff.len(), 17);
+
+        let group_keys: HashSet<_> = overview
+            .groups
+            .iter()
+            .filter_map(|group| group.administrative_key.as_ref())
+            .collect();
+        assert!(overview
+            .students
+            .iter()
+            .all(|student| group_keys.contains(student.group.as_ref().unwrap())));
+
+        let ids: HashSet<_> = overview
+            .students
+            .iter()
+            .map(|student| student.id)
+            .chain(overview.staff.iter().map(|staff_member| staff_member.id))
+            .collect();
+        assert_eq!(ids.len(), 48 + 17);
+
+        let names = |overview: &InstitutionOverview| -> Vec<_> {
+            overview
+                .students
+                .iter()
+                .map(|student| student.personal_data.first_name.clone())
+                .collect()
+        };
+        assert_eq!(names(&overview), names(&generator.generate()));
+        assert_ne!(
+            names(&overview),
+            names(
+                &RosterGenerator::new(8)
+                    .students_per_group(3)
+                    .groups_per_year_group(2)
+                    .generate()
+            )
+        );
+    }
+
+    #[test]
+    fn names_groups_beyond_z() {
+        assert_eq!(group_suffix(0, 1), "");
+        assert_eq!(group_suffix(25, 30), "Z");
+        assert_eq!(group_suffix(26, 30), "AA");
+    }
+}
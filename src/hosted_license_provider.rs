@@ -1,5 +1,13 @@
 pub use client::*;
+pub use content_hash::*;
+pub use icon_cache::*;
+pub use ids::*;
 pub use model::*;
+pub use registry::*;
 
 mod client;
+mod content_hash;
+mod icon_cache;
+mod ids;
 mod model;
+mod registry;
@@ -1,272 +1,631 @@
+use std::collections::BTreeSet;
+use std::env;
 use std::fmt::Debug;
+use std::future::Future;
+use std::num::NonZeroUsize;
 
+use futures_lite::{stream, Stream, StreamExt};
+use reqwest::Method;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use tracing::debug;
 #[cfg(not(coverage))]
 use tracing::instrument;
 
-use crate::{rest, Result};
+use crate::{
+    checkpoint::Checkpoint,
+    dry_run::{DryRun, PlannedRequest},
+    error::{Error, ErrorKind},
+    progress::{ProgressReporter, ProgressSender},
+    rest,
+    rollback::Rollback,
+    transport::Transport,
+    BasispoortId, Result,
+};
 
+use super::icon_cache::IconCache;
+use super::ids::{IdentityCode, MethodId, ProductId};
 use super::model::*;
 
+/// Environment variable holding the identity code, as read by [`HostedLicenseProviderClient::new_from_env`].
+const IDENTITY_CODE_VAR: &str = "HOSTED_LICENSE_PROVIDER_IDENTITY_CODE";
+
 /// An API client for the hosted license provider service ("Hosted Lika").
-#[derive(Debug)]
-pub struct HostedLicenseProviderClient<'a> {
-    rest_client: &'a rest::RestClient,
-    base_path: &'static str,
+///
+/// Sends its requests through a [`Transport`], usually a [`RestClient`][rest::RestClient].
+#[derive(Debug, Clone)]
+pub struct HostedLicenseProviderClient<C: Transport = rest::RestClient> {
+    transport: C,
     identity_code: String,
+    allow_destructive: bool,
+    dry_run: DryRun,
+    limits: Limits,
 }
 
-// TODO: Ensure method ID is valid and does not contain a slash; fail with an appropriate error otherwise.
-// TODO: Ensure all validation as documented.
-impl<'a> HostedLicenseProviderClient<'a> {
+impl<C: Transport> HostedLicenseProviderClient<C> {
+    /// Create a client for `identity_code`, which may be an [`IdentityCode`].
+    ///
+    /// Method and product IDs passed to the client's methods, raw or as [`MethodId`] and [`ProductId`], are validated
+    /// before each request, failing with [`ErrorKind::InvalidId`] if they would not form a valid URL path segment.
+    /// So is the identity code. Methods, products, user ID lists and bulk requests are validated against the
+    /// [default limits][Limits::default] before they are sent, unless others are set with [`Self::with_limits`].
+    ///
+    /// If `transport` connects to the production environment, destructive operations are blocked,
+    /// unless allowed with [`Self::allow_destructive`].
     #[cfg_attr(not(coverage), instrument)]
-    pub fn new<S: Into<String> + Debug>(
-        rest_client: &'a rest::RestClient,
-        identity_code: S,
-    ) -> Self {
+    pub fn new<S: Into<String> + Debug>(transport: &C, identity_code: S) -> Self {
         HostedLicenseProviderClient {
-            rest_client,
-            base_path: "/hosted-lika/management/lika/",
+            transport: transport.clone(),
             identity_code: identity_code.into(),
+            allow_destructive: !transport.is_production(),
+            dry_run: DryRun::default(),
+            limits: Limits::default(),
+        }
+    }
+
+    /// Validate requests against `limits`, instead of the [default limits][Limits::default].
+    ///
+    /// Requests exceeding them fail with [`ErrorKind::LimitExceeded`] or [`ErrorKind::InvalidUserIds`],
+    /// without being sent.
+    pub fn with_limits(self, limits: Limits) -> Self {
+        Self { limits, ..self }
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// Allow destructive operations in the production environment: deleting methods and products,
+    /// deleting all their users, and bulk-revoking permissions.
+    ///
+    /// Without this, they fail with [`ErrorKind::DestructiveOperationBlocked`], so test code accidentally pointed
+    /// at production cannot wipe live license data. Other environments always allow them.
+    pub fn allow_destructive(self) -> Self {
+        Self {
+            allow_destructive: true,
+            ..self
         }
     }
 
-    fn make_path(&self, path: &str) -> String {
-        format!(
+    /// Log and record the mutating requests, such as creating methods and adding users, instead of sending them.
+    ///
+    /// Reads are still sent. Get the recorded requests with [`Self::planned_requests`], e.g. to validate a sync plan
+    /// before applying it. Clones of the client share their records. Enabling a dry run discards earlier records.
+    pub fn dry_run(self, enabled: bool) -> Self {
+        Self {
+            dry_run: DryRun::new(enabled),
+            ..self
+        }
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run.is_enabled()
+    }
+
+    /// The mutating requests not sent during the [dry run][Self::dry_run], in order.
+    pub fn planned_requests(&self) -> Vec<PlannedRequest> {
+        self.dry_run.planned_requests()
+    }
+
+    /// Create a client for the identity code read from the `HOSTED_LICENSE_PROVIDER_IDENTITY_CODE` environment variable.
+    #[cfg_attr(not(coverage), instrument)]
+    pub fn new_from_env(transport: &C) -> Result<Self> {
+        let identity_code =
+            env::var(IDENTITY_CODE_VAR).map_err(|_| ErrorKind::MissingConfigValue {
+                key: IDENTITY_CODE_VAR.into(),
+            })?;
+
+        Ok(Self::new(transport, identity_code))
+    }
+
+    #[cfg(feature = "test-support")]
+    pub(crate) fn transport(&self) -> &C {
+        &self.transport
+    }
+
+    /// Split `users` into lists within [`Limits::max_users_per_list`], yielding none if `users` is empty.
+    fn chunks_within_limits(&self, users: &[BasispoortId]) -> Vec<UserIdList> {
+        match self.limits.max_users_per_list.and_then(NonZeroUsize::new) {
+            Some(size) => UserIdList::from(users.to_vec()).chunks(size),
+            None if users.is_empty() => Vec::new(),
+            None => vec![users.to_vec().into()],
+        }
+    }
+
+    fn ensure_destructive_allowed(&self, operation: &'static str) -> Result<()> {
+        ensure_destructive_allowed(self.allow_destructive, operation)
+    }
+
+    fn make_path(&self, path: &str) -> Result<String> {
+        Ok(format!(
             "{base_path}{identity_code}/{path}",
-            base_path = self.base_path,
-            identity_code = self.identity_code
-        )
+            base_path = self.transport.service_paths().hosted_license_provider,
+            identity_code = IdentityCode::check(&self.identity_code)?
+        ))
     }
 
     #[cfg_attr(not(coverage), instrument(skip(self)))]
-    async fn get<T: DeserializeOwned + Debug + ?Sized>(&self, path: &str) -> Result<T> {
-        self.rest_client.get(&self.make_path(path)).await
+    async fn get<T: DeserializeOwned + Debug + Send>(&self, path: &str) -> Result<T> {
+        self.transport.get(&self.make_path(path)?).await
     }
 
     #[cfg_attr(not(coverage), instrument(skip(self, payload)))]
-    async fn post<P: Serialize + Debug + ?Sized, T: DeserializeOwned + Debug + ?Sized>(
+    async fn post<P: Serialize + Debug + Sync + ?Sized, T: DeserializeOwned + Debug + Send>(
         &self,
         path: &str,
         payload: &P,
     ) -> Result<T> {
-        self.rest_client.post(&self.make_path(path), payload).await
+        let path = self.make_path(path)?;
+        if let Some(response) = self.dry_run.intercept(Method::POST, &path, Some(payload)) {
+            return response;
+        }
+
+        self.transport.post(&path, payload).await
     }
 
     #[cfg_attr(not(coverage), instrument(skip(self, payload)))]
-    async fn put<P: Serialize + Debug + ?Sized, T: DeserializeOwned + Debug + ?Sized>(
+    async fn put<P: Serialize + Debug + Sync + ?Sized, T: DeserializeOwned + Debug + Send>(
         &self,
         path: &str,
         payload: &P,
     ) -> Result<T> {
-        self.rest_client.put(&self.make_path(path), payload).await
+        let path = self.make_path(path)?;
+        if let Some(response) = self.dry_run.intercept(Method::PUT, &path, Some(payload)) {
+            return response;
+        }
+
+        self.transport.put(&path, payload).await
     }
 
     #[cfg_attr(not(coverage), instrument(skip(self)))]
-    async fn delete<T: DeserializeOwned + Debug + ?Sized>(&self, path: &str) -> Result<T> {
-        self.rest_client.delete(&self.make_path(path)).await
+    async fn delete<T: DeserializeOwned + Debug + Send>(&self, path: &str) -> Result<T> {
+        let path = self.make_path(path)?;
+        if let Some(response) = self.dry_run.intercept::<(), _>(Method::DELETE, &path, None) {
+            return response;
+        }
+
+        self.transport.delete(&path).await
     }
 
     /*
      * Method management
      */
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "hosted_license_provider.get_methods", skip(self))
+    )]
     pub async fn get_methods(&self) -> Result<MethodDetailsList> {
         self.get("methode").await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    /// Stream all methods, fetched with a single request, as [`Self::get_methods`] does.
+    ///
+    /// The API does not page method lists, so the whole list is held in memory while the stream yields its methods.
+    pub fn stream_methods(&self) -> impl Stream<Item = Result<MethodDetails>> + '_ {
+        Self::stream_list(async move { Ok(self.get_methods().await?.methods) })
+    }
+
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "hosted_license_provider.get_method", skip(self))
+    )]
     pub async fn get_method<S: AsRef<str> + Debug>(&self, method_id: S) -> Result<MethodDetails> {
         self.get(&format!(
             "methode/{method_id}",
-            method_id = method_id.as_ref()
+            method_id = MethodId::check(method_id.as_ref())?
         ))
         .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.create_method",
+            skip(self, method),
+            fields(method.id = %method.id)
+        )
+    )]
     pub async fn create_method(&self, method: &MethodDetails) -> Result<()> {
+        method.validate_with(&self.limits)?;
         self.post("methode", method).await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.update_method",
+            skip(self, method),
+            fields(method.id = %method.id)
+        )
+    )]
     pub async fn update_method(&self, method: &MethodDetails) -> Result<()> {
+        method.validate_with(&self.limits)?;
         self.put(
-            &format!("methode/{method_id}", method_id = method.id),
+            &format!(
+                "methode/{method_id}",
+                method_id = MethodId::check(&method.id)?
+            ),
             method,
         )
         .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    /// Update `method`, omitting its icon if `icon_cache` shows it is unchanged.
+    ///
+    /// Relies on Basispoort keeping the stored icon when an update omits it. The cache only records icons
+    /// which were actually uploaded, so a [dry run][Self::dry_run] leaves it as it was.
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.update_method_with_icon_cache",
+            skip(self, method, icon_cache),
+            fields(method.id = %method.id)
+        )
+    )]
+    pub async fn update_method_with_icon_cache(
+        &self,
+        method: &MethodDetails,
+        icon_cache: &IconCache,
+    ) -> Result<()> {
+        let key = self.make_path(&format!(
+            "methode/{method_id}",
+            method_id = MethodId::check(&method.id)?
+        ))?;
+        if icon_cache.get(&key).is_none() {
+            let remote = self.get_method(&method.id).await?;
+            icon_cache.insert(key.clone(), remote.icon.as_deref());
+        }
+
+        if icon_cache.is_unchanged(&key, method.icon.as_deref()) {
+            debug!("Icon unchanged, skipping its upload.");
+            return self.update_method(&method.without_icon()).await;
+        }
+
+        self.update_method(method).await?;
+        if !self.is_dry_run() {
+            icon_cache.insert(key, method.icon.as_deref());
+        }
+
+        Ok(())
+    }
+
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "hosted_license_provider.delete_method", skip(self))
+    )]
     pub async fn delete_method<S: AsRef<str> + Debug>(&self, method_id: S) -> Result<()> {
+        self.ensure_destructive_allowed("delete_method")?;
         self.delete(&format!(
             "methode/{method_id}",
-            method_id = method_id.as_ref()
+            method_id = MethodId::check(method_id.as_ref())?
         ))
         .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "hosted_license_provider.get_method_user_ids", skip(self))
+    )]
     pub async fn get_method_user_ids<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
     ) -> Result<UserIdList> {
         self.get(&format!(
             "methode/{method_id}/gebruiker",
-            method_id = method_id.as_ref()
+            method_id = MethodId::check(method_id.as_ref())?
         ))
         .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.set_method_user_ids",
+            skip(self, users),
+            fields(users.len = users.users.len())
+        )
+    )]
     pub async fn set_method_user_ids<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
         users: &UserIdList,
     ) -> Result<()> {
-        self.put(
-            &format!(
-                "methode/{method_id}/gebruiker",
-                method_id = method_id.as_ref()
-            ),
-            users,
-        )
-        .await
+        let path = format!(
+            "methode/{method_id}/gebruiker",
+            method_id = MethodId::check(method_id.as_ref())?
+        );
+        ensure_not_empty(&path, users.users.len())?;
+        users.validate_with(&self.limits)?;
+        self.put(&path, users).await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "hosted_license_provider.delete_method_user_ids", skip(self))
+    )]
     pub async fn delete_method_user_ids<S: AsRef<str> + Debug>(&self, method_id: S) -> Result<()> {
+        self.ensure_destructive_allowed("delete_method_user_ids")?;
         self.delete(&format!(
             "methode/{method_id}/gebruiker",
-            method_id = method_id.as_ref()
+            method_id = MethodId::check(method_id.as_ref())?
         ))
         .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.add_method_user_ids",
+            skip(self, users),
+            fields(users.len = users.users.len())
+        )
+    )]
     pub async fn add_method_user_ids<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
         users: &UserIdList,
     ) -> Result<()> {
-        self.post(
-            &format!(
-                "methode/{method_id}/gebruiker/addlist",
-                method_id = method_id.as_ref()
-            ),
-            users,
+        let path = format!(
+            "methode/{method_id}/gebruiker/addlist",
+            method_id = MethodId::check(method_id.as_ref())?
+        );
+        ensure_not_empty(&path, users.users.len())?;
+        users.validate_with(&self.limits)?;
+        self.post(&path, users).await
+    }
+
+    /// Add `users` to the method in chunks of `chunk_size`, resuming from `checkpoint` if interrupted.
+    ///
+    /// Reports each chunk to `progress`, if given.
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.add_method_user_ids_chunked",
+            skip(self, users, checkpoint, progress),
+            fields(users.len = users.users.len())
         )
-        .await
+    )]
+    pub async fn add_method_user_ids_chunked<S: AsRef<str> + Debug>(
+        &self,
+        method_id: S,
+        users: &UserIdList,
+        chunk_size: NonZeroUsize,
+        checkpoint: &Checkpoint,
+        progress: Option<&ProgressSender>,
+    ) -> Result<()> {
+        checkpoint
+            .run_with_progress(
+                &users.chunks(chunk_size),
+                |chunk| self.add_method_user_ids(method_id.as_ref(), chunk),
+                progress,
+                "hosted_license_provider.add_method_user_ids_chunked",
+            )
+            .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.remove_method_user_ids",
+            skip(self, users),
+            fields(users.len = users.users.len())
+        )
+    )]
     pub async fn remove_method_user_ids<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
         users: &UserIdList,
     ) -> Result<()> {
-        self.post(
-            &format!(
-                "methode/{method_id}/gebruiker/removelist",
-                method_id = method_id.as_ref()
-            ),
-            users,
+        let path = format!(
+            "methode/{method_id}/gebruiker/removelist",
+            method_id = MethodId::check(method_id.as_ref())?
+        );
+        ensure_not_empty(&path, users.users.len())?;
+        users.validate_with(&self.limits)?;
+        self.post(&path, users).await
+    }
+
+    /// Grant access to the method to exactly the `desired` users, adding and removing only the users which differ
+    /// from the current ones, instead of replacing all users.
+    ///
+    /// Users are added and removed in lists within [`Limits::max_users_per_list`], each reported to `progress`,
+    /// if given. Returns the changes made. If adding succeeds but removing fails, the added users keep their access.
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.sync_method_user_ids",
+            skip(self, desired, progress),
+            fields(desired.len = desired.len())
         )
-        .await
+    )]
+    pub async fn sync_method_user_ids<S: AsRef<str> + Debug>(
+        &self,
+        method_id: S,
+        desired: &[BasispoortId],
+        progress: Option<&ProgressSender>,
+    ) -> Result<UserIdSync> {
+        let method_id = MethodId::check(method_id.as_ref())?;
+        let current = self.get_method_user_ids(method_id).await?;
+
+        let sync = UserIdSync::new(&current.users, desired);
+        let additions = self.chunks_within_limits(&sync.added);
+        let removals = self.chunks_within_limits(&sync.removed);
+        let mut reporter = ProgressReporter::start(
+            progress,
+            "hosted_license_provider.sync_method_user_ids",
+            Some(additions.len() + removals.len()),
+        );
+        let synced = async {
+            for users in &additions {
+                reporter.record(self.add_method_user_ids(method_id, users).await)?;
+            }
+            for users in &removals {
+                reporter.record(self.remove_method_user_ids(method_id, users).await)?;
+            }
+            Ok::<_, Error>(())
+        }
+        .await;
+        reporter.finish();
+        synced?;
+
+        debug!(
+            "Added {} and removed {} users of method '{method_id}'.",
+            sync.added.len(),
+            sync.removed.len()
+        );
+
+        Ok(sync)
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "hosted_license_provider.get_method_user_chain_ids", skip(self))
+    )]
     pub async fn get_method_user_chain_ids<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
     ) -> Result<UserChainIdList> {
         self.get(&format!(
             "methode/{method_id}/gebruiker_eckid",
-            method_id = method_id.as_ref()
+            method_id = MethodId::check(method_id.as_ref())?
         ))
         .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.set_method_user_chain_ids",
+            skip(self, users),
+            fields(users.len = users.users.len())
+        )
+    )]
     pub async fn set_method_user_chain_ids<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
         users: &UserChainIdList,
     ) -> Result<()> {
-        self.put(
-            &format!(
-                "methode/{method_id}/gebruiker_eckid",
-                method_id = method_id.as_ref()
-            ),
-            users,
-        )
-        .await
+        let path = format!(
+            "methode/{method_id}/gebruiker_eckid",
+            method_id = MethodId::check(method_id.as_ref())?
+        );
+        ensure_not_empty(&path, users.users.len())?;
+        users.validate_with(&self.limits)?;
+        self.put(&path, users).await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.delete_method_user_chain_ids",
+            skip(self)
+        )
+    )]
     pub async fn delete_method_user_chain_ids<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
     ) -> Result<()> {
+        self.ensure_destructive_allowed("delete_method_user_chain_ids")?;
         self.delete(&format!(
             "methode/{method_id}/gebruiker_eckid",
-            method_id = method_id.as_ref()
+            method_id = MethodId::check(method_id.as_ref())?
         ))
         .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.add_method_user_chain_ids",
+            skip(self, users),
+            fields(users.len = users.users.len())
+        )
+    )]
     pub async fn add_method_user_chain_ids<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
         users: &UserChainIdList,
     ) -> Result<()> {
-        self.post(
-            &format!(
-                "methode/{method_id}/gebruiker_eckid/addlist",
-                method_id = method_id.as_ref()
-            ),
-            users,
-        )
-        .await
+        let path = format!(
+            "methode/{method_id}/gebruiker_eckid/addlist",
+            method_id = MethodId::check(method_id.as_ref())?
+        );
+        ensure_not_empty(&path, users.users.len())?;
+        users.validate_with(&self.limits)?;
+        self.post(&path, users).await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.remove_method_user_chain_ids",
+            skip(self, users),
+            fields(users.len = users.users.len())
+        )
+    )]
     pub async fn remove_method_user_chain_ids<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
         users: &UserChainIdList,
     ) -> Result<()> {
-        self.post(
-            &format!(
-                "methode/{method_id}/gebruiker_eckid/removelist",
-                method_id = method_id.as_ref()
-            ),
-            users,
-        )
-        .await
+        let path = format!(
+            "methode/{method_id}/gebruiker_eckid/removelist",
+            method_id = MethodId::check(method_id.as_ref())?
+        );
+        ensure_not_empty(&path, users.users.len())?;
+        users.validate_with(&self.limits)?;
+        self.post(&path, users).await
     }
 
     /*
      * Product management
      */
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "hosted_license_provider.get_products", skip(self))
+    )]
     pub async fn get_products<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
     ) -> Result<ProductDetailsList> {
         self.get(&format!(
             "methode/{method_id}/product",
-            method_id = method_id.as_ref()
+            method_id = MethodId::check(method_id.as_ref())?
         ))
         .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    /// Stream all products of the method, fetched with a single request, as [`Self::get_products`] does.
+    ///
+    /// Like [`Self::stream_methods`], this holds the whole list in memory.
+    pub fn stream_products<S: AsRef<str>>(
+        &self,
+        method_id: S,
+    ) -> impl Stream<Item = Result<ProductDetails>> + '_ {
+        let method_id = method_id.as_ref().to_owned();
+        Self::stream_list(async move { Ok(self.get_products(method_id).await?.products) })
+    }
+
+    /// Stream the items of the list fetched by `list`, or its error.
+    fn stream_list<'s, T, L>(list: L) -> impl Stream<Item = Result<T>> + 's
+    where
+        T: 's,
+        L: Future<Output = Result<Vec<T>>> + 's,
+    {
+        stream::once_future(list).flat_map(|items| {
+            stream::iter(match items {
+                Ok(items) => items.into_iter().map(Ok).collect(),
+                Err(error) => vec![Err(error)],
+            })
+        })
+    }
+
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "hosted_license_provider.get_product", skip(self))
+    )]
     pub async fn get_product<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
@@ -274,60 +633,124 @@ impl<'a> HostedLicenseProviderClient<'a> {
     ) -> Result<ProductDetails> {
         self.get(&format!(
             "methode/{method_id}/product/{product_id}",
-            method_id = method_id.as_ref(),
-            product_id = product_id.as_ref()
+            method_id = MethodId::check(method_id.as_ref())?,
+            product_id = ProductId::check(product_id.as_ref())?
         ))
         .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.create_product",
+            skip(self, product),
+            fields(product.id = %product.id)
+        )
+    )]
     pub async fn create_product<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
         product: &ProductDetails,
     ) -> Result<()> {
+        product.validate_with(&self.limits)?;
         self.post(
             &format!(
                 "methode/{method_id}/product",
-                method_id = method_id.as_ref()
+                method_id = MethodId::check(method_id.as_ref())?
             ),
             product,
         )
         .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.update_product",
+            skip(self, product),
+            fields(product.id = %product.id)
+        )
+    )]
     pub async fn update_product<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
         product: &ProductDetails,
     ) -> Result<()> {
+        product.validate_with(&self.limits)?;
         self.put(
             &format!(
                 "methode/{method_id}/product/{product_id}",
-                method_id = method_id.as_ref(),
-                product_id = product.id
+                method_id = MethodId::check(method_id.as_ref())?,
+                product_id = ProductId::check(&product.id)?
             ),
             product,
         )
         .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    /// Update `product`, omitting its icon if `icon_cache` shows it is unchanged, as
+    /// [`Self::update_method_with_icon_cache`] does.
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.update_product_with_icon_cache",
+            skip(self, product, icon_cache),
+            fields(product.id = %product.id)
+        )
+    )]
+    pub async fn update_product_with_icon_cache<S: AsRef<str> + Debug>(
+        &self,
+        method_id: S,
+        product: &ProductDetails,
+        icon_cache: &IconCache,
+    ) -> Result<()> {
+        let method_id = MethodId::check(method_id.as_ref())?;
+        let key = self.make_path(&format!(
+            "methode/{method_id}/product/{product_id}",
+            product_id = ProductId::check(&product.id)?
+        ))?;
+        if icon_cache.get(&key).is_none() {
+            let remote = self.get_product(method_id, product.id.as_str()).await?;
+            icon_cache.insert(key.clone(), remote.icon.as_deref());
+        }
+
+        if icon_cache.is_unchanged(&key, product.icon.as_deref()) {
+            debug!("Icon unchanged, skipping its upload.");
+            return self
+                .update_product(method_id, &product.without_icon())
+                .await;
+        }
+
+        self.update_product(method_id, product).await?;
+        if !self.is_dry_run() {
+            icon_cache.insert(key, product.icon.as_deref());
+        }
+
+        Ok(())
+    }
+
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "hosted_license_provider.delete_product", skip(self))
+    )]
     pub async fn delete_product<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
         product_id: S,
     ) -> Result<()> {
+        self.ensure_destructive_allowed("delete_product")?;
         self.delete(&format!(
             "methode/{method_id}/product/{product_id}",
-            method_id = method_id.as_ref(),
-            product_id = product_id.as_ref()
+            method_id = MethodId::check(method_id.as_ref())?,
+            product_id = ProductId::check(product_id.as_ref())?
         ))
         .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "hosted_license_provider.get_product_user_ids", skip(self))
+    )]
     pub async fn get_product_user_ids<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
@@ -335,81 +758,195 @@ impl<'a> HostedLicenseProviderClient<'a> {
     ) -> Result<UserIdList> {
         self.get(&format!(
             "methode/{method_id}/product/{product_id}/gebruiker",
-            method_id = method_id.as_ref(),
-            product_id = product_id.as_ref()
+            method_id = MethodId::check(method_id.as_ref())?,
+            product_id = ProductId::check(product_id.as_ref())?
         ))
         .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.set_product_user_ids",
+            skip(self, users),
+            fields(users.len = users.users.len())
+        )
+    )]
     pub async fn set_product_user_ids<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
         product_id: S,
         users: &UserIdList,
     ) -> Result<()> {
-        self.put(
-            &format!(
-                "methode/{method_id}/product/{product_id}/gebruiker",
-                method_id = method_id.as_ref(),
-                product_id = product_id.as_ref()
-            ),
-            users,
-        )
-        .await
+        let path = format!(
+            "methode/{method_id}/product/{product_id}/gebruiker",
+            method_id = MethodId::check(method_id.as_ref())?,
+            product_id = ProductId::check(product_id.as_ref())?
+        );
+        ensure_not_empty(&path, users.users.len())?;
+        users.validate_with(&self.limits)?;
+        self.put(&path, users).await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "hosted_license_provider.delete_product_user_ids", skip(self))
+    )]
     pub async fn delete_product_user_ids<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
         product_id: S,
     ) -> Result<()> {
+        self.ensure_destructive_allowed("delete_product_user_ids")?;
         self.delete(&format!(
             "methode/{method_id}/product/{product_id}/gebruiker",
-            method_id = method_id.as_ref(),
-            product_id = product_id.as_ref()
+            method_id = MethodId::check(method_id.as_ref())?,
+            product_id = ProductId::check(product_id.as_ref())?
         ))
         .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.add_product_user_ids",
+            skip(self, users),
+            fields(users.len = users.users.len())
+        )
+    )]
     pub async fn add_product_user_ids<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
         product_id: S,
         users: &UserIdList,
     ) -> Result<()> {
-        self.post(
-            &format!(
-                "methode/{method_id}/product/{product_id}/gebruiker/addlist",
-                method_id = method_id.as_ref(),
-                product_id = product_id.as_ref()
-            ),
-            users,
+        let path = format!(
+            "methode/{method_id}/product/{product_id}/gebruiker/addlist",
+            method_id = MethodId::check(method_id.as_ref())?,
+            product_id = ProductId::check(product_id.as_ref())?
+        );
+        ensure_not_empty(&path, users.users.len())?;
+        users.validate_with(&self.limits)?;
+        self.post(&path, users).await
+    }
+
+    /// Add `users` to the product in chunks of `chunk_size`, resuming from `checkpoint` if interrupted.
+    ///
+    /// Reports each chunk to `progress`, if given.
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.add_product_user_ids_chunked",
+            skip(self, users, checkpoint, progress),
+            fields(users.len = users.users.len())
         )
-        .await
+    )]
+    pub async fn add_product_user_ids_chunked<S: AsRef<str> + Debug>(
+        &self,
+        method_id: S,
+        product_id: S,
+        users: &UserIdList,
+        chunk_size: NonZeroUsize,
+        checkpoint: &Checkpoint,
+        progress: Option<&ProgressSender>,
+    ) -> Result<()> {
+        checkpoint
+            .run_with_progress(
+                &users.chunks(chunk_size),
+                |chunk| self.add_product_user_ids(method_id.as_ref(), product_id.as_ref(), chunk),
+                progress,
+                "hosted_license_provider.add_product_user_ids_chunked",
+            )
+            .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.remove_product_user_ids",
+            skip(self, users),
+            fields(users.len = users.users.len())
+        )
+    )]
     pub async fn remove_product_user_ids<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
         product_id: S,
         users: &UserIdList,
     ) -> Result<()> {
-        self.post(
-            &format!(
-                "methode/{method_id}/product/{product_id}/gebruiker/removelist",
-                method_id = method_id.as_ref(),
-                product_id = product_id.as_ref()
-            ),
-            users,
+        let path = format!(
+            "methode/{method_id}/product/{product_id}/gebruiker/removelist",
+            method_id = MethodId::check(method_id.as_ref())?,
+            product_id = ProductId::check(product_id.as_ref())?
+        );
+        ensure_not_empty(&path, users.users.len())?;
+        users.validate_with(&self.limits)?;
+        self.post(&path, users).await
+    }
+
+    /// Grant access to the product to exactly the `desired` users, like [`Self::sync_method_user_ids`].
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.sync_product_user_ids",
+            skip(self, desired, progress),
+            fields(desired.len = desired.len())
         )
-        .await
+    )]
+    pub async fn sync_product_user_ids<S: AsRef<str> + Debug>(
+        &self,
+        method_id: S,
+        product_id: S,
+        desired: &[BasispoortId],
+        progress: Option<&ProgressSender>,
+    ) -> Result<UserIdSync> {
+        let method_id = MethodId::check(method_id.as_ref())?;
+        let product_id = ProductId::check(product_id.as_ref())?;
+        let current = self.get_product_user_ids(method_id, product_id).await?;
+
+        let sync = UserIdSync::new(&current.users, desired);
+        let additions = self.chunks_within_limits(&sync.added);
+        let removals = self.chunks_within_limits(&sync.removed);
+        let mut reporter = ProgressReporter::start(
+            progress,
+            "hosted_license_provider.sync_product_user_ids",
+            Some(additions.len() + removals.len()),
+        );
+        let synced = async {
+            for users in &additions {
+                reporter.record(
+                    self.add_product_user_ids(method_id, product_id, users)
+                        .await,
+                )?;
+            }
+            for users in &removals {
+                reporter.record(
+                    self.remove_product_user_ids(method_id, product_id, users)
+                        .await,
+                )?;
+            }
+            Ok::<_, Error>(())
+        }
+        .await;
+        reporter.finish();
+        synced?;
+
+        debug!(
+            "Added {} and removed {} users of product '{product_id}' of method '{method_id}'.",
+            sync.added.len(),
+            sync.removed.len()
+        );
+
+        Ok(sync)
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.get_product_user_chain_ids",
+            skip(self)
+        )
+    )]
     pub async fn get_product_user_chain_ids<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
@@ -417,91 +954,474 @@ impl<'a> HostedLicenseProviderClient<'a> {
     ) -> Result<UserChainIdList> {
         self.get(&format!(
             "methode/{method_id}/product/{product_id}/gebruiker_eckid",
-            method_id = method_id.as_ref(),
-            product_id = product_id.as_ref()
+            method_id = MethodId::check(method_id.as_ref())?,
+            product_id = ProductId::check(product_id.as_ref())?
         ))
         .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.set_product_user_chain_ids",
+            skip(self, users),
+            fields(users.len = users.users.len())
+        )
+    )]
     pub async fn set_product_user_chain_ids<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
         product_id: S,
         users: &UserChainIdList,
     ) -> Result<()> {
-        self.put(
-            &format!(
-                "methode/{method_id}/product/{product_id}/gebruiker_eckid",
-                method_id = method_id.as_ref(),
-                product_id = product_id.as_ref()
-            ),
-            users,
-        )
-        .await
+        let path = format!(
+            "methode/{method_id}/product/{product_id}/gebruiker_eckid",
+            method_id = MethodId::check(method_id.as_ref())?,
+            product_id = ProductId::check(product_id.as_ref())?
+        );
+        ensure_not_empty(&path, users.users.len())?;
+        users.validate_with(&self.limits)?;
+        self.put(&path, users).await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.delete_product_user_chain_ids",
+            skip(self)
+        )
+    )]
     pub async fn delete_product_user_chain_ids<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
         product_id: S,
     ) -> Result<()> {
+        self.ensure_destructive_allowed("delete_product_user_chain_ids")?;
         self.delete(&format!(
             "methode/{method_id}/product/{product_id}/gebruiker_eckid",
-            method_id = method_id.as_ref(),
-            product_id = product_id.as_ref()
+            method_id = MethodId::check(method_id.as_ref())?,
+            product_id = ProductId::check(product_id.as_ref())?
         ))
         .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.add_product_user_chain_ids",
+            skip(self, users),
+            fields(users.len = users.users.len())
+        )
+    )]
     pub async fn add_product_user_chain_ids<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
         product_id: S,
         users: &UserChainIdList,
     ) -> Result<()> {
-        self.post(
-            &format!(
-                "methode/{method_id}/product/{product_id}/gebruiker_eckid/addlist",
-                method_id = method_id.as_ref(),
-                product_id = product_id.as_ref()
-            ),
-            users,
-        )
-        .await
+        let path = format!(
+            "methode/{method_id}/product/{product_id}/gebruiker_eckid/addlist",
+            method_id = MethodId::check(method_id.as_ref())?,
+            product_id = ProductId::check(product_id.as_ref())?
+        );
+        ensure_not_empty(&path, users.users.len())?;
+        users.validate_with(&self.limits)?;
+        self.post(&path, users).await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.remove_product_user_chain_ids",
+            skip(self, users),
+            fields(users.len = users.users.len())
+        )
+    )]
     pub async fn remove_product_user_chain_ids<S: AsRef<str> + Debug>(
         &self,
         method_id: S,
         product_id: S,
         users: &UserChainIdList,
     ) -> Result<()> {
-        self.post(
-            &format!(
-                "methode/{method_id}/product/{product_id}/gebruiker_eckid/removelist",
-                method_id = method_id.as_ref(),
-                product_id = product_id.as_ref()
-            ),
-            users,
-        )
-        .await
+        let path = format!(
+            "methode/{method_id}/product/{product_id}/gebruiker_eckid/removelist",
+            method_id = MethodId::check(method_id.as_ref())?,
+            product_id = ProductId::check(product_id.as_ref())?
+        );
+        ensure_not_empty(&path, users.users.len())?;
+        users.validate_with(&self.limits)?;
+        self.post(&path, users).await
     }
 
     /*
      * Bulk actions
      */
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "hosted_license_provider.bulk_grant_permissions", skip(self))
+    )]
     pub async fn bulk_grant_permissions(&self, bulk_request: &BulkRequest) -> Result<()> {
+        ensure_bulk_not_empty("permissions/grant", bulk_request)?;
+        bulk_request.validate_with(&self.limits)?;
         self.post("permissions/grant", bulk_request).await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    /// Grant permissions in chunks of at most `chunk_size` users, resuming from `checkpoint` if interrupted.
+    ///
+    /// Reports each chunk to `progress`, if given.
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.bulk_grant_permissions_chunked",
+            skip(self, checkpoint, progress)
+        )
+    )]
+    pub async fn bulk_grant_permissions_chunked(
+        &self,
+        bulk_request: &BulkRequest,
+        chunk_size: NonZeroUsize,
+        checkpoint: &Checkpoint,
+        progress: Option<&ProgressSender>,
+    ) -> Result<()> {
+        checkpoint
+            .run_with_progress(
+                &bulk_request.chunks(chunk_size),
+                |chunk| self.bulk_grant_permissions(chunk),
+                progress,
+                "hosted_license_provider.bulk_grant_permissions_chunked",
+            )
+            .await
+    }
+
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "hosted_license_provider.bulk_revoke_permissions", skip(self))
+    )]
     pub async fn bulk_revoke_permissions(&self, bulk_request: &BulkRequest) -> Result<()> {
+        self.ensure_destructive_allowed("bulk_revoke_permissions")?;
+        ensure_bulk_not_empty("permissions/revoke", bulk_request)?;
+        bulk_request.validate_with(&self.limits)?;
         self.post("permissions/revoke", bulk_request).await
     }
+
+    /*
+     * Reporting
+     */
+
+    /// List the users with access to each method and product of the identity code.
+    ///
+    /// Makes one request per method and product, without fetching their details.
+    /// Reports each method, with its products, to `progress`, if given.
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.get_license_assignments",
+            skip(self, progress)
+        )
+    )]
+    pub async fn get_license_assignments(
+        &self,
+        progress: Option<&ProgressSender>,
+    ) -> Result<Vec<LicenseAssignment>> {
+        self.license_assignments(
+            true,
+            progress,
+            "hosted_license_provider.get_license_assignments",
+        )
+        .await
+    }
+
+    /// The distinct users with access to any method of the identity code, and, if `include_products`,
+    /// to any of their products.
+    ///
+    /// Makes one request per method, and, if `include_products`, one per product.
+    /// Reports each method, with its products, to `progress`, if given.
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.get_all_user_ids",
+            skip(self, progress)
+        )
+    )]
+    pub async fn get_all_user_ids(
+        &self,
+        include_products: bool,
+        progress: Option<&ProgressSender>,
+    ) -> Result<BTreeSet<BasispoortId>> {
+        Ok(self
+            .license_assignments(
+                include_products,
+                progress,
+                "hosted_license_provider.get_all_user_ids",
+            )
+            .await?
+            .into_iter()
+            .map(|assignment| assignment.user_id)
+            .collect())
+    }
+
+    /// Find the users with access to a product of the method, but not to the method itself,
+    /// and the users with access to the method, but to none of its products.
+    ///
+    /// Makes one request for the method, and one per product, each product reported to `progress`, if given.
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.find_inconsistent_assignments",
+            skip(self, progress)
+        )
+    )]
+    pub async fn find_inconsistent_assignments<S: AsRef<str> + Debug>(
+        &self,
+        method_id: S,
+        progress: Option<&ProgressSender>,
+    ) -> Result<InconsistentAssignments> {
+        let method_id = MethodId::check(method_id.as_ref())?;
+        let method_users = self.get_method_user_ids(method_id).await?;
+
+        let products: ProductIdList = self.get(&format!("methode/{method_id}/product")).await?;
+        let mut reporter = ProgressReporter::start(
+            progress,
+            "hosted_license_provider.find_inconsistent_assignments",
+            Some(products.products.len()),
+        );
+        let product_users = async {
+            let mut product_users = Vec::with_capacity(products.products.len());
+            for product in products.products {
+                let users = reporter.record(
+                    self.get_product_user_ids(method_id, product.id.as_str())
+                        .await,
+                )?;
+                product_users.push((product.id, users.users));
+            }
+            Ok::<_, Error>(product_users)
+        }
+        .await;
+        reporter.finish();
+        let product_users = product_users?;
+
+        Ok(InconsistentAssignments::new(
+            method_id,
+            &method_users.users,
+            &product_users,
+        ))
+    }
+
+    async fn license_assignments(
+        &self,
+        include_products: bool,
+        progress: Option<&ProgressSender>,
+        operation: &'static str,
+    ) -> Result<Vec<LicenseAssignment>> {
+        let methods: MethodIdList = self.get("methode").await?;
+
+        let mut reporter =
+            ProgressReporter::start(progress, operation, Some(methods.methods.len()));
+        let assignments = async {
+            let mut assignments = Vec::new();
+            for method in &methods.methods {
+                assignments.extend(
+                    reporter.record(
+                        self.method_license_assignments(&method.id, include_products)
+                            .await,
+                    )?,
+                );
+            }
+            Ok::<_, Error>(assignments)
+        }
+        .await;
+        reporter.finish();
+
+        assignments
+    }
+
+    /// The license assignments of the method, and, if `include_products`, of its products.
+    async fn method_license_assignments(
+        &self,
+        method_id: &str,
+        include_products: bool,
+    ) -> Result<Vec<LicenseAssignment>> {
+        let users = self.get_method_user_ids(method_id).await?;
+        let mut assignments: Vec<_> = users
+            .users
+            .into_iter()
+            .map(|user_id| LicenseAssignment {
+                method_id: method_id.into(),
+                product_id: None,
+                user_id,
+            })
+            .collect();
+
+        if !include_products {
+            return Ok(assignments);
+        }
+
+        let products: ProductIdList = self.get(&format!("methode/{method_id}/product")).await?;
+        for product in products.products {
+            let users = self
+                .get_product_user_ids(method_id, product.id.as_str())
+                .await?;
+            assignments.extend(users.users.into_iter().map(|user_id| LicenseAssignment {
+                method_id: method_id.into(),
+                product_id: Some(product.id.clone()),
+                user_id,
+            }));
+        }
+
+        Ok(assignments)
+    }
+
+    /*
+     * Composite operations
+     */
+
+    /// Create `method` with its `products`, and grant `users` access to the method.
+    ///
+    /// If a step fails, the completed steps are compensated: the created products and method are deleted.
+    /// Failing compensations are logged. The error of the failed step is returned.
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "hosted_license_provider.provision_method",
+            skip(self, method, products, users),
+            fields(method.id = %method.id, products.len = products.len(), users.len = users.users.len())
+        )
+    )]
+    pub async fn provision_method(
+        &self,
+        method: &MethodDetails,
+        products: &[ProductDetails],
+        users: &UserIdList,
+    ) -> Result<()> {
+        // Validate everything before creating anything, so invalid input needs no compensation.
+        method.validate_with(&self.limits)?;
+        for product in products {
+            product.validate_with(&self.limits)?;
+        }
+        users.validate_with(&self.limits)?;
+        let mut rollback = Rollback::new();
+
+        match self
+            .provision_method_steps(method, products, users, &mut rollback)
+            .await
+        {
+            Ok(()) => {
+                rollback.commit();
+                Ok(())
+            }
+            Err(error) => {
+                rollback.compensate().await;
+                Err(error)
+            }
+        }
+    }
+
+    async fn provision_method_steps<'r>(
+        &'r self,
+        method: &'r MethodDetails,
+        products: &'r [ProductDetails],
+        users: &'r UserIdList,
+        rollback: &mut Rollback<'r>,
+    ) -> Result<()> {
+        let method_id = method.id.as_str();
+
+        self.create_method(method).await?;
+        // The compensations only delete what was just created, so they bypass the destructive operation guard.
+        rollback.on_failure(format!("create method {method_id}"), move || async move {
+            self.delete(&format!("methode/{method_id}")).await
+        });
+
+        for product in products {
+            self.create_product(method_id, product).await?;
+            rollback.on_failure(
+                format!("create product {} of method {method_id}", product.id),
+                move || async move {
+                    self.delete(&format!(
+                        "methode/{method_id}/product/{product_id}",
+                        product_id = product.id
+                    ))
+                    .await
+                },
+            );
+        }
+
+        // The last step needs no compensation, as no step can fail after it.
+        if !users.users.is_empty() {
+            self.add_method_user_ids(method_id, users).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Fail with [`ErrorKind::EmptyPayload`] if there are no users to send to `path`.
+///
+/// An empty list is almost always a caller bug. To remove all users, use the `delete_*` methods instead of `set_*`.
+fn ensure_not_empty(path: &str, len: usize) -> Result<()> {
+    if len == 0 {
+        return Err(ErrorKind::EmptyPayload { path: path.into() }.into());
+    }
+
+    Ok(())
+}
+
+/// Fail with [`ErrorKind::DestructiveOperationBlocked`] unless destructive operations are `allowed`.
+fn ensure_destructive_allowed(allowed: bool, operation: &'static str) -> Result<()> {
+    if !allowed {
+        return Err(ErrorKind::DestructiveOperationBlocked { operation }.into());
+    }
+
+    Ok(())
+}
+
+/// Fail with [`ErrorKind::EmptyPayload`] if `bulk_request` has no users, or neither methods nor products.
+fn ensure_bulk_not_empty(path: &str, bulk_request: &BulkRequest) -> Result<()> {
+    ensure_not_empty(
+        path,
+        bulk_request.user_ids.len() + bulk_request.user_chain_ids.len(),
+    )?;
+    ensure_not_empty(
+        path,
+        bulk_request.method_ids.len() + bulk_request.product_ids.len(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BasispoortId;
+
+    #[test]
+    fn rejects_empty_bulk_requests() {
+        let mut bulk_request = BulkRequest {
+            method_ids: vec!["method".into()],
+            product_ids: vec![],
+            user_ids: vec![],
+            user_chain_ids: vec![],
+        };
+        assert!(matches!(
+            ensure_bulk_not_empty("permissions/grant", &bulk_request).unwrap_err().kind(),
+            ErrorKind::EmptyPayload { path } if path == "permissions/grant"
+        ));
+
+        bulk_request.user_ids.push(BasispoortId::from_u32(1));
+        assert!(ensure_bulk_not_empty("permissions/grant", &bulk_request).is_ok());
+
+        bulk_request.method_ids.clear();
+        assert!(ensure_bulk_not_empty("permissions/grant", &bulk_request).is_err());
+    }
+
+    #[test]
+    fn blocks_destructive_operations_unless_allowed() {
+        assert!(matches!(
+            ensure_destructive_allowed(false, "delete_method")
+                .unwrap_err()
+                .kind(),
+            ErrorKind::DestructiveOperationBlocked {
+                operation: "delete_method"
+            }
+        ));
+        assert!(ensure_destructive_allowed(true, "delete_method").is_ok());
+    }
 }
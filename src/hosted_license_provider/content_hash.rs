@@ -0,0 +1,151 @@
+use std::collections::HashSet;
+
+use crate::Url;
+
+use super::model::{ApplicationTag, MethodDetails, ProductDetails};
+
+/// A content hash, stable across processes, platforms and releases of this crate.
+///
+/// Hashes may be persisted, e.g. to skip updating catalog entities which have not changed since the last sync.
+/// Field order is normalized: tags are hashed in sorted order, and the icon by its digest.
+pub trait ContentHash {
+    fn content_hash(&self) -> u64;
+}
+
+/// Whether `local` differs from `remote`, comparing their [content hashes](ContentHash).
+pub fn has_drifted<T: ContentHash>(local: &T, remote: &T) -> bool {
+    local.content_hash() != remote.content_hash()
+}
+
+impl ContentHash for MethodDetails {
+    fn content_hash(&self) -> u64 {
+        hash_application(
+            &self.id,
+            self.code.as_deref(),
+            &self.name,
+            self.icon.as_deref(),
+            self.icon_url.as_ref(),
+            self.url.as_ref(),
+            &self.tags,
+        )
+    }
+}
+
+impl ContentHash for ProductDetails {
+    fn content_hash(&self) -> u64 {
+        hash_application(
+            &self.id,
+            self.code.as_deref(),
+            &self.name,
+            self.icon.as_deref(),
+            self.icon_url.as_ref(),
+            Some(&self.url),
+            &self.tags,
+        )
+    }
+}
+
+fn hash_application(
+    id: &str,
+    code: Option<&str>,
+    name: &str,
+    icon: Option<&str>,
+    icon_url: Option<&Url>,
+    url: Option<&Url>,
+    tags: &HashSet<ApplicationTag>,
+) -> u64 {
+    let mut tags: Vec<_> = tags.iter().map(ApplicationTag::as_str).collect();
+    tags.sort_unstable();
+
+    let mut hasher = Fnv1a::new();
+    hasher.write_str(Some(id));
+    hasher.write_str(code);
+    hasher.write_str(Some(name));
+    hasher.write_str(icon.map(digest).map(|digest| digest.to_string()).as_deref());
+    hasher.write_str(icon_url.map(Url::as_str));
+    hasher.write_str(url.map(Url::as_str));
+    for tag in tags {
+        hasher.write_str(Some(tag));
+    }
+
+    hasher.finish()
+}
+
+/// The stable digest of `data`, e.g. an icon.
+pub(crate) fn digest(data: &str) -> u64 {
+    let mut hasher = Fnv1a::new();
+    hasher.write(data.as_bytes());
+    hasher.finish()
+}
+
+/// 64-bit FNV-1a, a simple hash with a fixed specification. `std`'s `DefaultHasher` may change between releases.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    /// Write an optional string, length-prefixed, so adjacent fields cannot run into each other.
+    fn write_str(&mut self, value: Option<&str>) {
+        match value {
+            Some(value) => {
+                self.write(&[1]);
+                self.write(&(value.len() as u64).to_le_bytes());
+                self.write(value.as_bytes());
+            }
+            None => self.write(&[0]),
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_stably_regardless_of_tag_order() {
+        let method = MethodDetails::new("method", "Method")
+            .with_icon_bytes(b"<svg/>", "image/svg+xml")
+            .unwrap()
+            .into_teacher_application();
+        let mut tags_reversed = MethodDetails::new("method", "Method")
+            .with_icon_bytes(b"<svg/>", "image/svg+xml")
+            .unwrap();
+        tags_reversed.tags = [
+            ApplicationTag::TestApplication,
+            ApplicationTag::TeacherApplication,
+        ]
+        .into();
+        let mut tags_sorted = MethodDetails::new("method", "Method")
+            .with_icon_bytes(b"<svg/>", "image/svg+xml")
+            .unwrap();
+        tags_sorted.tags = [
+            ApplicationTag::TeacherApplication,
+            ApplicationTag::TestApplication,
+        ]
+        .into();
+
+        assert!(!has_drifted(&tags_reversed, &tags_sorted));
+        assert!(has_drifted(&method, &tags_sorted));
+        assert!(has_drifted(
+            &method,
+            &MethodDetails::new("method", "Method").into_teacher_application()
+        ));
+
+        // Hashes must not change between releases, as they may be persisted.
+        assert_eq!(digest(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(digest("a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
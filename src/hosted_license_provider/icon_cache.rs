@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use super::content_hash::digest;
+
+/// Digests of the icons stored by Basispoort, per method and product, to skip re-uploading unchanged icons.
+///
+/// Pass the cache to [`HostedLicenseProviderClient::update_method_with_icon_cache`][super::HostedLicenseProviderClient::update_method_with_icon_cache]
+/// and [`HostedLicenseProviderClient::update_product_with_icon_cache`][super::HostedLicenseProviderClient::update_product_with_icon_cache].
+/// The remote icon is fetched once per method or product, and the cache is kept up to date with each upload.
+/// Clones share their digests.
+#[derive(Debug, Clone, Default)]
+pub struct IconCache {
+    /// Icon digest by method or product key. `None` if no icon is stored.
+    digests: Arc<Mutex<HashMap<String, Option<u64>>>>,
+}
+
+impl IconCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget all digests, e.g. after icons were changed by another process.
+    pub fn clear(&self) {
+        self.digests().clear();
+    }
+
+    /// The digest of the stored icon, if cached. `Some(None)` if no icon is stored.
+    pub(crate) fn get(&self, key: &str) -> Option<Option<u64>> {
+        self.digests().get(key).copied()
+    }
+
+    pub(crate) fn insert(&self, key: String, icon: Option<&str>) {
+        self.digests().insert(key, icon.map(digest));
+    }
+
+    /// Whether `icon` equals the stored icon for `key`, according to the cache.
+    pub(crate) fn is_unchanged(&self, key: &str, icon: Option<&str>) -> bool {
+        icon.is_some() && self.get(key) == Some(icon.map(digest))
+    }
+
+    fn digests(&self) -> std::sync::MutexGuard<'_, HashMap<String, Option<u64>>> {
+        self.digests
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_unchanged_icons() {
+        let cache = IconCache::new();
+        assert!(!cache.is_unchanged("methode/1", Some("image/png,AAAA")));
+
+        cache.insert("methode/1".into(), Some("image/png,AAAA"));
+        cache.insert("methode/2".into(), None);
+        assert!(cache.is_unchanged("methode/1", Some("image/png,AAAA")));
+        assert!(!cache.is_unchanged("methode/1", Some("image/png,BBBB")));
+        assert!(!cache.is_unchanged("methode/1", None));
+        assert!(!cache.is_unchanged("methode/2", None));
+
+        cache.clear();
+        assert_eq!(cache.get("methode/1"), None);
+    }
+}
@@ -0,0 +1,181 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::error::ErrorKind;
+
+/// The maximum length of an ID, in characters.
+///
+/// Basispoort does not document a limit; this rejects only IDs no real method, product or publisher uses.
+pub const MAX_ID_LENGTH: usize = 255;
+
+/// [`MethodId`], [`ProductId`] or [`IdentityCode`] construction or parse error.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseIdError {
+    #[error("the {kind} is empty")]
+    Empty { kind: &'static str },
+
+    #[error("the {kind} '{id}' is longer than {MAX_ID_LENGTH} characters")]
+    TooLong { kind: &'static str, id: String },
+
+    #[error("the {kind} '{id}' contains the character {character:?}, which is not allowed in a URL path segment")]
+    InvalidCharacter {
+        kind: &'static str,
+        id: String,
+        character: char,
+    },
+}
+
+/// Check that `id` is usable as a single URL path segment.
+pub(super) fn validate(kind: &'static str, id: &str) -> Result<(), ParseIdError> {
+    if id.is_empty() {
+        return Err(ParseIdError::Empty { kind });
+    }
+
+    if id.chars().count() > MAX_ID_LENGTH {
+        return Err(ParseIdError::TooLong {
+            kind,
+            id: id.into(),
+        });
+    }
+
+    if let Some(character) = id.chars().find(|&character| {
+        matches!(character, '/' | '\\' | '?' | '#' | '%')
+            || character.is_whitespace()
+            || character.is_control()
+    }) {
+        return Err(ParseIdError::InvalidCharacter {
+            kind,
+            id: id.into(),
+            character,
+        });
+    }
+
+    Ok(())
+}
+
+/// Define a validated string ID type, named `$name` and described as `$kind` in errors.
+macro_rules! validated_id {
+    ($(#[$attr:meta])* $name:ident, $kind:literal) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+        #[serde(try_from = "String", into = "String")]
+        pub struct $name(String);
+
+        impl $name {
+            /// Create the ID, failing if it is empty, too long, or contains characters which would alter the URL path.
+            pub fn new(id: impl Into<String>) -> Result<Self, ParseIdError> {
+                let id = id.into();
+                validate($kind, &id)?;
+
+                Ok(Self(id))
+            }
+
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            /// Validate a raw `id` passed to a client method, failing with [`ErrorKind::InvalidId`].
+            pub(crate) fn check(id: &str) -> crate::Result<&str> {
+                validate($kind, id).map_err(ErrorKind::InvalidId)?;
+
+                Ok(id)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ParseIdError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::new(s)
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = ParseIdError;
+
+            fn try_from(id: String) -> Result<Self, Self::Error> {
+                Self::new(id)
+            }
+        }
+
+        impl TryFrom<&str> for $name {
+            type Error = ParseIdError;
+
+            fn try_from(id: &str) -> Result<Self, Self::Error> {
+                Self::new(id)
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+    };
+}
+
+validated_id!(
+    /// The ID of a method, as chosen by the publisher. Accepted by all client methods taking a method ID.
+    MethodId,
+    "method ID"
+);
+
+validated_id!(
+    /// The ID of a product, as chosen by the publisher. Accepted by all client methods taking a product ID.
+    ProductId,
+    "product ID"
+);
+
+validated_id!(
+    /// The publisher's identity code for the hosted license provider service.
+    /// Accepted by [`HostedLicenseProviderClient::new`][super::HostedLicenseProviderClient::new].
+    IdentityCode,
+    "identity code"
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_ids_breaking_the_url_path() {
+        assert_eq!(
+            MethodId::new("my-method_2").unwrap().as_str(),
+            "my-method_2"
+        );
+        assert_eq!(
+            "".parse::<ProductId>(),
+            Err(ParseIdError::Empty { kind: "product ID" })
+        );
+        assert_eq!(
+            MethodId::new("a/b"),
+            Err(ParseIdError::InvalidCharacter {
+                kind: "method ID",
+                id: "a/b".into(),
+                character: '/',
+            })
+        );
+        assert!(IdentityCode::new("x".repeat(MAX_ID_LENGTH + 1)).is_err());
+        assert!(serde_json::from_str::<MethodId>(r#""a b""#).is_err());
+
+        assert!(matches!(
+            MethodId::check("methode?x").unwrap_err().kind(),
+            ErrorKind::InvalidId(ParseIdError::InvalidCharacter { character: '?', .. })
+        ));
+    }
+}
@@ -1,49 +1,81 @@
-use std::collections::HashSet;
-use std::fmt::Debug;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::num::NonZeroUsize;
 use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
 
 use base64::{engine::general_purpose::STANDARD as base64, Engine as _};
+use futures_lite::{AsyncRead, AsyncReadExt};
 use serde::{Deserialize, Serialize};
-use tokio::{fs::File, io::AsyncReadExt};
+use thiserror::Error;
 
-use crate::error::Error;
-use crate::{BasispoortId, Result};
+use super::ids;
+use crate::error::ErrorKind;
+use crate::redact::{ListSummary, Redacted, Truncated};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::runtime::{self, ReadFileError};
+use crate::{BasispoortId, IntoUrl, Result, Url};
 
 #[derive(Debug, Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct MethodDetailsList {
     #[serde(rename = "methodes")]
     pub methods: Vec<MethodDetails>,
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+/// Method list, deserializing only the IDs, skipping the (icon) details.
+#[derive(Debug, Deserialize)]
+pub(crate) struct MethodIdList {
+    #[serde(rename = "methodes")]
+    pub(crate) methods: Vec<ApplicationId>,
+}
+
+/// Product list, deserializing only the IDs, skipping the (icon) details.
+#[derive(Debug, Deserialize)]
+pub(crate) struct ProductIdList {
+    #[serde(rename = "producten")]
+    pub(crate) products: Vec<ApplicationId>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ApplicationId {
+    pub(crate) id: String,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
+#[non_exhaustive]
 pub struct MethodDetails {
     pub id: String,
     pub code: Option<String>,
     #[serde(rename = "naam")]
     pub name: String,
     pub icon: Option<String>,
-    pub icon_url: Option<crate::Url>,
-    pub url: Option<crate::Url>,
+    pub icon_url: Option<Url>,
+    pub url: Option<Url>,
     pub tags: HashSet<ApplicationTag>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct ProductDetailsList {
     #[serde(rename = "producten")]
     pub products: Vec<ProductDetails>,
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
+#[non_exhaustive]
 pub struct ProductDetails {
     pub id: String,
     pub code: Option<String>,
     #[serde(rename = "naam")]
     pub name: String,
     pub icon: Option<String>,
-    pub icon_url: Option<crate::Url>,
-    pub url: crate::Url,
+    pub icon_url: Option<Url>,
+    pub url: Url,
     pub tags: HashSet<ApplicationTag>,
 }
 
@@ -55,27 +87,31 @@ pub enum ApplicationTag {
     TestApplication,
 }
 
-#[derive(Debug, Default, Deserialize, Serialize)]
+#[derive(Default, Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct UserIdList {
     #[serde(rename = "gebruikers")]
     pub users: Vec<BasispoortId>,
 }
 
-#[derive(Debug, Default, Deserialize, Serialize)]
+#[derive(Default, Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct UserChainIdList {
     #[serde(rename = "gebruikers")]
     pub users: Vec<UserChainId>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct UserChainId {
-    #[serde(rename = "instellingId")]
+    #[serde(rename = "instellingId", alias = "instellingid")]
     pub institution_id: BasispoortId,
-    #[serde(rename = "eckId")]
+    #[serde(rename = "eckId", alias = "eckid")]
     pub chain_id: String,
 }
 
-#[derive(Debug, Default, Deserialize, Serialize)]
+#[derive(Default, Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct BulkRequest {
     #[serde(rename = "methodes")]
     pub method_ids: Vec<String>,
@@ -87,9 +123,164 @@ pub struct BulkRequest {
     pub user_chain_ids: Vec<UserChainId>,
 }
 
+/// A user's access to a method, or to one of its products, as listed by
+/// [`HostedLicenseProviderClient::get_license_assignments`][super::HostedLicenseProviderClient::get_license_assignments].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[non_exhaustive]
+pub struct LicenseAssignment {
+    pub method_id: String,
+    /// The product, or `None` for access to the method itself.
+    pub product_id: Option<String>,
+    pub user_id: BasispoortId,
+}
+
+/// Users whose access to a method and to its products disagree, as found by
+/// [`HostedLicenseProviderClient::find_inconsistent_assignments`][super::HostedLicenseProviderClient::find_inconsistent_assignments].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct InconsistentAssignments {
+    pub method_id: String,
+    /// Per product, the users with access to the product, but not to the method. Consistent products are omitted.
+    pub product_users_without_method: BTreeMap<String, BTreeSet<BasispoortId>>,
+    /// The users with access to the method, but to none of its products. Empty if the method has no products.
+    pub method_users_without_product: BTreeSet<BasispoortId>,
+}
+
+/// The changes made by
+/// [`HostedLicenseProviderClient::sync_method_user_ids`][super::HostedLicenseProviderClient::sync_method_user_ids]
+/// and [`HostedLicenseProviderClient::sync_product_user_ids`][super::HostedLicenseProviderClient::sync_product_user_ids].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct UserIdSync {
+    /// Users granted access, in ascending order.
+    pub added: Vec<BasispoortId>,
+    /// Users whose access was revoked, in ascending order.
+    pub removed: Vec<BasispoortId>,
+    /// The number of users who kept their access.
+    pub unchanged: usize,
+}
+
 // == Implementations ==
 
+// Debug implementations truncate icons, summarize ID lists and redact chain IDs (personal data).
+
+impl Debug for MethodDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MethodDetails")
+            .field("id", &self.id)
+            .field("code", &self.code)
+            .field("name", &self.name)
+            .field("icon", &self.icon.as_deref().map(Truncated))
+            .field("icon_url", &self.icon_url)
+            .field("url", &self.url)
+            .field("tags", &self.tags)
+            .finish()
+    }
+}
+
+impl Debug for ProductDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProductDetails")
+            .field("id", &self.id)
+            .field("code", &self.code)
+            .field("name", &self.name)
+            .field("icon", &self.icon.as_deref().map(Truncated))
+            .field("icon_url", &self.icon_url)
+            .field("url", &self.url)
+            .field("tags", &self.tags)
+            .finish()
+    }
+}
+
+impl Debug for UserIdList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserIdList")
+            .field("users", &ListSummary(&self.users))
+            .finish()
+    }
+}
+
+impl Debug for UserChainIdList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserChainIdList")
+            .field("users", &ListSummary(&self.users))
+            .finish()
+    }
+}
+
+impl Debug for UserChainId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserChainId")
+            .field("institution_id", &self.institution_id)
+            .field("chain_id", &Redacted)
+            .finish()
+    }
+}
+
+impl Debug for BulkRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BulkRequest")
+            .field("method_ids", &ListSummary(&self.method_ids))
+            .field("product_ids", &ListSummary(&self.product_ids))
+            .field("user_ids", &ListSummary(&self.user_ids))
+            .field("user_chain_ids", &ListSummary(&self.user_chain_ids))
+            .finish()
+    }
+}
+
+impl ApplicationTag {
+    /// The tag's name, as used by the Basispoort API.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TeacherApplication => "leerkrachtApplicatie",
+            Self::TestApplication => "toetsApplicatie",
+        }
+    }
+}
+
+impl fmt::Display for ApplicationTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// [`ApplicationTag`] parse error.
+#[derive(Error, Debug)]
+pub enum ParseApplicationTagError {
+    #[error("'{0}' is not a valid application tag string")]
+    InvalidApplicationTagString(String),
+}
+
+impl FromStr for ApplicationTag {
+    type Err = ParseApplicationTagError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match s {
+            "leerkrachtApplicatie" => Self::TeacherApplication,
+            "toetsApplicatie" => Self::TestApplication,
+            s => {
+                return Err(ParseApplicationTagError::InvalidApplicationTagString(
+                    s.into(),
+                ))
+            }
+        })
+    }
+}
+
 impl MethodDetails {
+    /// A copy without the icon, to update the method while keeping its stored icon.
+    pub(crate) fn without_icon(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            code: self.code.clone(),
+            name: self.name.clone(),
+            icon: None,
+            icon_url: self.icon_url.clone(),
+            url: self.url.clone(),
+            tags: self.tags.clone(),
+        }
+    }
+
     /// Create a new `MethodDetails`.
     pub fn new(id: impl Into<String>, name: impl Into<String>) -> Self {
         Self {
@@ -120,31 +311,121 @@ impl MethodDetails {
         }
     }
 
+    /// Encode the icon `bytes` as base64, prefixed by `mime_type` (e.g. `image/png`),
+    /// then return a new `MethodDetails` with the provided icon.
+    ///
+    /// The icon is fitted into the [default limits][Limits::default], as [`Self::with_icon_bytes_within`] does.
+    pub fn with_icon_bytes(self, bytes: impl AsRef<[u8]>, mime_type: &str) -> Result<Self> {
+        self.with_icon_bytes_within(bytes, mime_type, &Limits::default())
+    }
+
+    /// Encode the icon `bytes` as [`Self::with_icon_bytes`] does, fitting it into `limits`.
+    ///
+    /// With the `image` feature, PNG icons larger than [`Limits::max_icon_dimension`] are downscaled,
+    /// and malformed PNGs fail with `ErrorKind::DownscaleIcon`.
+    /// Fails with [`ErrorKind::LimitExceeded`] if the icon, once downscaled, is larger than [`Limits::max_icon_bytes`],
+    /// rather than with the opaque `400 Bad Request` Basispoort responds with.
+    pub fn with_icon_bytes_within(
+        self,
+        bytes: impl AsRef<[u8]>,
+        mime_type: &str,
+        limits: &Limits,
+    ) -> Result<Self> {
+        Ok(self.with_icon(encode_icon_within(
+            bytes.as_ref().to_vec(),
+            Some(mime_type),
+            limits,
+        )?))
+    }
+
     /// Read the icon from the provided file, then return a new `MethodDetails` with the provided icon.
+    ///
+    /// The icon is fitted into the [default limits][Limits::default], as [`Self::with_icon_from_file_within`] does.
+    /// Not available on `wasm32` targets, which have no file system.
+    #[cfg(not(target_arch = "wasm32"))]
     pub async fn with_icon_from_file(self, path: &Path) -> Result<Self> {
-        Ok(self.with_icon(icon_from_file(path).await?))
+        self.with_icon_from_file_within(path, &Limits::default())
+            .await
+    }
+
+    /// Read the icon from the provided file as [`Self::with_icon_from_file`] does, fitting it into `limits`
+    /// as [`Self::with_icon_bytes_within`] does.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn with_icon_from_file_within(self, path: &Path, limits: &Limits) -> Result<Self> {
+        Ok(self.with_icon(icon_from_file(path, limits).await?))
+    }
+
+    /// Read the icon in the given format from `reader`, then return a new `MethodDetails` with the provided icon.
+    ///
+    /// Use this for icons from non-file sources, such as object storage streams or embedded assets.
+    /// Tokio readers can be adapted using `tokio_util::compat`. The icon is fitted into the
+    /// [default limits][Limits::default], as [`Self::with_icon_from_reader_within`] does.
+    pub async fn with_icon_from_reader(
+        self,
+        reader: impl AsyncRead + Unpin,
+        format: IconFormat,
+    ) -> Result<Self> {
+        self.with_icon_from_reader_within(reader, format, &Limits::default())
+            .await
+    }
+
+    /// Read the icon from `reader` as [`Self::with_icon_from_reader`] does, fitting it into `limits`
+    /// as [`Self::with_icon_bytes_within`] does.
+    ///
+    /// Reading stops as soon as the icon exceeds [`Limits::max_icon_bytes`], unless it is a PNG to be downscaled.
+    pub async fn with_icon_from_reader_within(
+        self,
+        reader: impl AsyncRead + Unpin,
+        format: IconFormat,
+        limits: &Limits,
+    ) -> Result<Self> {
+        Ok(self.with_icon(icon_from_reader(reader, format, limits).await?))
+    }
+
+    /// Download the icon from `url`, then return a new `MethodDetails` with the provided icon.
+    ///
+    /// The format is determined by the `Content-Type` response header, or else by the file extension of the URL path.
+    /// Use this for icons hosted elsewhere, e.g. on a CDN, so they do not have to be staged on disk first.
+    /// The icon is downloaded using `client`, usually the [`RestClient::download_client`][crate::rest::RestClient::download_client],
+    /// so the download passes the configured proxy. It is fitted into the [default limits][Limits::default],
+    /// as [`Self::with_icon_from_url_within`] does.
+    pub async fn with_icon_from_url(
+        self,
+        client: &reqwest::Client,
+        url: impl IntoUrl,
+    ) -> Result<Self> {
+        self.with_icon_from_url_within(client, url, &Limits::default())
+            .await
+    }
+
+    /// Download the icon from `url` as [`Self::with_icon_from_url`] does, fitting it into `limits`
+    /// as [`Self::with_icon_bytes_within`] does.
+    ///
+    /// The download times out after 30 seconds. Unless the icon is a PNG to be downscaled, it is refused
+    /// if the announced `Content-Length` exceeds [`Limits::max_icon_bytes`], and otherwise stops as soon as
+    /// the received icon does.
+    /// On `wasm32`, where the browser receives the body, the size is checked after the download.
+    pub async fn with_icon_from_url_within(
+        self,
+        client: &reqwest::Client,
+        url: impl IntoUrl,
+        limits: &Limits,
+    ) -> Result<Self> {
+        Ok(self.with_icon(icon_from_url(client, url, limits).await?))
     }
 
     /// Return a new `MethodeDetails` with the provided icon URL.
-    pub fn with_icon_url(self, icon_url: &str) -> Result<Self> {
+    pub fn with_icon_url(self, icon_url: impl IntoUrl) -> Result<Self> {
         Ok(Self {
-            icon_url: Some(
-                crate::Url::parse(icon_url).map_err(|source| Error::ParseUrl {
-                    url: icon_url.to_string(),
-                    source,
-                })?,
-            ),
+            icon_url: Some(icon_url.into_url()?),
             ..self
         })
     }
 
     /// Return a new `MethodDetails` with the provided URL.
-    pub fn with_url(self, url: &str) -> Result<Self> {
+    pub fn with_url(self, url: impl IntoUrl) -> Result<Self> {
         Ok(Self {
-            url: Some(crate::Url::parse(url).map_err(|source| Error::ParseUrl {
-                url: url.to_string(),
-                source,
-            })?),
+            url: Some(url.into_url()?),
             ..self
         })
     }
@@ -167,18 +448,28 @@ impl MethodDetails {
 }
 
 impl ProductDetails {
+    /// A copy without the icon, to update the product while keeping its stored icon.
+    pub(crate) fn without_icon(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            code: self.code.clone(),
+            name: self.name.clone(),
+            icon: None,
+            icon_url: self.icon_url.clone(),
+            url: self.url.clone(),
+            tags: self.tags.clone(),
+        }
+    }
+
     /// Create a new `ProductDetails`. Other than with `MethodDetails`, the `url` field is obligatory.
-    pub fn new(id: impl Into<String>, name: impl Into<String>, url: &str) -> Result<Self> {
+    pub fn new(id: impl Into<String>, name: impl Into<String>, url: impl IntoUrl) -> Result<Self> {
         Ok(Self {
             id: id.into(),
             code: None,
             name: name.into(),
             icon: None,
             icon_url: None,
-            url: crate::Url::parse(url).map_err(|source| Error::ParseUrl {
-                url: url.to_string(),
-                source,
-            })?,
+            url: url.into_url()?,
             // There is currently only one possible tag.
             tags: HashSet::with_capacity(1),
         })
@@ -200,20 +491,102 @@ impl ProductDetails {
         }
     }
 
+    /// Encode the icon `bytes` as base64, prefixed by `mime_type` (e.g. `image/png`),
+    /// then return a new `ProductDetails` with the provided icon.
+    ///
+    /// The icon is fitted into the [default limits][Limits::default], as [`Self::with_icon_bytes_within`] does.
+    pub fn with_icon_bytes(self, bytes: impl AsRef<[u8]>, mime_type: &str) -> Result<Self> {
+        self.with_icon_bytes_within(bytes, mime_type, &Limits::default())
+    }
+
+    /// Encode the icon `bytes`, fitting it into `limits`, as [`MethodDetails::with_icon_bytes_within`] does.
+    pub fn with_icon_bytes_within(
+        self,
+        bytes: impl AsRef<[u8]>,
+        mime_type: &str,
+        limits: &Limits,
+    ) -> Result<Self> {
+        Ok(self.with_icon(encode_icon_within(
+            bytes.as_ref().to_vec(),
+            Some(mime_type),
+            limits,
+        )?))
+    }
+
     /// Read the icon from the provided file, then return a new `ProductDetails` with the added icon.
+    ///
+    /// The icon is fitted into the [default limits][Limits::default], as [`Self::with_icon_from_file_within`] does.
+    /// Not available on `wasm32` targets, which have no file system.
+    #[cfg(not(target_arch = "wasm32"))]
     pub async fn with_icon_from_file(self, path: &Path) -> Result<Self> {
-        Ok(self.with_icon(icon_from_file(path).await?))
+        self.with_icon_from_file_within(path, &Limits::default())
+            .await
+    }
+
+    /// Read the icon from the provided file, fitting it into `limits`,
+    /// as [`MethodDetails::with_icon_from_file_within`] does.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn with_icon_from_file_within(self, path: &Path, limits: &Limits) -> Result<Self> {
+        Ok(self.with_icon(icon_from_file(path, limits).await?))
+    }
+
+    /// Read the icon in the given format from `reader`, then return a new `ProductDetails` with the provided icon.
+    ///
+    /// Use this for icons from non-file sources, such as object storage streams or embedded assets.
+    /// Tokio readers can be adapted using `tokio_util::compat`. The icon is fitted into the
+    /// [default limits][Limits::default], as [`Self::with_icon_from_reader_within`] does.
+    pub async fn with_icon_from_reader(
+        self,
+        reader: impl AsyncRead + Unpin,
+        format: IconFormat,
+    ) -> Result<Self> {
+        self.with_icon_from_reader_within(reader, format, &Limits::default())
+            .await
+    }
+
+    /// Read the icon from `reader` as [`Self::with_icon_from_reader`] does, fitting it into `limits`
+    /// as [`MethodDetails::with_icon_bytes_within`] does.
+    ///
+    /// Reading stops as soon as the icon exceeds [`Limits::max_icon_bytes`], unless it is a PNG to be downscaled.
+    pub async fn with_icon_from_reader_within(
+        self,
+        reader: impl AsyncRead + Unpin,
+        format: IconFormat,
+        limits: &Limits,
+    ) -> Result<Self> {
+        Ok(self.with_icon(icon_from_reader(reader, format, limits).await?))
+    }
+
+    /// Download the icon from `url`, then return a new `ProductDetails` with the provided icon.
+    ///
+    /// The format is determined by the `Content-Type` response header, or else by the file extension of the URL path.
+    /// Use this for icons hosted elsewhere, e.g. on a CDN, so they do not have to be staged on disk first.
+    /// The icon is downloaded using `client`, usually the [`RestClient::download_client`][crate::rest::RestClient::download_client],
+    /// so the download passes the configured proxy. It is fitted into the [default limits][Limits::default],
+    /// as [`Self::with_icon_from_url_within`] does.
+    pub async fn with_icon_from_url(
+        self,
+        client: &reqwest::Client,
+        url: impl IntoUrl,
+    ) -> Result<Self> {
+        self.with_icon_from_url_within(client, url, &Limits::default())
+            .await
+    }
+
+    /// Download the icon from `url`, fitting it into `limits`, as [`MethodDetails::with_icon_from_url_within`] does.
+    pub async fn with_icon_from_url_within(
+        self,
+        client: &reqwest::Client,
+        url: impl IntoUrl,
+        limits: &Limits,
+    ) -> Result<Self> {
+        Ok(self.with_icon(icon_from_url(client, url, limits).await?))
     }
 
     /// Return a new `ProductDetails` with the provided icon URL.
-    pub fn with_icon_url(self, icon_url: &str) -> Result<Self> {
+    pub fn with_icon_url(self, icon_url: impl IntoUrl) -> Result<Self> {
         Ok(Self {
-            icon_url: Some(
-                crate::Url::parse(icon_url).map_err(|source| Error::ParseUrl {
-                    url: icon_url.to_string(),
-                    source,
-                })?,
-            ),
+            icon_url: Some(icon_url.into_url()?),
             ..self
         })
     }
@@ -235,32 +608,708 @@ impl ProductDetails {
     }
 }
 
-/// Read an icon from file, encode it as base64 string and optionally prefix it by mime type.
-async fn icon_from_file(path: &Path) -> Result<String> {
-    let mut icon_data = Vec::new();
-    File::open(path)
-        .await
-        .map_err(|source| Error::OpenIconFile {
-            path: path.to_owned(),
-            source,
-        })?
-        .read_to_end(&mut icon_data)
+impl UserChainId {
+    pub fn new(institution_id: BasispoortId, chain_id: impl Into<String>) -> Self {
+        Self {
+            institution_id,
+            chain_id: chain_id.into(),
+        }
+    }
+}
+
+impl BulkRequest {
+    /// Create an empty `BulkRequest`, to be filled using the `with_*` methods.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return a new `BulkRequest` for the provided methods.
+    pub fn with_method_ids(self, method_ids: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            method_ids: method_ids.into_iter().map(Into::into).collect(),
+            ..self
+        }
+    }
+
+    /// Return a new `BulkRequest` for the provided products.
+    pub fn with_product_ids(
+        self,
+        product_ids: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Self {
+            product_ids: product_ids.into_iter().map(Into::into).collect(),
+            ..self
+        }
+    }
+
+    /// Return a new `BulkRequest` for the provided users.
+    pub fn with_user_ids(self, user_ids: impl IntoIterator<Item = BasispoortId>) -> Self {
+        Self {
+            user_ids: user_ids.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Return a new `BulkRequest` for the provided users, identified by chain ID.
+    pub fn with_user_chain_ids(
+        self,
+        user_chain_ids: impl IntoIterator<Item = UserChainId>,
+    ) -> Self {
+        Self {
+            user_chain_ids: user_chain_ids.into_iter().collect(),
+            ..self
+        }
+    }
+}
+
+/// The image format of an icon, determining its mime type prefix.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum IconFormat {
+    Svg,
+    Png,
+    /// Unknown format, encoded without mime type prefix.
+    Unknown,
+}
+
+impl IconFormat {
+    /// Determine the icon format from the file extension of `path`.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("svg") => Self::Svg,
+            Some("png") => Self::Png,
+            Some(_) | None => Self::Unknown,
+        }
+    }
+
+    /// Determine the icon format from `mime_type`, e.g. a `Content-Type` header value, ignoring its parameters.
+    pub fn from_mime_type(mime_type: &str) -> Self {
+        let essence = mime_type.split(';').next().unwrap_or_default().trim();
+        match essence.to_ascii_lowercase().as_str() {
+            "image/svg+xml" => Self::Svg,
+            "image/png" => Self::Png,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// The mime type of the format, if known.
+    pub fn mime_type(&self) -> Option<&'static str> {
+        match self {
+            Self::Svg => Some("image/svg+xml"),
+            Self::Png => Some("image/png"),
+            Self::Unknown => None,
+        }
+    }
+}
+
+/// Read an icon from file, fit it into `limits`, encode it as base64 string and optionally prefix it by mime type.
+#[cfg(not(target_arch = "wasm32"))]
+async fn icon_from_file(path: &Path, limits: &Limits) -> Result<String> {
+    let icon_data = runtime::read_file(path)
         .await
-        .map_err(|source| Error::ReadIconFile {
-            path: path.to_owned(),
-            source,
+        .map_err(|error| match error {
+            ReadFileError::Open(source) => ErrorKind::OpenIconFile {
+                path: path.to_owned(),
+                source,
+            },
+            ReadFileError::Read(source) => ErrorKind::ReadIconFile {
+                path: path.to_owned(),
+                source,
+            },
         })?;
 
-    let mime_type_prefix = match path.extension() {
-        Some(ext) => match ext.to_str() {
-            Some("svg") => "image/svg+xml,",
-            Some("png") => "image/png,",
-            Some(_) | None => "",
-        },
-        None => "",
+    encode_icon_within(icon_data, IconFormat::from_path(path).mime_type(), limits)
+}
+
+/// Encode an icon as [`encode_icon`] does, after fitting it into `limits`: PNGs larger than
+/// [`Limits::max_icon_dimension`] are downscaled, if the `image` feature is enabled, and icons which are then
+/// still larger than [`Limits::max_icon_bytes`] are rejected.
+///
+/// Every icon constructor goes through here, so all icons are held to the same limits.
+fn encode_icon_within(
+    icon_data: Vec<u8>,
+    mime_type: Option<&str>,
+    limits: &Limits,
+) -> Result<String> {
+    #[cfg(feature = "image")]
+    let icon_data = match (
+        mime_type.map(IconFormat::from_mime_type),
+        limits.max_icon_dimension,
+    ) {
+        (Some(IconFormat::Png), Some(max_dimension)) => downscale_png(icon_data, max_dimension)?,
+        _ => icon_data,
+    };
+
+    check_limit("max_icon_bytes", limits.max_icon_bytes, icon_data.len())?;
+
+    Ok(encode_icon(&icon_data, mime_type))
+}
+
+/// The most bytes to read of an icon in `format`, before [`encode_icon_within`] checks it.
+///
+/// PNGs which may be downscaled are read entirely, as they may only fit into [`Limits::max_icon_bytes`]
+/// once downscaled. Decoding them is bounded by the `image` crate's default allocation limit.
+fn read_limit(format: IconFormat, limits: &Limits) -> Option<usize> {
+    let downscaled =
+        cfg!(feature = "image") && format == IconFormat::Png && limits.max_icon_dimension.is_some();
+    if downscaled {
+        None
+    } else {
+        limits.max_icon_bytes
+    }
+}
+
+/// Downscale a PNG to fit into `max_dimension` by `max_dimension` pixels, keeping its aspect ratio.
+///
+/// PNGs which already fit are returned unchanged, rather than re-encoded.
+#[cfg(feature = "image")]
+fn downscale_png(png: Vec<u8>, max_dimension: u32) -> Result<Vec<u8>> {
+    use image::{imageops::FilterType, ImageFormat};
+
+    let image = image::load_from_memory_with_format(&png, ImageFormat::Png)
+        .map_err(ErrorKind::DownscaleIcon)?;
+    if image.width() <= max_dimension && image.height() <= max_dimension {
+        return Ok(png);
+    }
+
+    let mut downscaled = Vec::new();
+    image
+        .resize(max_dimension, max_dimension, FilterType::Lanczos3)
+        .write_to(&mut std::io::Cursor::new(&mut downscaled), ImageFormat::Png)
+        .map_err(ErrorKind::DownscaleIcon)?;
+
+    Ok(downscaled)
+}
+
+/// Read an icon from `reader`, fit it into `limits`, encode it as base64 string and optionally prefix it by mime type.
+async fn icon_from_reader(
+    reader: impl AsyncRead + Unpin,
+    format: IconFormat,
+    limits: &Limits,
+) -> Result<String> {
+    let icon_data = read_capped(reader, read_limit(format, limits))
+        .await
+        .map_err(ErrorKind::ReadIcon)?;
+
+    encode_icon_within(icon_data, format.mime_type(), limits)
+}
+
+/// Read `reader` to the end, but at most one byte more than `max_bytes`: enough for [`check_limit`]
+/// to reject an oversized icon, without holding all of it in memory.
+async fn read_capped(
+    mut reader: impl AsyncRead + Unpin,
+    max_bytes: Option<usize>,
+) -> std::io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    match max_bytes {
+        Some(max_bytes) => {
+            (&mut reader)
+                .take(max_bytes as u64 + 1)
+                .read_to_end(&mut data)
+                .await?
+        }
+        None => reader.read_to_end(&mut data).await?,
+    };
+
+    Ok(data)
+}
+
+/// How long to wait for an icon download, body included.
+const ICON_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Download an icon from `url` using `client`, fit it into `limits`, encode it as base64 string and prefix it by
+/// mime type, if known.
+async fn icon_from_url(
+    client: &reqwest::Client,
+    url: impl IntoUrl,
+    limits: &Limits,
+) -> Result<String> {
+    let url = url.into_url()?;
+    let download_error = |source: reqwest::Error| ErrorKind::DownloadIcon {
+        url: url.clone(),
+        source,
+    };
+
+    let response = client
+        .get(url.clone())
+        .timeout(ICON_DOWNLOAD_TIMEOUT)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(download_error)?;
+    let format = match response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map_or(IconFormat::Unknown, IconFormat::from_mime_type)
+    {
+        IconFormat::Unknown => IconFormat::from_path(Path::new(url.path())),
+        format => format,
+    };
+    if let Some(content_length) = response.content_length() {
+        let content_length = usize::try_from(content_length).unwrap_or(usize::MAX);
+        check_limit("max_icon_bytes", read_limit(format, limits), content_length)?;
+    }
+    let icon_data = download_capped(response, read_limit(format, limits))
+        .await
+        .map_err(download_error)?;
+
+    encode_icon_within(icon_data, format.mime_type(), limits)
+}
+
+/// Receive the response body, stopping once it exceeds `max_bytes`, as [`read_capped`] does.
+#[cfg(not(target_arch = "wasm32"))]
+async fn download_capped(
+    mut response: reqwest::Response,
+    max_bytes: Option<usize>,
+) -> reqwest::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        data.extend_from_slice(&chunk);
+        if max_bytes.is_some_and(|max_bytes| data.len() > max_bytes) {
+            break;
+        }
+    }
+
+    Ok(data)
+}
+
+/// Receive the response body. The browser offers no way to stop early, so the size is checked afterwards.
+#[cfg(target_arch = "wasm32")]
+async fn download_capped(
+    response: reqwest::Response,
+    _max_bytes: Option<usize>,
+) -> reqwest::Result<Vec<u8>> {
+    Ok(response.bytes().await?.to_vec())
+}
+
+/// Encode an icon as base64 string, prefixed by its mime type if known.
+fn encode_icon(icon_data: &[u8], mime_type: Option<&str>) -> String {
+    match mime_type {
+        Some(mime_type) => format!("{mime_type},{}", base64.encode(icon_data)),
+        None => base64.encode(icon_data),
+    }
+}
+
+/// Client-side limits on the size of requests, checked by the `validate_with` methods.
+///
+/// [`Limits::default`] accepts every valid [`BasispoortId`], and enforces no size limits, as Basispoort publishes none.
+/// Set the limits documented for your Basispoort environment, and update them when Basispoort changes them,
+/// without waiting for a release of this crate. Set a limit to `None` to lift it again:
+///
+/// ```
+/// use basispoort_sync_client::hosted_license_provider::Limits;
+///
+/// let mut limits = Limits::default()
+///     .with_max_users_per_list(50_000)
+///     .with_max_icon_bytes(512 * 1024);
+/// limits.max_users_per_list = None;
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Limits {
+    /// The maximum number of users per user ID list or bulk request.
+    pub max_users_per_list: Option<usize>,
+    /// The maximum number of methods per bulk request.
+    pub max_methods_per_bulk_request: Option<usize>,
+    /// The maximum number of products per bulk request.
+    pub max_products_per_bulk_request: Option<usize>,
+    /// The maximum size of a decoded icon.
+    pub max_icon_bytes: Option<usize>,
+    /// The maximum width and height of an icon, in pixels. PNG icons passed to any icon constructor,
+    /// such as `with_icon_bytes_within` or `with_icon_from_url_within`, are downscaled to fit,
+    /// if the `image` feature is enabled. Without it, the dimensions are not checked.
+    pub max_icon_dimension: Option<u32>,
+}
+
+impl Limits {
+    pub fn with_max_users_per_list(mut self, max_users_per_list: usize) -> Self {
+        self.max_users_per_list = Some(max_users_per_list);
+        self
+    }
+
+    pub fn with_max_methods_per_bulk_request(
+        mut self,
+        max_methods_per_bulk_request: usize,
+    ) -> Self {
+        self.max_methods_per_bulk_request = Some(max_methods_per_bulk_request);
+        self
+    }
+
+    pub fn with_max_products_per_bulk_request(
+        mut self,
+        max_products_per_bulk_request: usize,
+    ) -> Self {
+        self.max_products_per_bulk_request = Some(max_products_per_bulk_request);
+        self
+    }
+
+    pub fn with_max_icon_bytes(mut self, max_icon_bytes: usize) -> Self {
+        self.max_icon_bytes = Some(max_icon_bytes);
+        self
+    }
+
+    pub fn with_max_icon_dimension(mut self, max_icon_dimension: u32) -> Self {
+        self.max_icon_dimension = Some(max_icon_dimension);
+        self
+    }
+}
+
+/// Fail with [`ErrorKind::LimitExceeded`] if `actual` exceeds `max`.
+fn check_limit(limit: &'static str, max: Option<usize>, actual: usize) -> Result<()> {
+    match max {
+        Some(max) if actual > max => Err(ErrorKind::LimitExceeded { limit, max, actual }.into()),
+        _ => Ok(()),
+    }
+}
+
+/// The decoded size of an icon encoded by [`encode_icon`].
+fn decoded_icon_len(icon: &str) -> usize {
+    let encoded = icon.rsplit_once(',').map_or(icon, |(_, encoded)| encoded);
+    let padding = encoded
+        .bytes()
+        .rev()
+        .take_while(|&byte| byte == b'=')
+        .count();
+
+    (encoded.len() / 4 * 3).saturating_sub(padding)
+}
+
+/// The maximum length of a method or product name, in characters.
+///
+/// Basispoort does not document a limit; like [`MAX_ID_LENGTH`][super::MAX_ID_LENGTH], this rejects only names
+/// no real method or product uses.
+pub const MAX_NAME_LENGTH: usize = 255;
+
+/// A field failing client-side validation, as listed by [`ErrorKind::Validation`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl FieldError {
+    fn new(field: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            field,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Check the fields common to methods and products, failing with [`ErrorKind::Validation`] listing all invalid fields.
+fn validate_application(
+    kind: &'static str,
+    id: &str,
+    name: &str,
+    icon: Option<&str>,
+    urls: [(&'static str, Option<&Url>); 2],
+) -> Result<()> {
+    let mut errors = Vec::new();
+
+    if let Err(error) = ids::validate(kind, id) {
+        errors.push(FieldError::new("id", error.to_string()));
+    }
+
+    if name.trim().is_empty() {
+        errors.push(FieldError::new("name", "must not be blank"));
+    } else if name.chars().count() > MAX_NAME_LENGTH {
+        errors.push(FieldError::new(
+            "name",
+            format!("must not be longer than {MAX_NAME_LENGTH} characters"),
+        ));
+    } else if name.chars().any(char::is_control) {
+        errors.push(FieldError::new(
+            "name",
+            "must not contain control characters",
+        ));
+    }
+
+    if icon.is_some_and(|icon| !is_valid_icon(icon)) {
+        errors.push(FieldError::new(
+            "icon",
+            "must be base64-encoded, optionally prefixed by an image mime type",
+        ));
+    }
+
+    for (field, url) in urls {
+        if let Some(url) = url.filter(|url| url.scheme() != "https") {
+            errors.push(FieldError::new(
+                field,
+                format!("must use https, not {}", url.scheme()),
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        return Ok(());
+    }
+
+    Err(ErrorKind::Validation { errors }.into())
+}
+
+/// Whether `icon` is encoded as by [`encode_icon`].
+fn is_valid_icon(icon: &str) -> bool {
+    let encoded = match icon.split_once(',') {
+        Some((mime_type, encoded)) if mime_type.starts_with("image/") => encoded,
+        Some(_) => return false,
+        None => icon,
     };
 
-    Ok(format!("{mime_type_prefix}{}", base64.encode(icon_data)))
+    base64.decode(encoded).is_ok()
+}
+
+/// Check that neither `user_ids` nor `user_chain_ids` contain duplicates.
+///
+/// Basispoort silently ignores mistakes in user lists, so they are otherwise hard to spot.
+fn validate_user_ids(user_ids: &[BasispoortId], user_chain_ids: &[UserChainId]) -> Result<()> {
+    let duplicated = duplicated_indices(user_ids);
+    let duplicated_chain_ids = duplicated_indices(
+        user_chain_ids
+            .iter()
+            .map(|user| (user.institution_id, &user.chain_id)),
+    );
+
+    if duplicated.is_empty() && duplicated_chain_ids.is_empty() {
+        return Ok(());
+    }
+
+    Err(ErrorKind::InvalidUserIds {
+        duplicated,
+        duplicated_chain_ids,
+    }
+    .into())
+}
+
+/// The indices of the items equal to an earlier one.
+fn duplicated_indices<T: Eq + Hash>(items: impl IntoIterator<Item = T>) -> Vec<usize> {
+    let mut seen = HashSet::new();
+    items
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, item)| (!seen.insert(item)).then_some(index))
+        .collect()
+}
+
+impl MethodDetails {
+    /// Check the method as Basispoort would: its ID must form a valid URL path segment, its name must not be blank
+    /// or too long (see [`MAX_NAME_LENGTH`]), its icon must be encoded as by [`Self::with_icon_bytes`],
+    /// and its URLs must use https.
+    ///
+    /// Fails with [`ErrorKind::Validation`], listing each invalid field.
+    pub fn validate(&self) -> Result<()> {
+        validate_application(
+            "method ID",
+            &self.id,
+            &self.name,
+            self.icon.as_deref(),
+            [
+                ("url", self.url.as_ref()),
+                ("icon_url", self.icon_url.as_ref()),
+            ],
+        )
+    }
+
+    /// Check the method as [`MethodDetails::validate`] does, and that the icon does not exceed
+    /// [`Limits::max_icon_bytes`].
+    pub fn validate_with(&self, limits: &Limits) -> Result<()> {
+        self.validate()?;
+        check_limit(
+            "max_icon_bytes",
+            limits.max_icon_bytes,
+            self.icon.as_deref().map_or(0, decoded_icon_len),
+        )
+    }
+}
+
+impl ProductDetails {
+    /// Check the product as [`MethodDetails::validate`] checks methods.
+    ///
+    /// Fails with [`ErrorKind::Validation`], listing each invalid field.
+    pub fn validate(&self) -> Result<()> {
+        validate_application(
+            "product ID",
+            &self.id,
+            &self.name,
+            self.icon.as_deref(),
+            [
+                ("url", Some(&self.url)),
+                ("icon_url", self.icon_url.as_ref()),
+            ],
+        )
+    }
+
+    /// Check the product as [`ProductDetails::validate`] does, and that the icon does not exceed
+    /// [`Limits::max_icon_bytes`].
+    pub fn validate_with(&self, limits: &Limits) -> Result<()> {
+        self.validate()?;
+        check_limit(
+            "max_icon_bytes",
+            limits.max_icon_bytes,
+            self.icon.as_deref().map_or(0, decoded_icon_len),
+        )
+    }
+}
+
+impl UserChainIdList {
+    /// Check that the list does not exceed [`Limits::max_users_per_list`].
+    pub fn validate_with(&self, limits: &Limits) -> Result<()> {
+        check_limit(
+            "max_users_per_list",
+            limits.max_users_per_list,
+            self.users.len(),
+        )
+    }
+}
+
+impl UserIdList {
+    /// Check the list against the [default limits][Limits::default], as [`UserIdList::validate_with`] does.
+    ///
+    /// Fails with [`ErrorKind::InvalidUserIds`], listing the indices of the offending IDs.
+    pub fn validate(&self) -> Result<()> {
+        self.validate_with(&Limits::default())
+    }
+
+    /// Check that all user IDs are unique, and the length of the list.
+    ///
+    /// Fails with [`ErrorKind::LimitExceeded`] if the list is too long.
+    pub fn validate_with(&self, limits: &Limits) -> Result<()> {
+        check_limit(
+            "max_users_per_list",
+            limits.max_users_per_list,
+            self.users.len(),
+        )?;
+        validate_user_ids(&self.users, &[])
+    }
+
+    /// Split into lists of at most `size` users each.
+    pub fn chunks(&self, size: NonZeroUsize) -> Vec<UserIdList> {
+        self.users
+            .chunks(size.get())
+            .map(|users| users.to_vec().into())
+            .collect()
+    }
+}
+
+impl BulkRequest {
+    /// Check the request against the [default limits][Limits::default], as [`BulkRequest::validate_with`] does.
+    pub fn validate(&self) -> Result<()> {
+        self.validate_with(&Limits::default())
+    }
+
+    /// Check the request against `limits`: that its user IDs and user chain IDs are unique,
+    /// and its numbers of users, methods and products.
+    ///
+    /// User chain IDs count towards [`Limits::max_users_per_list`].
+    pub fn validate_with(&self, limits: &Limits) -> Result<()> {
+        check_limit(
+            "max_users_per_list",
+            limits.max_users_per_list,
+            self.user_ids.len() + self.user_chain_ids.len(),
+        )?;
+        check_limit(
+            "max_methods_per_bulk_request",
+            limits.max_methods_per_bulk_request,
+            self.method_ids.len(),
+        )?;
+        check_limit(
+            "max_products_per_bulk_request",
+            limits.max_products_per_bulk_request,
+            self.product_ids.len(),
+        )?;
+        validate_user_ids(&self.user_ids, &self.user_chain_ids)
+    }
+
+    /// Split into requests for the same methods and products, with at most `size` users each.
+    ///
+    /// User IDs and user chain IDs are not mixed within a chunk.
+    pub fn chunks(&self, size: NonZeroUsize) -> Vec<BulkRequest> {
+        let chunk = |user_ids: &[BasispoortId], user_chain_ids: &[UserChainId]| BulkRequest {
+            method_ids: self.method_ids.clone(),
+            product_ids: self.product_ids.clone(),
+            user_ids: user_ids.to_vec(),
+            user_chain_ids: user_chain_ids.to_vec(),
+        };
+
+        self.user_ids
+            .chunks(size.get())
+            .map(|user_ids| chunk(user_ids, &[]))
+            .chain(
+                self.user_chain_ids
+                    .chunks(size.get())
+                    .map(|user_chain_ids| chunk(&[], user_chain_ids)),
+            )
+            .collect()
+    }
+}
+
+impl InconsistentAssignments {
+    /// Compare the users with access to the method with the users with access to each of its products.
+    pub(super) fn new(
+        method_id: impl Into<String>,
+        method_users: &[BasispoortId],
+        product_users: &[(String, Vec<BasispoortId>)],
+    ) -> Self {
+        let method_users: BTreeSet<_> = method_users.iter().copied().collect();
+
+        let product_users_without_method = product_users
+            .iter()
+            .map(|(product_id, users)| {
+                let without_method: BTreeSet<_> = users
+                    .iter()
+                    .filter(|user| !method_users.contains(user))
+                    .copied()
+                    .collect();
+                (product_id.clone(), without_method)
+            })
+            .filter(|(_, users)| !users.is_empty())
+            .collect();
+
+        let method_users_without_product = if product_users.is_empty() {
+            BTreeSet::new()
+        } else {
+            let with_product: BTreeSet<_> = product_users
+                .iter()
+                .flat_map(|(_, users)| users.iter().copied())
+                .collect();
+            method_users.difference(&with_product).copied().collect()
+        };
+
+        Self {
+            method_id: method_id.into(),
+            product_users_without_method,
+            method_users_without_product,
+        }
+    }
+
+    /// Whether no inconsistency was found.
+    pub fn is_consistent(&self) -> bool {
+        self.product_users_without_method.is_empty() && self.method_users_without_product.is_empty()
+    }
+}
+
+impl UserIdSync {
+    /// Plan the changes turning the `current` users into the `desired` users, ignoring order and duplicates.
+    pub(super) fn new(current: &[BasispoortId], desired: &[BasispoortId]) -> Self {
+        let current: BTreeSet<_> = current.iter().copied().collect();
+        let desired: BTreeSet<_> = desired.iter().copied().collect();
+
+        Self {
+            added: desired.difference(&current).copied().collect(),
+            removed: current.difference(&desired).copied().collect(),
+            unchanged: current.intersection(&desired).count(),
+        }
+    }
+
+    /// Whether no change was made.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
 }
 
 impl From<Vec<BasispoortId>> for UserIdList {
@@ -404,4 +1453,356 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn accepts_parsed_and_string_urls() -> Result<()> {
+        let url: Url = "https://www.example.com/path/".parse().unwrap();
+
+        let method = MethodDetails::new("method", "Method")
+            .with_url(url.clone())?
+            .with_icon_url(String::from("https://www.example.com/icon.svg"))?;
+        assert_eq!(method.url, Some(url));
+
+        let error = MethodDetails::new("method", "Method")
+            .with_url("not a url")
+            .unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::ParseUrl { url, .. } if url == "not a url"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn builds_product_with_icon_from_reader_and_bytes() -> Result<()> {
+        let product = ProductDetails::new("product", "Product", "https://www.example.com/")?
+            .with_icon_from_reader(futures_lite::io::Cursor::new(b"<svg/>"), IconFormat::Svg)
+            .await?;
+
+        assert_eq!(product.icon.as_deref(), Some("image/svg+xml,PHN2Zy8+"));
+
+        let product = product.with_icon_bytes(b"<svg/>", "image/svg+xml")?;
+        assert_eq!(product.icon.as_deref(), Some("image/svg+xml,PHN2Zy8+"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn truncates_and_redacts_debug_output() {
+        let method = MethodDetails::new("method", "Method")
+            .with_icon_bytes([0; 300], "image/svg+xml")
+            .unwrap();
+        let debug = format!("{method:?}");
+        assert!(debug.contains("bytes)"));
+        assert!(debug.len() < 300);
+
+        let users = UserChainIdList {
+            users: vec![UserChainId {
+                institution_id: BasispoortId::from_u32(1),
+                chain_id: "secret-eck-id".into(),
+            }],
+        };
+        assert!(!format!("{users:?}").contains("secret-eck-id"));
+    }
+
+    #[test]
+    fn reports_duplicated_user_ids() {
+        let users = UserIdList::from([1, 0, 2, 1, 2].map(BasispoortId::from_u32).to_vec());
+
+        let error = users.validate().unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::InvalidUserIds { duplicated, duplicated_chain_ids }
+                if duplicated == &[3, 4] && duplicated_chain_ids.is_empty()
+        ));
+
+        let user = |institution_id, chain_id: &str| UserChainId {
+            institution_id: BasispoortId::from_u32(institution_id),
+            chain_id: chain_id.into(),
+        };
+        let bulk_request = BulkRequest {
+            user_chain_ids: vec![user(1, "a"), user(2, "a"), user(1, "a")],
+            ..Default::default()
+        };
+        assert!(matches!(
+            bulk_request.validate().unwrap_err().kind(),
+            ErrorKind::InvalidUserIds { duplicated, duplicated_chain_ids }
+                if duplicated.is_empty() && duplicated_chain_ids == &[2]
+        ));
+        assert!(UserIdList::from(vec![BasispoortId::from_u32(128684)])
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn enforces_configured_limits() {
+        let limits = Limits::default()
+            .with_max_users_per_list(2)
+            .with_max_methods_per_bulk_request(1)
+            .with_max_icon_bytes(4);
+
+        let users = UserIdList::from(vec![
+            BasispoortId::from_u32(1),
+            BasispoortId::from_u32(2),
+            BasispoortId::from_u32(3),
+        ]);
+        assert!(users.validate().is_ok());
+        assert!(matches!(
+            users.validate_with(&limits).unwrap_err().kind(),
+            ErrorKind::LimitExceeded {
+                limit: "max_users_per_list",
+                max: 2,
+                actual: 3,
+            }
+        ));
+
+        let bulk_request = BulkRequest {
+            method_ids: vec!["first".into(), "second".into()],
+            product_ids: vec![],
+            user_ids: vec![BasispoortId::from_u32(1)],
+            user_chain_ids: vec![],
+        };
+        assert!(matches!(
+            bulk_request.validate_with(&limits).unwrap_err().kind(),
+            ErrorKind::LimitExceeded {
+                limit: "max_methods_per_bulk_request",
+                ..
+            }
+        ));
+
+        let mut method = MethodDetails::new("method", "Method");
+        method.icon = Some(encode_icon(b"icon", Some("image/png")));
+        assert!(method.validate_with(&limits).is_ok());
+        method.icon = Some(encode_icon(b"icon!", Some("image/png")));
+        assert!(matches!(
+            method.validate_with(&limits).unwrap_err().kind(),
+            ErrorKind::LimitExceeded {
+                limit: "max_icon_bytes",
+                max: 4,
+                actual: 5,
+            }
+        ));
+    }
+
+    #[test]
+    fn determines_icon_format_from_mime_type() {
+        assert_eq!(IconFormat::from_mime_type("image/png"), IconFormat::Png);
+        assert_eq!(
+            IconFormat::from_mime_type("image/SVG+xml; charset=utf-8"),
+            IconFormat::Svg
+        );
+        assert_eq!(
+            IconFormat::from_mime_type("application/octet-stream"),
+            IconFormat::Unknown
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_oversized_icons() {
+        let limits = Limits::default().with_max_icon_bytes(4);
+        let is_too_large = |result: Result<MethodDetails>| {
+            matches!(
+                result.unwrap_err().kind(),
+                ErrorKind::LimitExceeded {
+                    limit: "max_icon_bytes",
+                    max: 4,
+                    actual: 5,
+                }
+            )
+        };
+        let method = || MethodDetails::new("method", "Method");
+
+        assert!(method()
+            .with_icon_bytes_within(b"icon", "image/svg+xml", &limits)
+            .is_ok());
+        assert!(is_too_large(method().with_icon_bytes_within(
+            b"icon!",
+            "image/svg+xml",
+            &limits
+        )));
+        // Reading stops one byte past the limit.
+        assert!(is_too_large(
+            method()
+                .with_icon_from_reader_within(&b"icon!!!"[..], IconFormat::Svg, &limits)
+                .await
+        ));
+    }
+
+    /// Respond to the first connection with the raw HTTP `response`, returning the URL to request.
+    async fn serve_once(response: &'static [u8]) -> Url {
+        use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/icon", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let _request = stream.read(&mut [0; 1024]).await.unwrap();
+            stream.write_all(response).await.unwrap();
+        });
+
+        url.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn downloads_icons_within_limits() -> Result<()> {
+        let limits = Limits::default().with_max_icon_bytes(6);
+        let method = || MethodDetails::new("method", "Method");
+        let client = reqwest::Client::new();
+
+        let url = serve_once(
+            b"HTTP/1.1 200 OK\r\nContent-Type: image/svg+xml\r\nContent-Length: 6\r\n\r\n<svg/>",
+        )
+        .await;
+        let downloaded = method()
+            .with_icon_from_url_within(&client, url, &limits)
+            .await?;
+        assert_eq!(downloaded.icon.as_deref(), Some("image/svg+xml,PHN2Zy8+"));
+
+        // The announced size is refused before receiving the body.
+        let url = serve_once(b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\n").await;
+        let error = method()
+            .with_icon_from_url_within(&client, url, &limits)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::LimitExceeded { actual: 100, .. }
+        ));
+
+        // Without an announced size, the download stops once it exceeds the limit.
+        let url = serve_once(b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n<svg></svg>").await;
+        let error = method()
+            .with_icon_from_url_within(&client, url, &limits)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::LimitExceeded {
+                limit: "max_icon_bytes",
+                max: 6,
+                ..
+            }
+        ));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn downscales_png_icons() {
+        let mut png = Vec::new();
+        image::DynamicImage::new_rgba8(64, 32)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let downscaled = downscale_png(png.clone(), 16).unwrap();
+        let downscaled = image::load_from_memory(&downscaled).unwrap();
+        assert_eq!((downscaled.width(), downscaled.height()), (16, 8));
+        assert_eq!(downscale_png(png.clone(), 64).unwrap(), png);
+    }
+
+    #[cfg(feature = "image")]
+    #[tokio::test]
+    async fn downscales_png_icons_before_checking_their_size() {
+        // Noise compresses poorly, so the PNG only fits into the byte limit once downscaled.
+        let mut seed = 1u32;
+        let noise = image::RgbaImage::from_fn(128, 128, |_, _| {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            image::Rgba(seed.to_le_bytes())
+        });
+        let mut png = Vec::new();
+        image::DynamicImage::from(noise)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let max_icon_bytes = downscale_png(png.clone(), 16).unwrap().len();
+        assert!(png.len() > max_icon_bytes);
+        let limits = Limits::default()
+            .with_max_icon_bytes(max_icon_bytes)
+            .with_max_icon_dimension(16);
+        let method = || MethodDetails::new("method", "Method");
+
+        assert!(method()
+            .with_icon_bytes_within(&png, "image/png", &limits)
+            .is_ok());
+        assert!(method()
+            .with_icon_from_reader_within(&png[..], IconFormat::Png, &limits)
+            .await
+            .is_ok());
+    }
+
+    #[test]
+    fn lists_invalid_fields() {
+        let product = ProductDetails::new("product", "Product", "https://example.com/product")
+            .unwrap()
+            .with_icon("image/png,aWNvbg==");
+        assert!(product.validate().is_ok());
+
+        let method = MethodDetails::new("a/b", " ")
+            .with_icon("not base64!")
+            .with_url("http://example.com/method")
+            .unwrap();
+        let error = method.validate().unwrap_err();
+        let ErrorKind::Validation { errors } = error.kind() else {
+            panic!("unexpected error: {error:?}");
+        };
+        assert_eq!(
+            errors.iter().map(|error| error.field).collect::<Vec<_>>(),
+            ["id", "name", "icon", "url"]
+        );
+        assert_eq!(errors[3].message, "must use https, not http");
+    }
+
+    #[test]
+    fn chunks_bulk_requests_by_users() {
+        let bulk_request = BulkRequest::new()
+            .with_method_ids(["method"])
+            .with_user_ids((1..=5).map(BasispoortId::from_u32))
+            .with_user_chain_ids([UserChainId::new(BasispoortId::from_u32(1), "eck-id")]);
+
+        let chunks = bulk_request.chunks(NonZeroUsize::new(2).unwrap());
+        let sizes: Vec<_> = chunks
+            .iter()
+            .map(|chunk| (chunk.user_ids.len(), chunk.user_chain_ids.len()))
+            .collect();
+        assert_eq!(sizes, [(2, 0), (2, 0), (1, 0), (0, 1)]);
+        assert!(chunks.iter().all(|chunk| chunk.method_ids == ["method"]));
+    }
+
+    #[test]
+    fn finds_inconsistent_assignments() {
+        let users =
+            |ids: &[u32]| -> Vec<_> { ids.iter().copied().map(BasispoortId::from_u32).collect() };
+
+        let consistent = InconsistentAssignments::new("method", &users(&[1, 2]), &[]);
+        assert!(consistent.is_consistent());
+
+        let inconsistent = InconsistentAssignments::new(
+            "method",
+            &users(&[1, 2, 3]),
+            &[
+                ("product-a".into(), users(&[1, 4])),
+                ("product-b".into(), users(&[2])),
+            ],
+        );
+        assert!(!inconsistent.is_consistent());
+        assert_eq!(
+            inconsistent.product_users_without_method,
+            BTreeMap::from([("product-a".into(), users(&[4]).into_iter().collect())])
+        );
+        assert_eq!(
+            inconsistent.method_users_without_product,
+            users(&[3]).into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn plans_user_id_sync() {
+        let users =
+            |ids: &[u32]| -> Vec<_> { ids.iter().copied().map(BasispoortId::from_u32).collect() };
+
+        let sync = UserIdSync::new(&users(&[3, 1, 2]), &users(&[4, 2, 3, 4]));
+        assert_eq!(sync.added, users(&[4]));
+        assert_eq!(sync.removed, users(&[1]));
+        assert_eq!(sync.unchanged, 2);
+        assert!(UserIdSync::new(&users(&[1]), &users(&[1])).is_empty());
+    }
 }
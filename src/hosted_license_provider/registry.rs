@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+
+#[cfg(not(coverage))]
+use tracing::instrument;
+use tracing::warn;
+
+use crate::{rest, Result};
+
+use super::client::HostedLicenseProviderClient;
+
+/// API clients for several identity codes, e.g. one per imprint or brand, sharing a [`rest::RestClient`].
+#[derive(Debug)]
+pub struct HostedLicenseProviderRegistry {
+    clients: BTreeMap<String, HostedLicenseProviderClient>,
+}
+
+impl HostedLicenseProviderRegistry {
+    #[cfg_attr(not(coverage), instrument(skip(identity_codes)))]
+    pub fn new<S: Into<String>>(
+        rest_client: &rest::RestClient,
+        identity_codes: impl IntoIterator<Item = S>,
+    ) -> Self {
+        Self {
+            clients: identity_codes
+                .into_iter()
+                .map(|identity_code| {
+                    let identity_code = identity_code.into();
+                    let client =
+                        HostedLicenseProviderClient::new(rest_client, identity_code.clone());
+                    (identity_code, client)
+                })
+                .collect(),
+        }
+    }
+
+    /// The client for `identity_code`, if registered.
+    pub fn get(&self, identity_code: &str) -> Option<&HostedLicenseProviderClient> {
+        self.clients.get(identity_code)
+    }
+
+    /// All registered identity codes, in ascending order.
+    pub fn identity_codes(&self) -> impl Iterator<Item = &str> {
+        self.clients.keys().map(String::as_str)
+    }
+
+    /// All clients with their identity codes, in ascending order of identity codes.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &HostedLicenseProviderClient)> {
+        self.clients
+            .iter()
+            .map(|(identity_code, client)| (identity_code.as_str(), client))
+    }
+
+    /// Call `apply` with the client of each identity code in turn, e.g. to create a method under all identity codes.
+    ///
+    /// Unlike a single call, a failure for one identity code does not stop the others.
+    /// Returns each identity code with its result, in ascending order of identity codes.
+    #[cfg_attr(not(coverage), instrument(skip(self, apply)))]
+    pub async fn broadcast<'r, T, F, Fut>(&'r self, mut apply: F) -> Vec<(&'r str, Result<T>)>
+    where
+        F: FnMut(&'r HostedLicenseProviderClient) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut results = Vec::with_capacity(self.clients.len());
+        for (identity_code, client) in self.iter() {
+            let result = apply(client).await;
+            if let Err(error) = &result {
+                warn!("Failed for identity code '{identity_code}': {error}");
+            }
+            results.push((identity_code, result));
+        }
+
+        results
+    }
+}
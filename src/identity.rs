@@ -0,0 +1,135 @@
+//! Sources for the client identity certificate.
+//!
+//! Implement [`IdentityProvider`] to fetch the identity from a secrets store (e.g. Vault, a KMS or Kubernetes secrets)
+//! instead of a file on disk, then pass it to [`RestClientBuilder::from_identity_provider`][crate::rest::RestClientBuilder::from_identity_provider].
+//! An identity already held in memory is passed to
+//! [`RestClientBuilder::from_identity_pem`][crate::rest::RestClientBuilder::from_identity_pem] or
+//! [`RestClientBuilder::from_identity`][crate::rest::RestClientBuilder::from_identity] instead.
+
+use std::fmt::Debug;
+use std::fs::File;
+use std::io;
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use reqwest::Identity;
+#[cfg(not(coverage))]
+use tracing::instrument;
+
+use crate::{
+    error::ErrorKind,
+    runtime::{self, ReadFileError},
+    Result,
+};
+
+/// Provides the client identity (certificate and private key) used to authenticate with Basispoort.
+#[async_trait]
+pub trait IdentityProvider: Debug + Send + Sync {
+    /// Fetch the current identity.
+    async fn fetch_identity(&self) -> Result<Identity>;
+}
+
+/// Reads the identity from a PEM file, containing both certificate and private key.
+#[derive(Debug, Clone)]
+pub struct IdentityFile {
+    path: PathBuf,
+}
+
+impl IdentityFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Check that the file exists, is a regular file and can be opened for reading.
+    pub fn check_readable(&self) -> Result<()> {
+        let open_error = |source| ErrorKind::OpenIdentityCertFile {
+            path: self.path.clone(),
+            source,
+        };
+
+        let file = File::open(&self.path).map_err(open_error)?;
+        if !file.metadata().map_err(open_error)?.is_file() {
+            return Err(open_error(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "not a regular file",
+            ))
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Provides a fixed identity, held in memory.
+#[derive(Debug, Clone)]
+pub struct StaticIdentity {
+    identity: Identity,
+}
+
+impl StaticIdentity {
+    pub fn new(identity: Identity) -> Self {
+        Self { identity }
+    }
+
+    /// Parse the identity from PEM bytes, containing both certificate and private key.
+    pub fn from_pem(pem: &[u8]) -> Result<Self> {
+        Ok(Self::new(
+            Identity::from_pem(pem).map_err(ErrorKind::ParseIdentity)?,
+        ))
+    }
+}
+
+#[async_trait]
+impl IdentityProvider for StaticIdentity {
+    async fn fetch_identity(&self) -> Result<Identity> {
+        Ok(self.identity.clone())
+    }
+}
+
+#[async_trait]
+impl IdentityProvider for IdentityFile {
+    #[cfg_attr(not(coverage), instrument)]
+    async fn fetch_identity(&self) -> Result<Identity> {
+        let cert = runtime::read_file(&self.path)
+            .await
+            .map_err(|error| match error {
+                ReadFileError::Open(source) => ErrorKind::OpenIdentityCertFile {
+                    path: self.path.clone(),
+                    source,
+                },
+                ReadFileError::Read(source) => ErrorKind::ReadIdentityCertFile {
+                    path: self.path.clone(),
+                    source,
+                },
+            })?;
+
+        Ok(
+            Identity::from_pem(&cert).map_err(|source| ErrorKind::ParseIdentityCertFile {
+                path: self.path.clone(),
+                source,
+            })?,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_missing_and_non_file_paths() {
+        for path in ["does/not/exist.pem", "src"] {
+            let error = IdentityFile::new(path).check_readable().unwrap_err();
+            assert!(matches!(
+                error.kind(),
+                ErrorKind::OpenIdentityCertFile { path: error_path, .. } if error_path.as_path() == std::path::Path::new(path)
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_pem() {
+        let error = StaticIdentity::from_pem(b"not a certificate").unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::ParseIdentity(_)));
+    }
+}
@@ -1,7 +1,16 @@
+#[cfg(not(any(feature = "chrono", feature = "time")))]
+compile_error!("The `institutions` feature requires either the `chrono` or the `time` feature.");
+
 pub use client::*;
 pub use model::*;
+pub use permission_report::*;
 
+pub mod borrowed;
 mod client;
+pub mod date;
+#[cfg(feature = "las-import")]
+pub mod las_import;
 mod model;
+mod permission_report;
 
 // TODO: Unit tests
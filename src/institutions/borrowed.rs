@@ -0,0 +1,211 @@
+//! Borrowing variants of the roster models, deserialized without allocating a `String` per field.
+//!
+//! For institutions with many students, fetch the raw response body with
+//! [`InstitutionsServiceClient::get_institution_students_bytes`][super::InstitutionsServiceClient::get_institution_students_bytes],
+//! then deserialize it with [`InstitutionStudents::from_slice`]. The models borrow their strings from the body.
+//! Only strings containing JSON escape sequences are allocated.
+
+use std::borrow::Cow;
+use std::fmt;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::{error::ErrorKind, redact::redacted, BasispoortId, Result};
+
+use super::ResultMetadata;
+
+#[derive(Debug, Deserialize)]
+#[non_exhaustive]
+pub struct InstitutionStudents<'a> {
+    #[serde(rename = "leerlingen", borrow)]
+    pub students: Vec<Student<'a>>,
+
+    #[serde(rename = "metaResult")]
+    pub result_metadata: ResultMetadata,
+}
+
+#[derive(Deserialize)]
+#[non_exhaustive]
+pub struct Student<'a> {
+    pub id: BasispoortId,
+
+    #[serde(
+        rename = "eckid",
+        alias = "eckId",
+        borrow,
+        default,
+        deserialize_with = "optional"
+    )]
+    pub chain_id: Option<Cow<'a, str>>,
+
+    #[serde(
+        rename = "lasKey",
+        alias = "laskey",
+        borrow,
+        default,
+        deserialize_with = "optional"
+    )]
+    pub administrative_key: Option<Cow<'a, str>>,
+
+    #[serde(rename = "persoonsgegevens", borrow)]
+    pub personal_data: PersonalData<'a>,
+
+    #[serde(rename = "jaargroep", borrow, default, deserialize_with = "optional")]
+    pub year_group: Option<Cow<'a, str>>,
+
+    #[serde(rename = "groep", borrow, default, deserialize_with = "optional")]
+    pub group: Option<Cow<'a, str>>,
+
+    #[serde(rename = "subgroepen", borrow, deserialize_with = "list")]
+    pub sub_groups: Vec<Cow<'a, str>>,
+}
+
+#[derive(Deserialize)]
+#[non_exhaustive]
+pub struct PersonalData<'a> {
+    #[serde(rename = "achternaam", borrow, default, deserialize_with = "optional")]
+    pub last_name: Option<Cow<'a, str>>,
+
+    #[serde(rename = "voornaam", borrow, default, deserialize_with = "optional")]
+    pub first_name: Option<Cow<'a, str>>,
+
+    #[serde(rename = "voorvoegsel", borrow, default, deserialize_with = "optional")]
+    pub prefix: Option<Cow<'a, str>>,
+
+    #[serde(rename = "voorletters", borrow, default, deserialize_with = "optional")]
+    pub initials: Option<Cow<'a, str>>,
+}
+
+impl<'a> InstitutionStudents<'a> {
+    /// Deserialize the students from a raw response body, borrowing their strings from `body`.
+    pub fn from_slice(body: &'a [u8]) -> Result<Self> {
+        Ok(serde_json::from_slice(body).map_err(ErrorKind::DeserializeResponseBody)?)
+    }
+
+    /// Convert into the owned model.
+    pub fn into_owned(self) -> super::InstitutionStudents {
+        super::InstitutionStudents {
+            students: self.students.into_iter().map(Student::into_owned).collect(),
+            result_metadata: self.result_metadata,
+        }
+    }
+}
+
+impl Student<'_> {
+    /// Convert into the owned model.
+    pub fn into_owned(self) -> super::Student {
+        super::Student {
+            id: self.id,
+            chain_id: self.chain_id.map(Cow::into_owned),
+            administrative_key: self.administrative_key.map(Cow::into_owned),
+            personal_data: self.personal_data.into_owned(),
+            year_group: self.year_group.map(Cow::into_owned),
+            group: self.group.map(Cow::into_owned),
+            sub_groups: self.sub_groups.into_iter().map(Cow::into_owned).collect(),
+        }
+    }
+}
+
+impl PersonalData<'_> {
+    /// Convert into the owned model.
+    pub fn into_owned(self) -> super::PersonalData {
+        super::PersonalData {
+            last_name: self.last_name.map(Cow::into_owned),
+            first_name: self.first_name.map(Cow::into_owned),
+            prefix: self.prefix.map(Cow::into_owned),
+            initials: self.initials.map(Cow::into_owned),
+        }
+    }
+}
+
+// Debug implementations redact personal data, as on the owned models.
+
+impl fmt::Debug for Student<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Student")
+            .field("id", &self.id)
+            .field("chain_id", &redacted(&self.chain_id))
+            .field("administrative_key", &self.administrative_key)
+            .field("personal_data", &self.personal_data)
+            .field("year_group", &self.year_group)
+            .field("group", &self.group)
+            .field("sub_groups", &self.sub_groups)
+            .finish()
+    }
+}
+
+impl fmt::Debug for PersonalData<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PersonalData")
+            .field("last_name", &redacted(&self.last_name))
+            .field("first_name", &redacted(&self.first_name))
+            .field("prefix", &redacted(&self.prefix))
+            .field("initials", &redacted(&self.initials))
+            .finish()
+    }
+}
+
+// `Cow<str>` only borrows when deserialized as a field marked `#[serde(borrow)]`, not when nested in `Option` or `Vec`.
+#[derive(Deserialize)]
+struct Borrowed<'a>(#[serde(borrow)] Cow<'a, str>);
+
+fn optional<'de: 'a, 'a, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<Cow<'a, str>>, D::Error> {
+    Ok(Option::<Borrowed<'a>>::deserialize(deserializer)?.map(|borrowed| borrowed.0))
+}
+
+fn list<'de: 'a, 'a, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Vec<Cow<'a, str>>, D::Error> {
+    Ok(Vec::<Borrowed<'a>>::deserialize(deserializer)?
+        .into_iter()
+        .map(|borrowed| borrowed.0)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn borrows_unescaped_strings() -> Result<()> {
+        let body = br#"{
+            "leerlingen": [{
+                "id": 1,
+                "eckid": "https://id.school/LL_abc",
+                "lasKey": "42",
+                "persoonsgegevens": {"achternaam": "Jansen", "voornaam": "Anne \"Annie\""},
+                "jaargroep": "3",
+                "groep": null,
+                "subgroepen": ["sub-1"]
+            }],
+            "metaResult": {
+                "mutationTimestamp": "2024-05-01T12:00:00Z",
+                "generationTimestamp": "2024-05-01T12:00:00Z"
+            }
+        }"#;
+
+        let students = InstitutionStudents::from_slice(body)?;
+        let student = &students.students[0];
+
+        assert!(matches!(
+            student.administrative_key,
+            Some(Cow::Borrowed("42"))
+        ));
+        assert!(matches!(student.sub_groups[..], [Cow::Borrowed("sub-1")]));
+        assert!(matches!(
+            student.personal_data.last_name,
+            Some(Cow::Borrowed("Jansen"))
+        ));
+        assert!(matches!(
+            student.personal_data.first_name,
+            Some(Cow::Owned(ref name)) if name == "Anne \"Annie\""
+        ));
+
+        let owned = students.into_owned();
+        assert_eq!(owned.students[0].group, None);
+
+        Ok(())
+    }
+}
@@ -1,63 +1,228 @@
-use std::fmt::Debug;
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug};
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+use std::sync::Mutex;
 
-use chrono::NaiveDate;
-use serde::{de::DeserializeOwned, Serialize};
+use bytes::Bytes;
+use futures_lite::future::try_zip;
+#[cfg(feature = "stream")]
+use futures_lite::{stream, Stream, StreamExt};
+use reqwest::{Method, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+use tracing::debug;
 #[cfg(not(coverage))]
 use tracing::instrument;
 
-use crate::{error::Error, rest, BasispoortId, Result};
+use crate::{
+    concurrency::map_bounded,
+    dry_run::{DryRun, PlannedRequest},
+    error::ErrorKind,
+    progress::{ProgressReporter, ProgressSender},
+    rest,
+    transport::Transport,
+    BasispoortId, Result,
+};
 
+use super::date::{Date, Timestamp};
 use super::model::*;
 
-#[derive(Debug)]
-pub struct InstitutionsServiceClient<'a> {
-    rest_client: &'a rest::RestClient,
-    base_path: &'static str,
+/// A version of the institutions service API.
+///
+/// Only `v2` is currently supported. Newer versions will be added here, with their model differences handled
+/// by [`InstitutionsServiceClient`], once Basispoort documents them.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[non_exhaustive]
+pub enum InstitutionsApiVersion {
+    #[default]
+    #[serde(rename = "v2")]
+    V2,
 }
 
-impl<'a> InstitutionsServiceClient<'a> {
+impl InstitutionsApiVersion {
+    /// All supported versions, newest first.
+    pub const SUPPORTED: &'static [Self] = &[Self::V2];
+
+    /// The version's name, as used in paths and parsed by [`InstitutionsApiVersion::from_str`].
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::V2 => "v2",
+        }
+    }
+
+    /// The path segment of the version, following [`ServicePaths::institutions`][rest::ServicePaths::institutions].
+    fn path_segment(&self) -> &'static str {
+        match self {
+            Self::V2 => "v2/",
+        }
+    }
+
+    fn base_path(&self, service_paths: &rest::ServicePaths) -> String {
+        format!("{}{}", service_paths.institutions, self.path_segment())
+    }
+}
+
+impl fmt::Display for InstitutionsApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// [`InstitutionsApiVersion`] parse error.
+#[derive(Error, Debug)]
+pub enum ParseInstitutionsApiVersionError {
+    #[error("'{0}' is not a supported institutions API version")]
+    UnsupportedVersion(String),
+}
+
+impl FromStr for InstitutionsApiVersion {
+    type Err = ParseInstitutionsApiVersionError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Self::SUPPORTED
+            .iter()
+            .copied()
+            .find(|version| version.as_str() == s)
+            .ok_or_else(|| ParseInstitutionsApiVersionError::UnsupportedVersion(s.into()))
+    }
+}
+
+/// An API client for the institutions service.
+///
+/// Sends its requests through a [`Transport`], usually a [`RestClient`][rest::RestClient].
+#[derive(Debug, Clone)]
+pub struct InstitutionsServiceClient<C: Transport = rest::RestClient> {
+    transport: C,
+    version: InstitutionsApiVersion,
+    dry_run: DryRun,
+}
+
+impl<C: Transport> InstitutionsServiceClient<C> {
+    /// Create a client for the default API version, see [`InstitutionsApiVersion::default`].
     #[cfg_attr(not(coverage), instrument)]
-    pub fn new(rest_client: &'a rest::RestClient) -> Self {
+    pub fn new(transport: &C) -> Self {
+        Self::with_version(transport, InstitutionsApiVersion::default())
+    }
+
+    /// Create a client for the API `version`, e.g. as configured.
+    #[cfg_attr(not(coverage), instrument)]
+    pub fn with_version(transport: &C, version: InstitutionsApiVersion) -> Self {
         InstitutionsServiceClient {
-            rest_client,
-            // TODO: "/v2/licenties" as separate service (and crate feature)?
-            base_path: "rest/v2/",
+            transport: transport.clone(),
+            version,
+            dry_run: DryRun::default(),
+        }
+    }
+
+    /// Create a client for the newest API version the environment serves.
+    ///
+    /// Probes the institution list of each [supported version][InstitutionsApiVersion::SUPPORTED], newest first,
+    /// until one does not respond with `404 Not Found`.
+    #[cfg_attr(not(coverage), instrument)]
+    pub async fn negotiate(transport: &C) -> Result<Self> {
+        for &version in InstitutionsApiVersion::SUPPORTED {
+            let probe = transport
+                .get_bytes(&format!(
+                    "{}instellingen",
+                    version.base_path(transport.service_paths())
+                ))
+                .await;
+            match probe {
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        ErrorKind::HttpResponse { status, .. } if *status == StatusCode::NOT_FOUND
+                    ) =>
+                {
+                    debug!("Institutions API {version} is not served.");
+                }
+                Err(error) => return Err(error),
+                Ok(_) => return Ok(Self::with_version(transport, version)),
+            }
+        }
+
+        Err(ErrorKind::NoSupportedApiVersion {
+            service: "institutions",
         }
+        .into())
+    }
+
+    /// The API version this client requests.
+    pub fn version(&self) -> InstitutionsApiVersion {
+        self.version
+    }
+
+    /// Log and record the mutating requests, such as relinquishing synchronization permissions,
+    /// instead of sending them.
+    ///
+    /// Reads, including searches sent as `POST` requests, are still sent. Get the recorded requests
+    /// with [`Self::planned_requests`]. Clones of the client share their records.
+    pub fn dry_run(self, enabled: bool) -> Self {
+        Self {
+            dry_run: DryRun::new(enabled),
+            ..self
+        }
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run.is_enabled()
+    }
+
+    /// The mutating requests not sent during the [dry run][Self::dry_run], in order.
+    pub fn planned_requests(&self) -> Vec<PlannedRequest> {
+        self.dry_run.planned_requests()
     }
 
     fn make_path(&self, path: &str) -> String {
-        format!("{}{}", self.base_path, path)
+        format!(
+            "{}{}",
+            self.version.base_path(self.transport.service_paths()),
+            path
+        )
     }
 
     #[cfg_attr(not(coverage), instrument(skip(self)))]
-    async fn get<T: DeserializeOwned + Debug + ?Sized>(&self, path: &str) -> Result<T> {
-        self.rest_client.get(&self.make_path(path)).await
+    async fn get<T: DeserializeOwned + Debug + Send>(&self, path: &str) -> Result<T> {
+        self.transport.get(&self.make_path(path)).await
     }
 
     #[cfg_attr(not(coverage), instrument(skip(self, payload)))]
-    async fn post<P: Serialize + Debug + ?Sized, T: DeserializeOwned + Debug + ?Sized>(
+    async fn post<P: Serialize + Debug + Sync + ?Sized, T: DeserializeOwned + Debug + Send>(
         &self,
         path: &str,
         payload: &P,
     ) -> Result<T> {
-        self.rest_client.post(&self.make_path(path), payload).await
+        self.transport.post(&self.make_path(path), payload).await
     }
 
     #[cfg_attr(not(coverage), instrument(skip(self)))]
-    async fn delete<T: DeserializeOwned + Debug + ?Sized>(&self, path: &str) -> Result<T> {
-        self.rest_client.delete(&self.make_path(path)).await
+    async fn delete<T: DeserializeOwned + Debug + Send>(&self, path: &str) -> Result<T> {
+        let path = self.make_path(path);
+        if let Some(response) = self.dry_run.intercept::<(), _>(Method::DELETE, &path, None) {
+            return response;
+        }
+
+        self.transport.delete(&path).await
     }
 
     /*
      * Institutions service
      */
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "institutions.get_institution_ids", skip(self))
+    )]
     pub async fn get_institution_ids(&self) -> Result<Vec<BasispoortId>> {
         self.get("instellingen").await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "institutions.get_institution_overview", skip(self))
+    )]
     pub async fn get_institution_overview(
         &self,
         institution_id: BasispoortId,
@@ -65,7 +230,10 @@ impl<'a> InstitutionsServiceClient<'a> {
         self.get(&format!("instellingen/{institution_id}")).await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "institutions.get_institution_details", skip(self))
+    )]
     pub async fn get_institution_details(
         &self,
         institution_id: BasispoortId,
@@ -74,7 +242,10 @@ impl<'a> InstitutionsServiceClient<'a> {
             .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "institutions.get_institution_groups", skip(self))
+    )]
     pub async fn get_institution_groups(
         &self,
         institution_id: BasispoortId,
@@ -83,7 +254,10 @@ impl<'a> InstitutionsServiceClient<'a> {
             .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "institutions.get_institution_students", skip(self))
+    )]
     pub async fn get_institution_students(
         &self,
         institution_id: BasispoortId,
@@ -92,7 +266,29 @@ impl<'a> InstitutionsServiceClient<'a> {
             .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    /// Get the raw response body of [`Self::get_institution_students`], to be deserialized into the borrowing
+    /// [`borrowed::InstitutionStudents`][super::borrowed::InstitutionStudents], allocating less for large rosters.
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "institutions.get_institution_students_bytes", skip(self))
+    )]
+    pub async fn get_institution_students_bytes(
+        &self,
+        institution_id: BasispoortId,
+    ) -> Result<Bytes> {
+        self.transport
+            .get_bytes(&self.make_path(&format!("instellingen/{institution_id}/leerlingen")))
+            .await
+    }
+
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "institutions.get_institution_students_by_id",
+            skip(self, student_ids),
+            fields(student_ids.len = student_ids.len())
+        )
+    )]
     pub async fn get_institution_students_by_id(
         &self,
         institution_id: BasispoortId,
@@ -105,7 +301,14 @@ impl<'a> InstitutionsServiceClient<'a> {
         .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "institutions.get_institution_students_by_chain_id",
+            skip(self, student_chain_ids),
+            fields(student_chain_ids.len = student_chain_ids.len())
+        )
+    )]
     pub async fn get_institution_students_by_chain_id(
         &self,
         institution_id: BasispoortId,
@@ -118,7 +321,10 @@ impl<'a> InstitutionsServiceClient<'a> {
         .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "institutions.get_institution_staff", skip(self))
+    )]
     pub async fn get_institution_staff(
         &self,
         institution_id: BasispoortId,
@@ -127,7 +333,10 @@ impl<'a> InstitutionsServiceClient<'a> {
             .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "institutions.get_institution_shortcut_reference", skip(self))
+    )]
     pub async fn get_institution_shortcut_reference(
         &self,
         institution_id: BasispoortId,
@@ -137,7 +346,13 @@ impl<'a> InstitutionsServiceClient<'a> {
     }
 
     // TODO: Test requesting sync permission manually with a school with ICT coordinator account.
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "institutions.get_institution_synchronization_permission",
+            skip(self)
+        )
+    )]
     pub async fn get_institution_synchronization_permission(
         &self,
         institution_id: BasispoortId,
@@ -150,7 +365,13 @@ impl<'a> InstitutionsServiceClient<'a> {
     }
 
     // TODO: Test manually with a school with ICT coordinator account?
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "institutions.relinquish_institution_synchronization_permission",
+            skip(self)
+        )
+    )]
     pub async fn relinquish_institution_synchronization_permission(
         &self,
         institution_id: BasispoortId,
@@ -161,10 +382,16 @@ impl<'a> InstitutionsServiceClient<'a> {
         .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "institutions.get_synchronization_permissions_granted",
+            skip(self)
+        )
+    )]
     pub async fn get_synchronization_permissions_granted(
         &self,
-        date: &NaiveDate,
+        date: &Date,
     ) -> Result<Vec<BasispoortId>> {
         self.get(&format!(
             "instellingen/synchronizationpermission/toegekend/{date}"
@@ -172,10 +399,16 @@ impl<'a> InstitutionsServiceClient<'a> {
         .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "institutions.get_synchronization_permissions_revoked",
+            skip(self)
+        )
+    )]
     pub async fn get_synchronization_permissions_revoked(
         &self,
-        date: &NaiveDate,
+        date: &Date,
     ) -> Result<Vec<BasispoortId>> {
         self.get(&format!(
             "instellingen/synchronizationpermission/ingetrokken/{date}"
@@ -183,15 +416,296 @@ impl<'a> InstitutionsServiceClient<'a> {
         .await
     }
 
-    #[cfg_attr(not(coverage), instrument)]
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "institutions.find_institutions", skip(self))
+    )]
     pub async fn find_institutions(
         &self,
         predicate: InstitutionsSearchPredicate<'_>,
     ) -> Result<Vec<InstitutionSearchResult>> {
         self.get(&format!(
             "nawsearch?{query}",
-            query = String::try_from(&predicate).map_err(Error::SerializeSearchPredicate)?
+            query = String::try_from(&predicate).map_err(ErrorKind::SerializeSearchPredicate)?
         ))
         .await
     }
+
+    /*
+     * Combined requests
+     */
+
+    /// Fetch the institution's details, groups, students and staff concurrently, rather than one after another.
+    ///
+    /// Fails with the first error of any of the requests.
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "institutions.fetch_institution_bundle", skip(self))
+    )]
+    pub async fn fetch_institution_bundle(
+        &self,
+        institution_id: BasispoortId,
+    ) -> Result<InstitutionBundle> {
+        let ((details, groups), (students, staff)) = try_zip(
+            try_zip(
+                self.get_institution_details(institution_id),
+                self.get_institution_groups(institution_id),
+            ),
+            try_zip(
+                self.get_institution_students(institution_id),
+                self.get_institution_staff(institution_id),
+            ),
+        )
+        .await?;
+
+        Ok(InstitutionBundle {
+            institution_id,
+            details,
+            groups,
+            students,
+            staff,
+        })
+    }
+
+    /*
+     * Bulk requests
+     */
+
+    /// Fetch the details of each of the institutions, with at most `max_in_flight` requests in flight at a time.
+    ///
+    /// A failed request does not abort the others: each institution's result is returned separately,
+    /// and reported to `progress`, if given.
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "institutions.get_institutions_details_concurrent",
+            skip(self, institution_ids, progress)
+        )
+    )]
+    pub async fn get_institutions_details_concurrent(
+        &self,
+        institution_ids: impl IntoIterator<Item = BasispoortId>,
+        max_in_flight: NonZeroUsize,
+        progress: Option<&ProgressSender>,
+    ) -> BTreeMap<BasispoortId, Result<InstitutionDetails>> {
+        self.map_institutions(
+            institution_ids,
+            max_in_flight,
+            progress,
+            "institutions.get_institutions_details_concurrent",
+            |institution_id| self.get_institution_details(institution_id),
+        )
+        .await
+    }
+
+    /// Fetch the students of each of the institutions, like [`Self::get_institutions_details_concurrent`].
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "institutions.get_institutions_students_concurrent",
+            skip(self, institution_ids, progress)
+        )
+    )]
+    pub async fn get_institutions_students_concurrent(
+        &self,
+        institution_ids: impl IntoIterator<Item = BasispoortId>,
+        max_in_flight: NonZeroUsize,
+        progress: Option<&ProgressSender>,
+    ) -> BTreeMap<BasispoortId, Result<InstitutionStudents>> {
+        self.map_institutions(
+            institution_ids,
+            max_in_flight,
+            progress,
+            "institutions.get_institutions_students_concurrent",
+            |institution_id| self.get_institution_students(institution_id),
+        )
+        .await
+    }
+
+    /// Fetch the [`InstitutionBundle`] of each of the institutions, with at most `max_in_flight` institutions
+    /// fetched at a time. As each bundle is fetched with four concurrent requests, up to four times as many
+    /// requests may be in flight.
+    ///
+    /// A failed request does not abort the other institutions: each institution's result is returned separately,
+    /// and reported to `progress`, if given.
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "institutions.fetch_institution_bundles_concurrent",
+            skip(self, institution_ids, progress)
+        )
+    )]
+    pub async fn fetch_institution_bundles_concurrent(
+        &self,
+        institution_ids: impl IntoIterator<Item = BasispoortId>,
+        max_in_flight: NonZeroUsize,
+        progress: Option<&ProgressSender>,
+    ) -> BTreeMap<BasispoortId, Result<InstitutionBundle>> {
+        self.map_institutions(
+            institution_ids,
+            max_in_flight,
+            progress,
+            "institutions.fetch_institution_bundles_concurrent",
+            |institution_id| self.fetch_institution_bundle(institution_id),
+        )
+        .await
+    }
+
+    /// Call `fetch` for each of the institutions, as [`map_bounded`] does, reporting each result to `progress`.
+    async fn map_institutions<T, F, Fut>(
+        &self,
+        institution_ids: impl IntoIterator<Item = BasispoortId>,
+        max_in_flight: NonZeroUsize,
+        progress: Option<&ProgressSender>,
+        operation: &'static str,
+        fetch: F,
+    ) -> BTreeMap<BasispoortId, Result<T>>
+    where
+        F: Fn(BasispoortId) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let institution_ids: Vec<_> = institution_ids.into_iter().collect();
+        let reporter = Mutex::new(ProgressReporter::start(
+            progress,
+            operation,
+            Some(institution_ids.len()),
+        ));
+
+        let results = map_bounded(institution_ids, max_in_flight, |institution_id| {
+            let fetched = fetch(institution_id);
+            let reporter = &reporter;
+            async move {
+                let result = fetched.await;
+                reporter
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .record(result)
+            }
+        })
+        .await;
+
+        reporter
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .finish();
+
+        results.into_iter().collect()
+    }
+
+    /*
+     * Streaming requests
+     */
+
+    /// Stream the details of all institutions, fetching each institution's details only when polled.
+    ///
+    /// This lists the institution IDs, then fetches one institution at a time, so consumers can process
+    /// institutions incrementally, rather than collecting all of them first. A failure to list the institutions
+    /// ends the stream after yielding the error.
+    #[cfg(feature = "stream")]
+    pub fn stream_institution_details(
+        &self,
+    ) -> impl Stream<Item = Result<(BasispoortId, InstitutionDetails)>> + '_ {
+        self.stream_institutions(move |institution_id| self.get_institution_details(institution_id))
+    }
+
+    /// Stream the students of all institutions, like [`Self::stream_institution_details`].
+    #[cfg(feature = "stream")]
+    pub fn stream_institution_students(
+        &self,
+    ) -> impl Stream<Item = Result<(BasispoortId, InstitutionStudents)>> + '_ {
+        self.stream_institutions(move |institution_id| {
+            self.get_institution_students(institution_id)
+        })
+    }
+
+    /// Stream the data fetched by `fetch` for each institution, one at a time.
+    #[cfg(feature = "stream")]
+    fn stream_institutions<'s, T, F, Fut>(
+        &'s self,
+        fetch: F,
+    ) -> impl Stream<Item = Result<(BasispoortId, T)>> + 's
+    where
+        F: Fn(BasispoortId) -> Fut + 's,
+        Fut: Future<Output = Result<T>> + 's,
+    {
+        stream::once_future(self.get_institution_ids())
+            .flat_map(|institution_ids| {
+                stream::iter(match institution_ids {
+                    Ok(institution_ids) => institution_ids.into_iter().map(Ok).collect(),
+                    Err(error) => vec![Err(error)],
+                })
+            })
+            .then(move |institution_id| {
+                let fetched =
+                    institution_id.map(|institution_id| (institution_id, fetch(institution_id)));
+                async move {
+                    let (institution_id, data) = fetched?;
+                    Ok((institution_id, data.await?))
+                }
+            })
+    }
+
+    /*
+     * Change detection
+     */
+
+    /// List the institutions whose data was mutated after `since`, e.g. the start of the previous sync,
+    /// so an incremental sync can skip unchanged institutions.
+    ///
+    /// The institutions service does not list mutations, so this is emulated by comparing the mutation timestamp
+    /// reported with each institution's details, the smallest response carrying one. This makes one request per
+    /// institution, each reported to `progress`, if given, but avoids fetching the rosters of unchanged institutions.
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "institutions.get_institutions_mutated_since",
+            skip(self, progress)
+        )
+    )]
+    pub async fn get_institutions_mutated_since(
+        &self,
+        since: &Timestamp,
+        progress: Option<&ProgressSender>,
+    ) -> Result<Vec<BasispoortId>> {
+        let institution_ids = self.get_institution_ids().await?;
+
+        let mut reporter = ProgressReporter::start(
+            progress,
+            "institutions.get_institutions_mutated_since",
+            Some(institution_ids.len()),
+        );
+        let mutated = async {
+            let mut mutated = Vec::new();
+            for institution_id in institution_ids {
+                let details =
+                    reporter.record(self.get_institution_details(institution_id).await)?;
+                if details.result_metadata.mutation_timestamp > *since {
+                    mutated.push(institution_id);
+                }
+            }
+            Ok::<_, crate::error::Error>(mutated)
+        }
+        .await;
+        reporter.finish();
+        let mutated = mutated?;
+
+        debug!("{} institutions were mutated since {since}.", mutated.len());
+
+        Ok(mutated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_supported_versions_only() {
+        assert_eq!(
+            "v2".parse::<InstitutionsApiVersion>().unwrap(),
+            InstitutionsApiVersion::V2
+        );
+        assert!("v1".parse::<InstitutionsApiVersion>().is_err());
+        assert_eq!(InstitutionsApiVersion::default().to_string(), "v2");
+    }
 }
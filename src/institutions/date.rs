@@ -0,0 +1,117 @@
+//! Date and timestamp types of the institutions service models.
+//!
+//! Dates and timestamps are represented using `chrono` if the `chrono` crate feature is enabled (default),
+//! and using `time` otherwise. If both features are enabled, `chrono` takes precedence,
+//! and the functions in this module convert between both representations.
+
+use crate::clock::Clock;
+
+/// A calendar date, as in [`StaffMember::end_date`][super::StaffMember::end_date].
+#[cfg(feature = "chrono")]
+pub type Date = chrono::NaiveDate;
+
+/// A calendar date, as in [`StaffMember::end_date`][super::StaffMember::end_date].
+#[cfg(not(feature = "chrono"))]
+pub type Date = time::Date;
+
+/// A UTC timestamp, as in [`ResultMetadata`][super::ResultMetadata].
+#[cfg(feature = "chrono")]
+pub type Timestamp = chrono::DateTime<chrono::Utc>;
+
+/// A UTC timestamp, as in [`ResultMetadata`][super::ResultMetadata].
+#[cfg(not(feature = "chrono"))]
+pub type Timestamp = time::OffsetDateTime;
+
+/// The day after `date`, or `None` if it is the last representable date.
+#[cfg(feature = "chrono")]
+pub(crate) fn next_day(date: Date) -> Option<Date> {
+    date.succ_opt()
+}
+
+/// The day after `date`, or `None` if it is the last representable date.
+#[cfg(not(feature = "chrono"))]
+pub(crate) fn next_day(date: Date) -> Option<Date> {
+    date.next_day()
+}
+
+/// The day `days` days before `date`, or `None` if it is before the first representable date.
+#[cfg(feature = "chrono")]
+pub(crate) fn days_before(date: Date, days: u32) -> Option<Date> {
+    date.checked_sub_days(chrono::Days::new(days.into()))
+}
+
+/// The day `days` days before `date`, or `None` if it is before the first representable date.
+#[cfg(not(feature = "chrono"))]
+pub(crate) fn days_before(date: Date, days: u32) -> Option<Date> {
+    date.checked_sub(time::Duration::days(days.into()))
+}
+
+/// The current UTC date, as read from `clock`.
+#[cfg(feature = "chrono")]
+pub fn today(clock: &dyn Clock) -> Date {
+    chrono::DateTime::<chrono::Utc>::from(clock.now()).date_naive()
+}
+
+/// The current UTC date, as read from `clock`.
+#[cfg(not(feature = "chrono"))]
+pub fn today(clock: &dyn Clock) -> Date {
+    time::OffsetDateTime::from(clock.now()).date()
+}
+
+/// Convert a `chrono` date into a `time` date.
+///
+/// Returns `None` if the date is outside of the range supported by `time`.
+#[cfg(all(feature = "chrono", feature = "time"))]
+pub fn date_to_time(date: chrono::NaiveDate) -> Option<time::Date> {
+    use chrono::Datelike;
+
+    time::Date::from_ordinal_date(date.year(), date.ordinal().try_into().ok()?).ok()
+}
+
+/// Convert a `time` date into a `chrono` date.
+///
+/// Returns `None` if the date is outside of the range supported by `chrono`.
+#[cfg(all(feature = "chrono", feature = "time"))]
+pub fn date_from_time(date: time::Date) -> Option<chrono::NaiveDate> {
+    chrono::NaiveDate::from_yo_opt(date.year(), date.ordinal().into())
+}
+
+/// Convert a `chrono` timestamp into a `time` timestamp.
+///
+/// Returns `None` if the timestamp is outside of the range supported by `time`, or is a leap second.
+#[cfg(all(feature = "chrono", feature = "time"))]
+pub fn timestamp_to_time(timestamp: chrono::DateTime<chrono::Utc>) -> Option<time::OffsetDateTime> {
+    time::OffsetDateTime::from_unix_timestamp(timestamp.timestamp())
+        .ok()?
+        .replace_nanosecond(timestamp.timestamp_subsec_nanos())
+        .ok()
+}
+
+/// Convert a `time` timestamp into a `chrono` timestamp.
+///
+/// Returns `None` if the timestamp is outside of the range supported by `chrono`.
+#[cfg(all(feature = "chrono", feature = "time"))]
+pub fn timestamp_from_time(
+    timestamp: time::OffsetDateTime,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::from_timestamp(timestamp.unix_timestamp(), timestamp.nanosecond())
+}
+
+#[cfg(all(test, feature = "chrono", feature = "time"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_between_chrono_and_time() {
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        let converted = date_to_time(date).unwrap();
+        assert_eq!(converted.to_string(), "2024-02-29");
+        assert_eq!(date_from_time(converted), Some(date));
+
+        let timestamp = chrono::DateTime::from_timestamp(1_714_000_000, 123_456_789).unwrap();
+        let converted = timestamp_to_time(timestamp).unwrap();
+        assert_eq!(converted.unix_timestamp(), 1_714_000_000);
+        assert_eq!(converted.nanosecond(), 123_456_789);
+        assert_eq!(timestamp_from_time(converted), Some(timestamp));
+    }
+}
@@ -0,0 +1,281 @@
+//! Import of student administration system ("LAS") roster exports, and comparison with the Basispoort roster.
+//!
+//! Read a [`LasRoster`] from a CSV or JSON export, then [`compare`][LasRoster::compare] it with the
+//! [`InstitutionOverview`] of the institution. The resulting [`RosterComparison`] lists students missing
+//! on either side, and students whose names or group assignments differ, pointing at synchronization problems
+//! between the school's LAS and Basispoort.
+//!
+//! The CSV export has a header row with the columns `lasKey`, `voornaam`, `voorvoegsel`, `achternaam`, `jaargroep`
+//! and `groep`. Only `lasKey` is required. The JSON export is an object with the students in a `leerlingen` array,
+//! using the same keys. `groep` is the name of the group, as shown in the LAS, e.g. `Groep 3a`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+
+use serde::{Deserialize, Serialize};
+#[cfg(not(coverage))]
+use tracing::instrument;
+
+use crate::{error::ErrorKind, BasispoortId, Result};
+
+use super::{AdministrativeKey, InstitutionOverview, InstitutionsServiceClient, Student};
+
+/// A student, as exported by the LAS.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LasStudent {
+    #[serde(rename = "lasKey")]
+    pub administrative_key: AdministrativeKey,
+
+    #[serde(rename = "voornaam", default)]
+    pub first_name: Option<String>,
+
+    #[serde(rename = "voorvoegsel", default)]
+    pub prefix: Option<String>,
+
+    #[serde(rename = "achternaam", default)]
+    pub last_name: Option<String>,
+
+    #[serde(rename = "jaargroep", default)]
+    pub year_group: Option<String>,
+
+    #[serde(rename = "groep", default)]
+    pub group: Option<String>,
+}
+
+/// The students of an institution, as exported by the LAS.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LasRoster {
+    #[serde(rename = "leerlingen")]
+    pub students: Vec<LasStudent>,
+}
+
+/// A field of a student differing between the LAS and Basispoort.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldMismatch {
+    /// The name of the field, e.g. `group`.
+    pub field: &'static str,
+    pub las: Option<String>,
+    pub basispoort: Option<String>,
+}
+
+/// A student present in both the LAS and Basispoort, with differing fields.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StudentMismatch {
+    pub administrative_key: AdministrativeKey,
+    pub basispoort_id: BasispoortId,
+    pub fields: Vec<FieldMismatch>,
+}
+
+/// The differences between a [`LasRoster`] and the roster in Basispoort. Students are matched by their LAS key.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RosterComparison {
+    /// Students in the LAS, but not in Basispoort.
+    pub missing_in_basispoort: Vec<LasStudent>,
+    /// Students in Basispoort, but not in the LAS. Includes students without a LAS key.
+    pub missing_in_las: Vec<BasispoortId>,
+    /// Students in both, with differing names or group assignments, ordered by LAS key.
+    pub mismatches: Vec<StudentMismatch>,
+}
+
+impl RosterComparison {
+    /// Whether the rosters match.
+    pub fn is_empty(&self) -> bool {
+        self.missing_in_basispoort.is_empty()
+            && self.missing_in_las.is_empty()
+            && self.mismatches.is_empty()
+    }
+}
+
+impl LasRoster {
+    /// Read a CSV export.
+    pub fn from_csv(reader: impl Read) -> Result<Self> {
+        let students = csv::Reader::from_reader(reader)
+            .deserialize()
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|error| ErrorKind::ParseLasExport(Box::new(error)))?;
+
+        Ok(Self { students })
+    }
+
+    /// Read a JSON export.
+    pub fn from_json(reader: impl Read) -> Result<Self> {
+        Ok(serde_json::from_reader(reader)
+            .map_err(|error| ErrorKind::ParseLasExport(Box::new(error)))?)
+    }
+
+    /// Compare with the roster in Basispoort.
+    ///
+    /// Empty values count as absent, and values are compared ignoring surrounding whitespace.
+    /// Groups are compared by name, falling back to the LAS key of the Basispoort group if it has no name.
+    pub fn compare(&self, overview: &InstitutionOverview) -> RosterComparison {
+        let group_names: HashMap<&str, &str> = overview
+            .groups
+            .iter()
+            .filter_map(|group| {
+                let key = group.administrative_key.as_deref()?;
+                Some((key, group.name.as_deref().unwrap_or(key)))
+            })
+            .collect();
+
+        let mut basispoort: BTreeMap<&str, &Student> = BTreeMap::new();
+        let mut comparison = RosterComparison::default();
+        for student in &overview.students {
+            match student.administrative_key.as_deref() {
+                Some(key) => {
+                    basispoort.insert(key, student);
+                }
+                None => comparison.missing_in_las.push(student.id),
+            }
+        }
+
+        for las_student in &self.students {
+            let Some(student) = basispoort.remove(las_student.administrative_key.as_str()) else {
+                comparison.missing_in_basispoort.push(las_student.clone());
+                continue;
+            };
+
+            let basispoort_group = student
+                .group
+                .as_deref()
+                .map(|key| group_names.get(key).copied().unwrap_or(key));
+            let fields: Vec<_> = [
+                (
+                    "first_name",
+                    &las_student.first_name,
+                    student.personal_data.first_name.as_deref(),
+                ),
+                (
+                    "prefix",
+                    &las_student.prefix,
+                    student.personal_data.prefix.as_deref(),
+                ),
+                (
+                    "last_name",
+                    &las_student.last_name,
+                    student.personal_data.last_name.as_deref(),
+                ),
+                (
+                    "year_group",
+                    &las_student.year_group,
+                    student.year_group.as_deref(),
+                ),
+                ("group", &las_student.group, basispoort_group),
+            ]
+            .into_iter()
+            .filter_map(|(field, las, basispoort)| {
+                let las = normalize(las.as_deref());
+                let basispoort = normalize(basispoort);
+                (las != basispoort).then(|| FieldMismatch {
+                    field,
+                    las: las.map(Into::into),
+                    basispoort: basispoort.map(Into::into),
+                })
+            })
+            .collect();
+
+            if !fields.is_empty() {
+                comparison.mismatches.push(StudentMismatch {
+                    administrative_key: las_student.administrative_key.clone(),
+                    basispoort_id: student.id,
+                    fields,
+                });
+            }
+        }
+
+        comparison
+            .missing_in_las
+            .extend(basispoort.into_values().map(|student| student.id));
+        comparison
+            .mismatches
+            .sort_by(|a, b| a.administrative_key.cmp(&b.administrative_key));
+
+        comparison
+    }
+}
+
+fn normalize(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|value| !value.is_empty())
+}
+
+impl InstitutionsServiceClient {
+    /// Fetch the overview of the institution, and compare its students with `roster`.
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "institutions.compare_las_roster",
+            skip(self, roster),
+            fields(students.len = roster.students.len())
+        )
+    )]
+    pub async fn compare_las_roster(
+        &self,
+        institution_id: BasispoortId,
+        roster: &LasRoster,
+    ) -> Result<RosterComparison> {
+        let overview = self.get_institution_overview(institution_id).await?;
+
+        Ok(roster.compare(&overview))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OVERVIEW: &str = r#"{
+        "groepen": [{"lasKey": "G3A", "naam": "Groep 3a", "jaargroep": "3"}],
+        "subgroepen": [],
+        "leerlingen": [
+            {"id": 1, "lasKey": "L1", "persoonsgegevens": {"voornaam": "Anna", "achternaam": "Jansen"},
+             "jaargroep": "3", "groep": "G3A", "subgroepen": []},
+            {"id": 2, "lasKey": "L2", "persoonsgegevens": {"voornaam": "Daan", "achternaam": "Visser"},
+             "jaargroep": "3", "groep": "G3A", "subgroepen": []},
+            {"id": 3, "lasKey": "L3", "persoonsgegevens": {"voornaam": "Emma", "achternaam": "Smit"},
+             "jaargroep": "3", "groep": "G3A", "subgroepen": []}
+        ],
+        "medewerkers": [],
+        "actief": true,
+        "metaResult": {"mutationTimestamp": "2024-05-01T00:00:00Z", "generationTimestamp": "2024-05-01T00:00:00Z"}
+    }"#;
+
+    #[test]
+    fn compares_csv_export_with_basispoort_roster() -> Result<()> {
+        let roster = LasRoster::from_csv(
+            "lasKey,voornaam,voorvoegsel,achternaam,jaargroep,groep\n\
+             L1,Anna,,Jansen,3,Groep 3a\n\
+             L2,Daan,,Visser,4,Groep 4a\n\
+             L4,Bram,de,Vries,3,Groep 3a\n"
+                .as_bytes(),
+        )?;
+        let overview: InstitutionOverview = serde_json::from_str(OVERVIEW).unwrap();
+
+        let comparison = roster.compare(&overview);
+        assert_eq!(comparison.missing_in_basispoort.len(), 1);
+        assert_eq!(comparison.missing_in_basispoort[0].administrative_key, "L4");
+        assert_eq!(comparison.missing_in_las, [BasispoortId::from_u32(3)]);
+        assert_eq!(comparison.mismatches.len(), 1);
+        assert_eq!(comparison.mismatches[0].administrative_key, "L2");
+        assert_eq!(
+            comparison.mismatches[0]
+                .fields
+                .iter()
+                .map(|field| field.field)
+                .collect::<Vec<_>>(),
+            ["year_group", "group"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn reads_json_export() -> Result<()> {
+        let roster = LasRoster::from_json(
+            r#"{"leerlingen": [{"lasKey": "L1", "voornaam": "Anna", "groep": "Groep 3a"}]}"#
+                .as_bytes(),
+        )?;
+        assert_eq!(roster.students[0].group.as_deref(), Some("Groep 3a"));
+        assert!(LasRoster::from_json("[]".as_bytes()).is_err());
+
+        Ok(())
+    }
+}
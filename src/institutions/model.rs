@@ -1,14 +1,20 @@
 use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
 
-use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
+use crate::redact::redacted;
 use crate::BasispoortId;
 
+use super::date::{Date, Timestamp};
+
 // LasKey
 pub type AdministrativeKey = String;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct InstitutionOverview {
     #[serde(rename = "groepen")]
     pub groups: Vec<Group>,
@@ -25,14 +31,15 @@ pub struct InstitutionOverview {
     #[serde(rename = "actief")]
     pub active: bool,
 
-    #[serde(rename = "gefuseerdNaar")]
+    #[serde(rename = "gefuseerdNaar", alias = "gefuseerdnaar")]
     pub merged_into: Option<BasispoortId>,
 
     #[serde(rename = "metaResult")]
     pub result_metadata: ResultMetadata,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct InstitutionDetails {
     #[serde(rename = "naam")]
     pub name: Option<String>,
@@ -43,7 +50,7 @@ pub struct InstitutionDetails {
     #[serde(rename = "huisnummer")]
     pub house_number: Option<String>,
 
-    #[serde(rename = "huisnummertoevoeging")]
+    #[serde(rename = "huisnummertoevoeging", alias = "huisnummerToevoeging")]
     pub house_number_postfix: Option<String>,
 
     #[serde(rename = "postcode")]
@@ -52,7 +59,7 @@ pub struct InstitutionDetails {
     #[serde(rename = "woonplaats")]
     pub city: Option<String>,
 
-    #[serde(rename = "brincode")]
+    #[serde(rename = "brincode", alias = "brinCode")]
     pub brin_code: Option<String>,
 
     #[serde(rename = "dependancecode")]
@@ -64,20 +71,21 @@ pub struct InstitutionDetails {
     #[serde(rename = "instellingRef")]
     pub shortcut_reference: Option<String>,
 
-    #[serde(rename = "bestuurscode")]
+    #[serde(rename = "bestuurscode", alias = "bestuursCode")]
     pub governance_code: Option<String>,
 
     #[serde(rename = "actief")]
     pub active: bool,
 
-    #[serde(rename = "gefuseerdNaar")]
+    #[serde(rename = "gefuseerdNaar", alias = "gefuseerdnaar")]
     pub merged_into: Option<BasispoortId>,
 
     #[serde(rename = "metaResult")]
     pub result_metadata: ResultMetadata,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct InstitutionGroups {
     #[serde(rename = "groepen")]
     pub groups: Vec<Group>,
@@ -89,7 +97,8 @@ pub struct InstitutionGroups {
     pub result_metadata: ResultMetadata,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct InstitutionStudents {
     #[serde(rename = "leerlingen")]
     pub students: Vec<Student>,
@@ -98,7 +107,8 @@ pub struct InstitutionStudents {
     pub result_metadata: ResultMetadata,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct InstitutionStaff {
     #[serde(rename = "medewerkers")]
     pub staff: Vec<StaffMember>,
@@ -107,9 +117,22 @@ pub struct InstitutionStaff {
     pub result_metadata: ResultMetadata,
 }
 
-#[derive(Debug, Deserialize)]
+/// An institution's details, groups, students and staff, as fetched concurrently by
+/// [`InstitutionsServiceClient::fetch_institution_bundle`][super::InstitutionsServiceClient::fetch_institution_bundle].
+#[derive(Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct InstitutionBundle {
+    pub institution_id: BasispoortId,
+    pub details: InstitutionDetails,
+    pub groups: InstitutionGroups,
+    pub students: InstitutionStudents,
+    pub staff: InstitutionStaff,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct Group {
-    #[serde(rename = "lasKey")]
+    #[serde(rename = "lasKey", alias = "laskey")]
     pub administrative_key: Option<AdministrativeKey>,
 
     #[serde(rename = "naam")]
@@ -122,14 +145,15 @@ pub struct Group {
     pub description: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct Student {
     pub id: BasispoortId,
 
-    #[serde(rename = "eckid")]
+    #[serde(rename = "eckid", alias = "eckId")]
     pub chain_id: Option<String>,
 
-    #[serde(rename = "lasKey")]
+    #[serde(rename = "lasKey", alias = "laskey")]
     pub administrative_key: Option<AdministrativeKey>,
 
     #[serde(rename = "persoonsgegevens")]
@@ -145,24 +169,25 @@ pub struct Student {
     pub sub_groups: Vec<AdministrativeKey>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct StaffMember {
     pub id: BasispoortId,
 
-    #[serde(rename = "eckid")]
+    #[serde(rename = "eckid", alias = "eckId")]
     pub chain_id: Option<String>,
 
-    #[serde(rename = "lasKey")]
+    #[serde(rename = "lasKey", alias = "laskey")]
     pub administrative_key: Option<AdministrativeKey>,
 
     #[serde(rename = "persoonsgegevens")]
     pub personal_data: PersonalData,
 
-    #[serde(rename = "emailadres")]
+    #[serde(rename = "emailadres", alias = "emailAdres")]
     pub email: Option<String>,
 
     #[serde(rename = "einddatum")]
-    pub end_date: Option<NaiveDate>,
+    pub end_date: Option<Date>,
 
     #[serde(rename = "rollen")]
     pub roles: HashSet<StaffMemberRole>,
@@ -174,7 +199,7 @@ pub struct StaffMember {
     pub sub_groups: Vec<AdministrativeKey>,
 }
 
-#[derive(Debug, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
 pub enum StaffMemberRole {
     #[serde(rename = "Leerkracht")]
     Teacher,
@@ -188,7 +213,96 @@ pub enum StaffMemberRole {
     ReplacementTeacher,
 }
 
-#[derive(Debug, Deserialize)]
+// Debug implementations redact personal data.
+
+impl fmt::Debug for Student {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Student")
+            .field("id", &self.id)
+            .field("chain_id", &redacted(&self.chain_id))
+            .field("administrative_key", &self.administrative_key)
+            .field("personal_data", &self.personal_data)
+            .field("year_group", &self.year_group)
+            .field("group", &self.group)
+            .field("sub_groups", &self.sub_groups)
+            .finish()
+    }
+}
+
+impl fmt::Debug for StaffMember {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StaffMember")
+            .field("id", &self.id)
+            .field("chain_id", &redacted(&self.chain_id))
+            .field("administrative_key", &self.administrative_key)
+            .field("personal_data", &self.personal_data)
+            .field("email", &redacted(&self.email))
+            .field("end_date", &self.end_date)
+            .field("roles", &self.roles)
+            .field("groups", &self.groups)
+            .field("sub_groups", &self.sub_groups)
+            .finish()
+    }
+}
+
+impl fmt::Debug for PersonalData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PersonalData")
+            .field("last_name", &redacted(&self.last_name))
+            .field("first_name", &redacted(&self.first_name))
+            .field("prefix", &redacted(&self.prefix))
+            .field("initials", &redacted(&self.initials))
+            .finish()
+    }
+}
+
+impl StaffMemberRole {
+    /// The role's name, as used by the Basispoort API.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Teacher => "Leerkracht",
+            Self::ITCoordinator => "ICTCoordinator",
+            Self::AssistantTeacher => "IBRTer",
+            Self::TraineeTeacher => "Stagiair",
+            Self::ReplacementTeacher => "Inval",
+        }
+    }
+}
+
+impl fmt::Display for StaffMemberRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// [`StaffMemberRole`] parse error.
+#[derive(Error, Debug)]
+pub enum ParseStaffMemberRoleError {
+    #[error("'{0}' is not a valid staff member role string")]
+    InvalidStaffMemberRoleString(String),
+}
+
+impl FromStr for StaffMemberRole {
+    type Err = ParseStaffMemberRoleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "Leerkracht" => Self::Teacher,
+            "ICTCoordinator" => Self::ITCoordinator,
+            "IBRTer" => Self::AssistantTeacher,
+            "Stagiair" => Self::TraineeTeacher,
+            "Inval" => Self::ReplacementTeacher,
+            s => {
+                return Err(ParseStaffMemberRoleError::InvalidStaffMemberRoleString(
+                    s.into(),
+                ))
+            }
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct PersonalData {
     #[serde(rename = "achternaam")]
     pub last_name: Option<String>,
@@ -203,20 +317,25 @@ pub struct PersonalData {
     pub initials: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
+#[non_exhaustive]
 pub struct ResultMetadata {
-    pub mutation_timestamp: chrono::DateTime<chrono::Utc>,
-    pub generation_timestamp: chrono::DateTime<chrono::Utc>,
+    #[cfg_attr(not(feature = "chrono"), serde(with = "time::serde::rfc3339"))]
+    pub mutation_timestamp: Timestamp,
+    #[cfg_attr(not(feature = "chrono"), serde(with = "time::serde::rfc3339"))]
+    pub generation_timestamp: Timestamp,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
+#[non_exhaustive]
 pub struct SynchronizationPermission {
     pub has_synchronization_permission: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct InstitutionSearchResult {
     pub id: BasispoortId,
 
@@ -225,7 +344,7 @@ pub struct InstitutionSearchResult {
 
     // Note: In opposition to `InstitutionDetails`, this `brin_code` field
     //       includes the "dependancecode" / `branch_code`!
-    #[serde(rename = "brincode")]
+    #[serde(rename = "brincode", alias = "brinCode")]
     pub brin_code: Option<String>,
 
     #[serde(rename = "straat")]
@@ -234,7 +353,7 @@ pub struct InstitutionSearchResult {
     #[serde(rename = "huisnummer")]
     pub house_number: Option<String>,
 
-    #[serde(rename = "huisnummerToevoeging")]
+    #[serde(rename = "huisnummerToevoeging", alias = "huisnummertoevoeging")]
     pub house_number_postfix: Option<String>,
 
     #[serde(rename = "postcode")]
@@ -249,17 +368,18 @@ pub struct InstitutionSearchResult {
     #[serde(rename = "telefoonAbon")]
     pub phone_subscriber_number: Option<String>,
 
-    #[serde(rename = "emailadres")]
+    #[serde(rename = "emailadres", alias = "emailAdres")]
     pub email_address: Option<String>,
 
     #[serde(rename = "actief")]
     pub active: bool,
 
-    #[serde(rename = "bestuurscode")]
+    #[serde(rename = "bestuurscode", alias = "bestuursCode")]
     pub governance_code: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
+#[non_exhaustive]
 pub struct InstitutionsSearchPredicate<'a> {
     #[serde(rename = "naam")]
     pub name: Option<&'a str>,
@@ -359,3 +479,29 @@ impl<'a> InstitutionsSearchPredicate<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_students() {
+        let json = serde_json::json!({
+            "id": 7,
+            "eckid": "https://id.school/LL_abc",
+            "lasKey": "123",
+            "persoonsgegevens": {
+                "achternaam": "Jansen",
+                "voornaam": "Anna",
+                "voorvoegsel": null,
+                "voorletters": "A."
+            },
+            "jaargroep": "5",
+            "groep": "5a",
+            "subgroepen": ["rekenen"]
+        });
+
+        let student: Student = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&student).unwrap(), json);
+    }
+}
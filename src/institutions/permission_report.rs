@@ -0,0 +1,242 @@
+//! Summaries of synchronization permission mutations over a period, e.g. for a weekly operations report.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Serialize;
+#[cfg(not(coverage))]
+use tracing::instrument;
+
+use crate::{clock::Clock, BasispoortId, Result};
+
+use super::client::InstitutionsServiceClient;
+use super::date::{days_before, next_day, today, Date};
+
+/// The institutions which granted or revoked synchronization permission on a day.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DailyPermissionMutations {
+    pub date: Date,
+    pub granted: Vec<BasispoortId>,
+    pub revoked: Vec<BasispoortId>,
+}
+
+/// Synchronization permission mutations over a period of days.
+///
+/// Displays as a human-readable report, and serializes for further processing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PermissionMutationSummary {
+    /// The mutations per day, in chronological order.
+    pub days: Vec<DailyPermissionMutations>,
+    /// Institutions which did not have permission before the period, but do at its end.
+    pub new_institutions: Vec<BasispoortId>,
+    /// Institutions which had permission before the period, but no longer do at its end.
+    pub churned_institutions: Vec<BasispoortId>,
+}
+
+/// The net mutation of an institution on a single day.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Mutation {
+    Granted,
+    Revoked,
+    /// Granted and revoked on the same day, in unknown order.
+    Both,
+}
+
+impl PermissionMutationSummary {
+    /// Summarize `days` of mutations, which are sorted chronologically.
+    ///
+    /// An institution granting and revoking permission on the same day is counted as neither new nor churned,
+    /// as the order of its mutations is unknown.
+    pub fn new(mut days: Vec<DailyPermissionMutations>) -> Self {
+        days.sort_by_key(|day| day.date);
+
+        let mut mutations: BTreeMap<BasispoortId, Vec<Mutation>> = BTreeMap::new();
+        for day in &days {
+            let mut today: BTreeMap<BasispoortId, Mutation> = BTreeMap::new();
+            for &institution_id in &day.granted {
+                today.insert(institution_id, Mutation::Granted);
+            }
+            for &institution_id in &day.revoked {
+                today
+                    .entry(institution_id)
+                    .and_modify(|mutation| {
+                        if *mutation == Mutation::Granted {
+                            *mutation = Mutation::Both;
+                        }
+                    })
+                    .or_insert(Mutation::Revoked);
+            }
+            for (institution_id, mutation) in today {
+                mutations.entry(institution_id).or_default().push(mutation);
+            }
+        }
+
+        let mut new_institutions = Vec::new();
+        let mut churned_institutions = Vec::new();
+        for (institution_id, mutations) in mutations {
+            match (mutations.first(), mutations.last()) {
+                (Some(Mutation::Granted), Some(Mutation::Granted)) => {
+                    new_institutions.push(institution_id)
+                }
+                (Some(Mutation::Revoked), Some(Mutation::Revoked)) => {
+                    churned_institutions.push(institution_id)
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            days,
+            new_institutions,
+            churned_institutions,
+        }
+    }
+
+    /// The number of grants over all days.
+    pub fn total_granted(&self) -> usize {
+        self.days.iter().map(|day| day.granted.len()).sum()
+    }
+
+    /// The number of revocations over all days.
+    pub fn total_revoked(&self) -> usize {
+        self.days.iter().map(|day| day.revoked.len()).sum()
+    }
+}
+
+impl fmt::Display for PermissionMutationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.days.first(), self.days.last()) {
+            (Some(first), Some(last)) => writeln!(
+                f,
+                "Synchronization permission mutations from {} to {}",
+                first.date, last.date
+            )?,
+            _ => return writeln!(f, "No synchronization permission mutations"),
+        }
+
+        for day in &self.days {
+            writeln!(
+                f,
+                "  {}: {} granted, {} revoked",
+                day.date,
+                day.granted.len(),
+                day.revoked.len()
+            )?;
+        }
+        writeln!(
+            f,
+            "Total: {} granted, {} revoked",
+            self.total_granted(),
+            self.total_revoked()
+        )?;
+
+        for (label, institution_ids) in [
+            ("New institutions", &self.new_institutions),
+            ("Churned institutions", &self.churned_institutions),
+        ] {
+            write!(f, "{label} ({}):", institution_ids.len())?;
+            for institution_id in institution_ids {
+                write!(f, " {institution_id}")?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl InstitutionsServiceClient {
+    /// Fetch the synchronization permission mutations of each day from `from` to `to` (inclusive), and summarize them.
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "institutions.summarize_permission_mutations", skip(self))
+    )]
+    pub async fn summarize_permission_mutations(
+        &self,
+        from: Date,
+        to: Date,
+    ) -> Result<PermissionMutationSummary> {
+        let mut days = Vec::new();
+        let mut date = Some(from);
+        while let Some(day) = date.filter(|day| *day <= to) {
+            days.push(DailyPermissionMutations {
+                date: day,
+                granted: self.get_synchronization_permissions_granted(&day).await?,
+                revoked: self.get_synchronization_permissions_revoked(&day).await?,
+            });
+            date = next_day(day);
+        }
+
+        Ok(PermissionMutationSummary::new(days))
+    }
+
+    /// Like [`Self::summarize_permission_mutations`], for the last `days` days up to and including today,
+    /// as read from `clock`.
+    #[cfg_attr(
+        not(coverage),
+        instrument(
+            name = "institutions.summarize_recent_permission_mutations",
+            skip(self)
+        )
+    )]
+    pub async fn summarize_recent_permission_mutations(
+        &self,
+        days: u32,
+        clock: &dyn Clock,
+    ) -> Result<PermissionMutationSummary> {
+        let to = today(clock);
+        let Some(from) = days.checked_sub(1).and_then(|days| days_before(to, days)) else {
+            return Ok(PermissionMutationSummary::new(Vec::new()));
+        };
+
+        self.summarize_permission_mutations(from, to).await
+    }
+}
+
+#[cfg(all(test, feature = "chrono"))]
+mod tests {
+    use super::*;
+
+    fn day(day: u32, granted: &[u32], revoked: &[u32]) -> DailyPermissionMutations {
+        DailyPermissionMutations {
+            date: Date::from_ymd_opt(2024, 5, day).unwrap(),
+            granted: granted
+                .iter()
+                .copied()
+                .map(BasispoortId::from_u32)
+                .collect(),
+            revoked: revoked
+                .iter()
+                .copied()
+                .map(BasispoortId::from_u32)
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn summarizes_net_mutations() {
+        let summary = PermissionMutationSummary::new(vec![
+            day(2, &[1, 3], &[2, 4]),
+            day(1, &[2], &[5]),
+            day(3, &[4], &[3, 6]),
+        ]);
+
+        assert_eq!(summary.total_granted(), 4);
+        assert_eq!(summary.total_revoked(), 5);
+        assert_eq!(summary.new_institutions, [BasispoortId::from_u32(1)]);
+        assert_eq!(
+            summary.churned_institutions,
+            [5, 6].map(BasispoortId::from_u32)
+        );
+        assert_eq!(
+            summary.to_string(),
+            "Synchronization permission mutations from 2024-05-01 to 2024-05-03\n\
+             \x20 2024-05-01: 1 granted, 1 revoked\n\
+             \x20 2024-05-02: 2 granted, 2 revoked\n\
+             \x20 2024-05-03: 1 granted, 2 revoked\n\
+             Total: 4 granted, 5 revoked\n\
+             New institutions (1): 1\n\
+             Churned institutions (2): 5 6\n"
+        );
+    }
+}
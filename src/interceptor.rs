@@ -0,0 +1,64 @@
+//! Hooks into every request of a [`RestClient`][crate::rest::RestClient], e.g. to add headers, sign requests,
+//! or log request and response metadata centrally.
+//!
+//! Register a [`RequestInterceptor`] with
+//! [`RestClientBuilder::interceptor`][crate::rest::RestClientBuilder::interceptor]. Interceptors are called
+//! in the order they were registered: [`RequestInterceptor::before_send`] once the request is built, and
+//! [`RequestInterceptor::after_receive`] once the response headers have arrived, before error statuses are handled.
+
+use std::fmt::Debug;
+
+use reqwest::{header::HeaderMap, Method, Request, Response};
+
+/// A hook called for each request and response of a [`RestClient`][crate::rest::RestClient].
+///
+/// Interceptors are called synchronously, so implementations should not block for long.
+pub trait RequestInterceptor: Debug + Send + Sync {
+    /// Inspect or modify `request` before it is sent.
+    fn before_send(&self, _request: &mut Request) {}
+
+    /// Inspect the `response` to a request with `method`. Not called if no response was received.
+    fn after_receive(&self, _method: &Method, _response: &Response) {}
+}
+
+/// Inserts fixed headers into each request, replacing headers of the same name.
+#[derive(Debug, Clone, Default)]
+pub struct StaticHeaders {
+    headers: HeaderMap,
+}
+
+impl StaticHeaders {
+    pub fn new(headers: HeaderMap) -> Self {
+        Self { headers }
+    }
+}
+
+impl RequestInterceptor for StaticHeaders {
+    fn before_send(&self, request: &mut Request) {
+        for (name, value) in &self.headers {
+            request.headers_mut().insert(name, value.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::{HeaderName, HeaderValue};
+
+    use super::*;
+
+    #[test]
+    fn inserts_static_headers() {
+        let name = HeaderName::from_static("x-tenant");
+        let mut headers = HeaderMap::new();
+        headers.insert(name.clone(), HeaderValue::from_static("school-board"));
+
+        let mut request = Request::new(Method::GET, "https://example.com/".parse().unwrap());
+        request
+            .headers_mut()
+            .insert(name.clone(), HeaderValue::from_static("replaced"));
+        StaticHeaders::new(headers).before_send(&mut request);
+
+        assert_eq!(request.headers()[&name], "school-board");
+    }
+}
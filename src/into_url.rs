@@ -0,0 +1,43 @@
+use crate::{error::ErrorKind, Result, Url};
+
+/// A value convertible into a [`Url`]: either an already parsed `Url`, or a string to be parsed.
+pub trait IntoUrl {
+    /// Convert into a `Url`, failing with [`ErrorKind::ParseUrl`] if a string is not a valid URL.
+    fn into_url(self) -> Result<Url>;
+}
+
+impl IntoUrl for Url {
+    fn into_url(self) -> Result<Url> {
+        Ok(self)
+    }
+}
+
+impl IntoUrl for &Url {
+    fn into_url(self) -> Result<Url> {
+        Ok(self.clone())
+    }
+}
+
+impl IntoUrl for &str {
+    fn into_url(self) -> Result<Url> {
+        Url::parse(self).map_err(|source| {
+            ErrorKind::ParseUrl {
+                url: self.to_string(),
+                source,
+            }
+            .into()
+        })
+    }
+}
+
+impl IntoUrl for &String {
+    fn into_url(self) -> Result<Url> {
+        self.as_str().into_url()
+    }
+}
+
+impl IntoUrl for String {
+    fn into_url(self) -> Result<Url> {
+        self.as_str().into_url()
+    }
+}
@@ -0,0 +1,275 @@
+//! Leases preventing concurrent syncs of the same identity code or institution.
+//!
+//! A [`Lease`] is recorded in a [`StateStore`] shared by all sync processes.
+//! It expires after its time to live, so a crashed process cannot block others forever.
+
+use std::fmt::Display;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+#[cfg(not(coverage))]
+use tracing::instrument;
+use tracing::{debug, warn};
+
+use crate::{
+    clock::{Clock, SystemClock},
+    error::ErrorKind,
+    state::StateStore,
+    BasispoortId, Result,
+};
+
+/// What a lease protects from concurrent syncs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LeaseScope {
+    /// A hosted license provider identity code.
+    IdentityCode(String),
+    /// An institution.
+    Institution(BasispoortId),
+}
+
+impl LeaseScope {
+    fn key(&self) -> String {
+        match self {
+            LeaseScope::IdentityCode(identity_code) => {
+                format!("lease/identity-code/{identity_code}")
+            }
+            LeaseScope::Institution(institution_id) => {
+                format!("lease/institution/{institution_id}")
+            }
+        }
+    }
+}
+
+impl Display for LeaseScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LeaseScope::IdentityCode(identity_code) => write!(f, "identity code '{identity_code}'"),
+            LeaseScope::Institution(institution_id) => write!(f, "institution {institution_id}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct LeaseRecord {
+    holder: String,
+    expires_at: SystemTime,
+}
+
+/// A held lease. Release it with [`Lease::release`] when the sync is done.
+#[derive(Debug)]
+pub struct Lease {
+    store: Arc<dyn StateStore>,
+    clock: Arc<dyn Clock>,
+    key: String,
+    record: LeaseRecord,
+}
+
+impl Lease {
+    /// Acquire the lease on `scope` for `holder`, e.g. a host name and process ID.
+    ///
+    /// Fails with [`ErrorKind::SyncLocked`] if another holder has an unexpired lease on the same scope.
+    /// A holder may re-acquire its own lease.
+    #[cfg_attr(not(coverage), instrument(skip(store)))]
+    pub async fn acquire(
+        store: Arc<dyn StateStore>,
+        scope: &LeaseScope,
+        holder: &str,
+        time_to_live: Duration,
+    ) -> Result<Self> {
+        Self::acquire_with_clock(store, Arc::new(SystemClock), scope, holder, time_to_live).await
+    }
+
+    /// Like [`Lease::acquire`], reading the time from `clock`, also when renewing the lease.
+    #[cfg_attr(not(coverage), instrument(skip(store)))]
+    pub async fn acquire_with_clock(
+        store: Arc<dyn StateStore>,
+        clock: Arc<dyn Clock>,
+        scope: &LeaseScope,
+        holder: &str,
+        time_to_live: Duration,
+    ) -> Result<Self> {
+        let key = scope.key();
+
+        loop {
+            let current = store.get(&key).await?;
+            if let Some(value) = &current {
+                let held = decode(&key, value)?;
+                if held.holder != holder && held.expires_at > clock.now() {
+                    return Err(ErrorKind::SyncLocked {
+                        scope: scope.to_string(),
+                        holder: held.holder,
+                        expires_at: held.expires_at,
+                    }
+                    .into());
+                }
+            }
+
+            let record = LeaseRecord {
+                holder: holder.to_owned(),
+                expires_at: clock.now() + time_to_live,
+            };
+            if store
+                .compare_and_swap(&key, current.as_ref(), Some(encode(&key, &record)?))
+                .await?
+            {
+                debug!("Acquired lease on {scope}.");
+                return Ok(Self {
+                    store,
+                    clock,
+                    key,
+                    record,
+                });
+            }
+
+            // Another process modified the lease in the meantime. Check again.
+        }
+    }
+
+    /// When the lease expires, unless renewed.
+    pub fn expires_at(&self) -> SystemTime {
+        self.record.expires_at
+    }
+
+    /// Extend the lease by `time_to_live` from now.
+    ///
+    /// Fails with [`ErrorKind::LeaseLost`] if the lease expired and was taken over by another holder.
+    #[cfg_attr(not(coverage), instrument)]
+    pub async fn renew(&mut self, time_to_live: Duration) -> Result<()> {
+        let current = encode(&self.key, &self.record)?;
+        let record = LeaseRecord {
+            holder: self.record.holder.clone(),
+            expires_at: self.clock.now() + time_to_live,
+        };
+
+        if !self
+            .store
+            .compare_and_swap(&self.key, Some(&current), Some(encode(&self.key, &record)?))
+            .await?
+        {
+            return Err(ErrorKind::LeaseLost {
+                key: self.key.clone(),
+            }
+            .into());
+        }
+
+        self.record = record;
+        Ok(())
+    }
+
+    /// Release the lease, allowing other holders to acquire it.
+    #[cfg_attr(not(coverage), instrument)]
+    pub async fn release(self) -> Result<()> {
+        let current = encode(&self.key, &self.record)?;
+        if !self
+            .store
+            .compare_and_swap(&self.key, Some(&current), None)
+            .await?
+        {
+            warn!(
+                "Lease '{}' was taken over by another holder before release.",
+                self.key
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn encode(key: &str, record: &LeaseRecord) -> Result<serde_json::Value> {
+    serde_json::to_value(record).map_err(|source| {
+        ErrorKind::StateRecord {
+            key: key.to_owned(),
+            source,
+        }
+        .into()
+    })
+}
+
+fn decode(key: &str, value: &serde_json::Value) -> Result<LeaseRecord> {
+    LeaseRecord::deserialize(value).map_err(|source| {
+        ErrorKind::StateRecord {
+            key: key.to_owned(),
+            source,
+        }
+        .into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::state::MemoryStateStore;
+
+    const TIME_TO_LIVE: Duration = Duration::from_secs(60);
+
+    #[tokio::test]
+    async fn excludes_other_holders() -> Result<()> {
+        let store: Arc<dyn StateStore> = Arc::new(MemoryStateStore::new());
+        let scope = LeaseScope::IdentityCode("publisher".into());
+
+        let lease = Lease::acquire(store.clone(), &scope, "worker-1", TIME_TO_LIVE).await?;
+
+        let error = Lease::acquire(store.clone(), &scope, "worker-2", TIME_TO_LIVE)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(error.kind(), ErrorKind::SyncLocked { holder, .. } if holder == "worker-1")
+        );
+
+        // Other scopes are not affected.
+        Lease::acquire(
+            store.clone(),
+            &LeaseScope::Institution(BasispoortId::from_u32(1)),
+            "worker-2",
+            TIME_TO_LIVE,
+        )
+        .await?;
+
+        lease.release().await?;
+        Lease::acquire(store, &scope, "worker-2", TIME_TO_LIVE).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn takes_over_expired_lease() -> Result<()> {
+        let store: Arc<dyn StateStore> = Arc::new(MemoryStateStore::new());
+        let scope = LeaseScope::Institution(BasispoortId::from_u32(42));
+
+        let mut expired = Lease::acquire(store.clone(), &scope, "worker-1", Duration::ZERO).await?;
+        Lease::acquire(store, &scope, "worker-2", TIME_TO_LIVE).await?;
+
+        let error = expired.renew(TIME_TO_LIVE).await.unwrap_err();
+        assert!(matches!(error.kind(), ErrorKind::LeaseLost { .. }));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn expires_by_the_clock() -> Result<()> {
+        let store: Arc<dyn StateStore> = Arc::new(MemoryStateStore::new());
+        let clock = ManualClock::new(SystemTime::UNIX_EPOCH);
+        let scope = LeaseScope::Institution(BasispoortId::from_u32(42));
+
+        let acquire = |holder| {
+            Lease::acquire_with_clock(
+                store.clone(),
+                Arc::new(clock.clone()),
+                &scope,
+                holder,
+                TIME_TO_LIVE,
+            )
+        };
+
+        acquire("worker-1").await?;
+        clock.advance(TIME_TO_LIVE - Duration::from_secs(1));
+        assert!(acquire("worker-2").await.is_err());
+
+        clock.advance(Duration::from_secs(1));
+        acquire("worker-2").await?;
+
+        Ok(())
+    }
+}
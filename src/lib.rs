@@ -1,17 +1,115 @@
+pub use basispoort_id::{BasispoortId, ParseBasispoortIdError};
+pub use into_url::IntoUrl;
 pub use url::Url;
 
+mod basispoort_id;
+mod into_url;
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+
+#[cfg(all(feature = "test-support", not(target_arch = "wasm32")))]
+pub mod assertions;
+
+pub mod changes;
+
+pub mod checkpoint;
+
+pub mod clock;
+
+mod concurrency;
+
+#[cfg(feature = "chaos")]
+pub mod chaos;
+
+#[cfg(feature = "config")]
+pub mod config;
+
+pub mod dead_letter;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod debug_capture;
+
+pub mod deprecation;
+
+#[cfg(any(
+    feature = "hosted-license-provider",
+    feature = "institutions",
+    feature = "license-gateway"
+))]
+pub mod dry_run;
+
+#[cfg(feature = "config")]
+pub mod diagnostics;
+
+pub mod environments;
+
 pub mod error;
 
+pub mod error_translation;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod events;
+
+pub mod field_aliases;
+
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+
 #[cfg(feature = "hosted-license-provider")]
 pub mod hosted_license_provider;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod identity;
+
 #[cfg(feature = "institutions")]
 pub mod institutions;
 
-// TODO: Add licenses client. (crate feature)
+pub mod interceptor;
+
+#[cfg(feature = "license-gateway")]
+pub mod license_gateway;
+
+pub mod lease;
+
+#[cfg(all(feature = "metrics", not(target_arch = "wasm32")))]
+pub mod metrics;
+
+pub mod mutation_queue;
+
+pub mod progress;
+
+pub mod publisher;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod rate_limit;
+
+mod redact;
+
+pub mod response_cache;
 
 pub mod rest;
 
-pub type Result<T> = std::result::Result<T, Box<crate::error::Error>>;
+pub mod rollback;
+
+#[cfg(feature = "schema-drift")]
+pub mod schema_drift;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod scheduler;
+
+pub mod shard;
+
+pub mod state;
+
+#[cfg(feature = "test-support")]
+pub mod test_support;
+
+pub mod transport;
+
+pub mod types;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod runtime;
 
-pub type BasispoortId = i64; // Defined as signed `int64`, as OpenAPI knows no unsigned types. ¯\_(ツ)_/¯
+pub type Result<T> = std::result::Result<T, crate::error::Error>;
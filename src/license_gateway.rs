@@ -0,0 +1,10 @@
+//! Client for the classic (non-hosted) license gateway ("LiKa") integration.
+//!
+//! [`LicenseGatewayClient`] sends requests relative to the license service base path, deserializing responses into
+//! caller-provided types, while sharing the [`RestClient`][crate::rest::RestClient] configuration, the crate's
+//! [`Error`][crate::error::Error] model, and the [dry runs][crate::dry_run] and production safeguards
+//! of the other service clients.
+
+pub use client::*;
+
+mod client;
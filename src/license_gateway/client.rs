@@ -0,0 +1,135 @@
+use std::fmt::Debug;
+
+use reqwest::Method;
+use serde::{de::DeserializeOwned, Serialize};
+#[cfg(not(coverage))]
+use tracing::instrument;
+
+use crate::{
+    dry_run::{DryRun, PlannedRequest},
+    error::ErrorKind,
+    rest,
+    transport::Transport,
+    Result,
+};
+
+/// An API client for the license gateway service ("LiKa").
+///
+/// Sends its requests through a [`Transport`], usually a [`RestClient`][rest::RestClient].
+#[derive(Debug, Clone)]
+pub struct LicenseGatewayClient<C: Transport = rest::RestClient> {
+    transport: C,
+    allow_destructive: bool,
+    dry_run: DryRun,
+}
+
+impl<C: Transport> LicenseGatewayClient<C> {
+    /// Create a client sending its requests through `transport`.
+    ///
+    /// If `transport` connects to the production environment, `DELETE` requests are blocked,
+    /// unless allowed with [`Self::allow_destructive`].
+    #[cfg_attr(not(coverage), instrument)]
+    pub fn new(transport: &C) -> Self {
+        LicenseGatewayClient {
+            transport: transport.clone(),
+            allow_destructive: !transport.is_production(),
+            dry_run: DryRun::default(),
+        }
+    }
+
+    /// Allow `DELETE` requests in the production environment.
+    ///
+    /// Without this, they fail with [`ErrorKind::DestructiveOperationBlocked`]. Other environments always allow them.
+    pub fn allow_destructive(self) -> Self {
+        Self {
+            allow_destructive: true,
+            ..self
+        }
+    }
+
+    /// Log and record the `POST`, `PUT` and `DELETE` requests, instead of sending them.
+    ///
+    /// `GET` requests are still sent. Get the recorded requests with [`Self::planned_requests`].
+    /// Clones of the client share their records. Enabling a dry run discards earlier records.
+    pub fn dry_run(self, enabled: bool) -> Self {
+        Self {
+            dry_run: DryRun::new(enabled),
+            ..self
+        }
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run.is_enabled()
+    }
+
+    /// The requests not sent during the [dry run][Self::dry_run], in order.
+    pub fn planned_requests(&self) -> Vec<PlannedRequest> {
+        self.dry_run.planned_requests()
+    }
+
+    fn make_path(&self, path: &str) -> String {
+        format!("{}{}", self.transport.service_paths().license_gateway, path)
+    }
+
+    /// `GET` the endpoint at `path`, relative to the license service base path.
+    #[cfg_attr(not(coverage), instrument(name = "license_gateway.get", skip(self)))]
+    pub async fn get<T: DeserializeOwned + Debug + Send>(&self, path: &str) -> Result<T> {
+        self.transport.get(&self.make_path(path)).await
+    }
+
+    /// `POST` `payload` to the endpoint at `path`, relative to the license service base path.
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "license_gateway.post", skip(self, payload))
+    )]
+    pub async fn post<P: Serialize + Debug + Sync + ?Sized, T: DeserializeOwned + Debug + Send>(
+        &self,
+        path: &str,
+        payload: &P,
+    ) -> Result<T> {
+        let path = self.make_path(path);
+        if let Some(response) = self.dry_run.intercept(Method::POST, &path, Some(payload)) {
+            return response;
+        }
+
+        self.transport.post(&path, payload).await
+    }
+
+    /// `PUT` `payload` to the endpoint at `path`, relative to the license service base path.
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "license_gateway.put", skip(self, payload))
+    )]
+    pub async fn put<P: Serialize + Debug + Sync + ?Sized, T: DeserializeOwned + Debug + Send>(
+        &self,
+        path: &str,
+        payload: &P,
+    ) -> Result<T> {
+        let path = self.make_path(path);
+        if let Some(response) = self.dry_run.intercept(Method::PUT, &path, Some(payload)) {
+            return response;
+        }
+
+        self.transport.put(&path, payload).await
+    }
+
+    /// `DELETE` the endpoint at `path`, relative to the license service base path.
+    ///
+    /// Blocked in production, unless allowed with [`Self::allow_destructive`].
+    #[cfg_attr(not(coverage), instrument(name = "license_gateway.delete", skip(self)))]
+    pub async fn delete<T: DeserializeOwned + Debug + Send>(&self, path: &str) -> Result<T> {
+        if !self.allow_destructive {
+            return Err(ErrorKind::DestructiveOperationBlocked {
+                operation: "license_gateway.delete",
+            }
+            .into());
+        }
+
+        let path = self.make_path(path);
+        if let Some(response) = self.dry_run.intercept::<(), _>(Method::DELETE, &path, None) {
+            return response;
+        }
+
+        self.transport.delete(&path).await
+    }
+}
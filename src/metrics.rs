@@ -0,0 +1,221 @@
+//! Request metrics, emitted through the [`metrics`](https://docs.rs/metrics) facade.
+//!
+//! Install any `metrics` recorder (e.g. a Prometheus or StatsD exporter) to collect them.
+//! Call [`describe`] once after installing the recorder to register units and descriptions.
+//!
+//! All metrics are labeled by `method`, `service` and `endpoint`. The `service` is `hosted_license_provider`,
+//! `institutions`, `license_gateway` or `other`, as configured by [`ServicePaths`]. The `endpoint` is the request path
+//! following the service's base path (and identity code), with IDs replaced by `{id}`, e.g. `v2/instellingen/{id}`,
+//! keeping the number of label values small.
+
+use std::time::Duration;
+
+use ::metrics::{counter, describe_counter, describe_histogram, histogram, Unit};
+use reqwest::{Method, StatusCode, Url};
+
+use crate::rest::ServicePaths;
+
+/// Counter of requests sent, also labeled by `status`.
+///
+/// `status` is the HTTP status code, or `error` if no response was received.
+pub const REQUESTS_TOTAL: &str = "basispoort_requests_total";
+
+/// Histogram of request durations in seconds, until response headers were received.
+pub const REQUEST_DURATION_SECONDS: &str = "basispoort_request_duration_seconds";
+
+/// Histogram of request durations in seconds, until the response body was received.
+pub const RESPONSE_DURATION_SECONDS: &str = "basispoort_response_duration_seconds";
+
+/// Path segments followed by a publisher-chosen ID in the hosted license provider and license gateway services.
+const ID_COLLECTIONS: [&str; 2] = ["methode", "product"];
+
+/// The labels of a request's metrics.
+#[derive(Debug, Clone)]
+pub(crate) struct RequestLabels {
+    method: Method,
+    service: &'static str,
+    endpoint: String,
+}
+
+impl RequestLabels {
+    /// Label a request to `url`, sent by a client with `base_url` and `service_paths`.
+    pub(crate) fn new(
+        method: &Method,
+        base_url: &Url,
+        service_paths: &ServicePaths,
+        url: &Url,
+    ) -> Self {
+        let relative = |path: &str| -> String {
+            let path = path.trim_start_matches('/');
+            let base_path = base_url.path().trim_start_matches('/');
+            path.strip_prefix(base_path).unwrap_or(path).to_owned()
+        };
+        let path = relative(url.path());
+
+        let (service, rest) = [
+            (
+                "hosted_license_provider",
+                &service_paths.hosted_license_provider,
+            ),
+            ("institutions", &service_paths.institutions),
+            ("license_gateway", &service_paths.license_gateway),
+        ]
+        .into_iter()
+        .filter_map(|(service, service_path)| {
+            let service_path = relative(service_path);
+            path.strip_prefix(&service_path)
+                .map(|rest| (service, service_path.len(), rest))
+        })
+        // The most specific base path wins, as the license gateway's is nested in the institutions'.
+        .max_by_key(|(_, service_path_len, _)| *service_path_len)
+        .map_or(("other", path.as_str()), |(service, _, rest)| {
+            (service, rest)
+        });
+
+        let mut segments: Vec<&str> = rest
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        if service == "hosted_license_provider" && !segments.is_empty() {
+            // The identity code.
+            segments.remove(0);
+        }
+        let endpoint = segments
+            .iter()
+            .enumerate()
+            .map(|(index, segment)| {
+                let follows_collection = index > 0 && ID_COLLECTIONS.contains(&segments[index - 1]);
+                if follows_collection || segment.bytes().all(|byte| byte.is_ascii_digit()) {
+                    "{id}"
+                } else {
+                    segment
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+
+        Self {
+            method: method.clone(),
+            service,
+            endpoint,
+        }
+    }
+
+    fn labels(&self) -> [(&'static str, String); 3] {
+        [
+            ("method", self.method.to_string()),
+            ("service", self.service.to_owned()),
+            ("endpoint", self.endpoint.clone()),
+        ]
+    }
+}
+
+/// Register units and descriptions of all metrics with the installed recorder.
+pub fn describe() {
+    describe_counter!(
+        REQUESTS_TOTAL,
+        Unit::Count,
+        "Requests sent to the Basispoort REST API."
+    );
+    describe_histogram!(
+        REQUEST_DURATION_SECONDS,
+        Unit::Seconds,
+        "Duration of requests to the Basispoort REST API."
+    );
+    describe_histogram!(
+        RESPONSE_DURATION_SECONDS,
+        Unit::Seconds,
+        "Duration of requests to the Basispoort REST API, including receiving the response body."
+    );
+}
+
+/// Record a request, and its response status if a response was received.
+pub(crate) fn record_request(
+    labels: &RequestLabels,
+    status: Option<StatusCode>,
+    duration: Duration,
+) {
+    let status = status.map_or_else(|| "error".to_owned(), |status| status.as_str().to_owned());
+
+    let mut counter_labels = labels.labels().to_vec();
+    counter_labels.push(("status", status));
+    counter!(REQUESTS_TOTAL, &counter_labels).increment(1);
+    histogram!(REQUEST_DURATION_SECONDS, &labels.labels()).record(duration.as_secs_f64());
+}
+
+/// Record the total duration of a request, including receiving the response body.
+pub(crate) fn record_response(labels: &RequestLabels, duration: Duration) {
+    histogram!(RESPONSE_DURATION_SECONDS, &labels.labels()).record(duration.as_secs_f64());
+}
+
+#[cfg(test)]
+mod tests {
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    use super::*;
+
+    #[test]
+    fn records_request() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        let base_url: Url = "https://test-rest.basispoort.nl/".parse().unwrap();
+        let labels = RequestLabels::new(
+            &Method::GET,
+            &base_url,
+            &ServicePaths::default(),
+            &base_url.join("rest/v2/instellingen/12").unwrap(),
+        );
+        ::metrics::with_local_recorder(&recorder, || {
+            record_request(&labels, Some(StatusCode::OK), Duration::from_millis(250));
+            record_request(&labels, None, Duration::from_secs(1));
+        });
+
+        let snapshot = snapshotter.snapshot().into_vec();
+        let counter = snapshot
+            .iter()
+            .find(|(key, ..)| {
+                key.key().name() == REQUESTS_TOTAL
+                    && key.key().labels().any(|label| label.value() == "200")
+            })
+            .map(|(.., value)| value);
+        assert_eq!(counter, Some(&DebugValue::Counter(1)));
+
+        let histogram = snapshot
+            .iter()
+            .find(|(key, ..)| key.key().name() == REQUEST_DURATION_SECONDS)
+            .map(|(.., value)| value);
+        assert!(matches!(histogram, Some(DebugValue::Histogram(values)) if values.len() == 2));
+    }
+
+    #[test]
+    fn labels_endpoints_without_ids() {
+        let base_url: Url = "https://test-rest.basispoort.nl/".parse().unwrap();
+        let labels = |path: &str| {
+            let labels = RequestLabels::new(
+                &Method::GET,
+                &base_url,
+                &ServicePaths::default(),
+                &base_url.join(path).unwrap(),
+            );
+            (labels.service, labels.endpoint)
+        };
+
+        assert_eq!(
+            labels("/hosted-lika/management/lika/publisher/methode/my-method/product/p1/gebruiker"),
+            (
+                "hosted_license_provider",
+                "methode/{id}/product/{id}/gebruiker".into()
+            )
+        );
+        assert_eq!(
+            labels("rest/v2/instellingen/12/leerlingen"),
+            ("institutions", "v2/instellingen/{id}/leerlingen".into())
+        );
+        assert_eq!(
+            labels("rest/v2/licenties/methode/my-method"),
+            ("license_gateway", "methode/{id}".into())
+        );
+        assert_eq!(labels("status"), ("other", "status".into()));
+    }
+}
@@ -0,0 +1,153 @@
+//! Write-ahead queue of mutating requests, replayed after an outage.
+//!
+//! With a [`MutationQueue`] passed to [`RestClientBuilder::mutation_queue`][crate::rest::RestClientBuilder::mutation_queue],
+//! every `POST`, `PUT` and `DELETE` request is recorded in a [`StateStore`] before it is sent.
+//! The record is removed once the server has responded, unless the request failed due to an outage:
+//! a transport error, `429 Too Many Requests` or a `5xx` response.
+//! [`RestClient::replay_pending`][crate::rest::RestClient::replay_pending] re-sends the remaining requests
+//! in their original order, e.g. after a Basispoort maintenance window.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{error::ErrorKind, state::StateStore, Result};
+
+const KEY_PREFIX: &str = "mutation/";
+
+/// The HTTP method of a [`PendingMutation`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum MutationMethod {
+    Post,
+    Put,
+    Delete,
+}
+
+impl From<MutationMethod> for reqwest::Method {
+    fn from(method: MutationMethod) -> Self {
+        match method {
+            MutationMethod::Post => reqwest::Method::POST,
+            MutationMethod::Put => reqwest::Method::PUT,
+            MutationMethod::Delete => reqwest::Method::DELETE,
+        }
+    }
+}
+
+/// A recorded mutating request.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PendingMutation {
+    pub method: MutationMethod,
+    /// The request path, relative to the client's base URL.
+    pub path: String,
+    pub payload: Option<Value>,
+    pub recorded_at: SystemTime,
+}
+
+/// A write-ahead queue of mutating requests, recorded in a [`StateStore`].
+#[derive(Debug, Clone)]
+pub struct MutationQueue {
+    store: Arc<dyn StateStore>,
+    sequence: Arc<AtomicU64>,
+}
+
+impl MutationQueue {
+    pub fn new(store: Arc<dyn StateStore>) -> Self {
+        Self {
+            store,
+            sequence: Arc::default(),
+        }
+    }
+
+    /// All pending mutations, in the order they were recorded.
+    pub async fn pending(&self) -> Result<Vec<PendingMutation>> {
+        Ok(self
+            .entries()
+            .await?
+            .into_iter()
+            .map(|(_, mutation)| mutation)
+            .collect())
+    }
+
+    /// Record `mutation`, returning its key.
+    pub(crate) async fn push(&self, mutation: &PendingMutation) -> Result<String> {
+        let value = serde_json::to_value(mutation).map_err(ErrorKind::EncodePayload)?;
+        let micros = mutation
+            .recorded_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros();
+
+        // Keys sort by recording time. The sequence number keeps keys recorded in the same microsecond apart;
+        // on collision with another process sharing the store, take the next one.
+        loop {
+            let sequence = self.sequence.fetch_add(1, Ordering::Relaxed);
+            let key = format!("{KEY_PREFIX}{micros:020}-{sequence:010}");
+            if self
+                .store
+                .compare_and_swap(&key, None, Some(value.clone()))
+                .await?
+            {
+                return Ok(key);
+            }
+        }
+    }
+
+    pub(crate) async fn remove(&self, key: &str) -> Result<()> {
+        self.store.delete(key).await
+    }
+
+    /// All pending mutations with their keys, in the order they were recorded.
+    pub(crate) async fn entries(&self) -> Result<Vec<(String, PendingMutation)>> {
+        let mut entries = Vec::new();
+        for key in self.store.keys(KEY_PREFIX).await? {
+            // Records may have been removed by a concurrent replay since listing the keys.
+            let Some(value) = self.store.get(&key).await? else {
+                continue;
+            };
+            let mutation =
+                PendingMutation::deserialize(value).map_err(|source| ErrorKind::StateRecord {
+                    key: key.clone(),
+                    source,
+                })?;
+            entries.push((key, mutation));
+        }
+
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::MemoryStateStore;
+
+    #[tokio::test]
+    async fn keeps_mutations_in_order() -> Result<()> {
+        let queue = MutationQueue::new(Arc::new(MemoryStateStore::new()));
+        let mutation = |path: &str| PendingMutation {
+            method: MutationMethod::Put,
+            path: path.into(),
+            payload: Some(serde_json::json!({ "id": 1 })),
+            recorded_at: SystemTime::now(),
+        };
+
+        let first = queue.push(&mutation("first")).await?;
+        queue.push(&mutation("second")).await?;
+        queue.push(&mutation("third")).await?;
+        queue.remove(&first).await?;
+
+        let paths: Vec<_> = queue
+            .pending()
+            .await?
+            .into_iter()
+            .map(|mutation| mutation.path)
+            .collect();
+        assert_eq!(paths, ["second", "third"]);
+
+        Ok(())
+    }
+}
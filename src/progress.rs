@@ -0,0 +1,199 @@
+//! Progress reporting for long-running operations.
+//!
+//! Helpers which process many items, such as the scan, sync and bulk helpers of the service clients,
+//! accept an optional [`ProgressSender`], to which they report [`ProgressEvent`]s as they go.
+//!
+//! Reporting is best-effort and never slows an operation down: if the receiver has been dropped, or its channel
+//! is full, events are discarded. Events carry cumulative counts, so a later event supersedes the dropped ones.
+
+use futures_channel::mpsc::Sender;
+
+use crate::Result;
+
+/// The sending half of a progress channel, created with [`futures_channel::mpsc::channel`].
+///
+/// The channel is bounded, so events cannot pile up in memory if the receiver falls behind.
+pub type ProgressSender = Sender<ProgressEvent>;
+
+/// A progress update of a long-running operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ProgressEvent {
+    /// The operation started. `total` is the number of items to process, if known.
+    Started {
+        operation: &'static str,
+        total: Option<usize>,
+    },
+
+    /// An item was processed, successfully or not.
+    Progressed {
+        operation: &'static str,
+        completed: usize,
+        failed: usize,
+        total: Option<usize>,
+    },
+
+    /// The operation finished.
+    Finished {
+        operation: &'static str,
+        completed: usize,
+        failed: usize,
+    },
+}
+
+/// Tracks the progress of a single operation and reports it to an optional [`ProgressSender`].
+#[derive(Debug)]
+pub struct ProgressReporter {
+    sender: Option<ProgressSender>,
+    operation: &'static str,
+    total: Option<usize>,
+    completed: usize,
+    failed: usize,
+}
+
+impl ProgressReporter {
+    /// Start reporting progress of `operation`, sending [`ProgressEvent::Started`].
+    pub fn start(
+        sender: Option<&ProgressSender>,
+        operation: &'static str,
+        total: Option<usize>,
+    ) -> Self {
+        let mut reporter = Self {
+            sender: sender.cloned(),
+            operation,
+            total,
+            completed: 0,
+            failed: 0,
+        };
+        reporter.send(ProgressEvent::Started { operation, total });
+
+        reporter
+    }
+
+    /// Record a successfully processed item.
+    pub fn item_completed(&mut self) {
+        self.completed += 1;
+        self.send_progressed();
+    }
+
+    /// Record `count` items processed successfully at once, e.g. those confirmed by an earlier, interrupted run.
+    pub fn items_completed(&mut self, count: usize) {
+        if count > 0 {
+            self.completed += count;
+            self.send_progressed();
+        }
+    }
+
+    /// Record an item which failed to process.
+    pub fn item_failed(&mut self) {
+        self.failed += 1;
+        self.send_progressed();
+    }
+
+    /// Record the item processed with `result`, as completed or failed, passing `result` through.
+    pub fn record<T>(&mut self, result: Result<T>) -> Result<T> {
+        match result {
+            Ok(_) => self.item_completed(),
+            Err(_) => self.item_failed(),
+        }
+
+        result
+    }
+
+    /// Finish the operation, sending [`ProgressEvent::Finished`].
+    pub fn finish(mut self) {
+        self.send(ProgressEvent::Finished {
+            operation: self.operation,
+            completed: self.completed,
+            failed: self.failed,
+        });
+    }
+
+    fn send_progressed(&mut self) {
+        self.send(ProgressEvent::Progressed {
+            operation: self.operation,
+            completed: self.completed,
+            failed: self.failed,
+            total: self.total,
+        });
+    }
+
+    fn send(&mut self, event: ProgressEvent) {
+        if let Some(sender) = &mut self.sender {
+            // The receiver may have lost interest or fallen behind; progress is best-effort.
+            let _ = sender.try_send(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_channel::mpsc;
+
+    use super::*;
+
+    #[test]
+    fn reports_progress() {
+        let (sender, mut receiver) = mpsc::channel(8);
+
+        let mut reporter = ProgressReporter::start(Some(&sender), "test", Some(2));
+        reporter.item_completed();
+        reporter.item_failed();
+        reporter.finish();
+
+        let events = std::iter::from_fn(|| receiver.try_recv().ok()).collect::<Vec<_>>();
+
+        assert_eq!(
+            events,
+            [
+                ProgressEvent::Started {
+                    operation: "test",
+                    total: Some(2)
+                },
+                ProgressEvent::Progressed {
+                    operation: "test",
+                    completed: 1,
+                    failed: 0,
+                    total: Some(2)
+                },
+                ProgressEvent::Progressed {
+                    operation: "test",
+                    completed: 1,
+                    failed: 1,
+                    total: Some(2)
+                },
+                ProgressEvent::Finished {
+                    operation: "test",
+                    completed: 1,
+                    failed: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn discards_events_while_the_channel_is_full() {
+        let (sender, mut receiver) = mpsc::channel(0);
+
+        // Each sender is guaranteed a single slot, which `Started` takes.
+        let mut reporter = ProgressReporter::start(Some(&sender), "test", None);
+        reporter.item_completed();
+        assert_eq!(
+            receiver.try_recv().ok(),
+            Some(ProgressEvent::Started {
+                operation: "test",
+                total: None
+            })
+        );
+
+        reporter.finish();
+        assert_eq!(
+            receiver.try_recv().ok(),
+            Some(ProgressEvent::Finished {
+                operation: "test",
+                completed: 1,
+                failed: 0
+            })
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! A single entry point for publisher applications, owning the [`RestClient`] and creating the service clients.
+//!
+//! ```no_run
+//! # async fn example(
+//! #     rest_client: basispoort_sync_client::rest::RestClient,
+//! # ) -> basispoort_sync_client::Result<()> {
+//! use basispoort_sync_client::{publisher::Publisher, BasispoortId};
+//!
+//! let publisher = Publisher::new(rest_client).with_identity_code("my-publisher");
+//!
+//! let institution_id = BasispoortId::from_u32(1234);
+//! let coverage = publisher.license_coverage(institution_id, "my-method").await?;
+//! println!("{} students without a license", coverage.unlicensed.len());
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "institutions")]
+use std::sync::OnceLock;
+
+#[cfg(all(feature = "institutions", feature = "hosted-license-provider"))]
+use serde::Serialize;
+#[cfg(all(feature = "institutions", not(coverage)))]
+use tracing::instrument;
+
+#[cfg(feature = "hosted-license-provider")]
+use crate::error::ErrorKind;
+#[cfg(feature = "hosted-license-provider")]
+use crate::hosted_license_provider::HostedLicenseProviderClient;
+#[cfg(feature = "institutions")]
+use crate::institutions::{InstitutionsApiVersion, InstitutionsServiceClient};
+#[cfg(feature = "license-gateway")]
+use crate::license_gateway::LicenseGatewayClient;
+use crate::rest::RestClient;
+#[cfg(any(feature = "institutions", feature = "hosted-license-provider"))]
+use crate::Result;
+#[cfg(all(feature = "institutions", feature = "hosted-license-provider"))]
+use crate::{hosted_license_provider::UserIdList, BasispoortId};
+
+/// Owns a [`RestClient`], and creates the service clients on demand.
+#[derive(Debug)]
+pub struct Publisher {
+    rest_client: RestClient,
+    #[cfg(feature = "hosted-license-provider")]
+    identity_code: Option<String>,
+    /// The configured or negotiated institutions API version, once known.
+    #[cfg(feature = "institutions")]
+    institutions_version: OnceLock<InstitutionsApiVersion>,
+}
+
+impl Publisher {
+    pub fn new(rest_client: RestClient) -> Self {
+        Self {
+            rest_client,
+            #[cfg(feature = "hosted-license-provider")]
+            identity_code: None,
+            #[cfg(feature = "institutions")]
+            institutions_version: OnceLock::new(),
+        }
+    }
+
+    /// Use `identity_code` for the hosted license provider service.
+    #[cfg(feature = "hosted-license-provider")]
+    pub fn with_identity_code(self, identity_code: impl Into<String>) -> Self {
+        Self {
+            identity_code: Some(identity_code.into()),
+            ..self
+        }
+    }
+
+    /// Use the institutions API `version`, instead of negotiating it with the environment.
+    #[cfg(feature = "institutions")]
+    pub fn with_institutions_version(self, version: InstitutionsApiVersion) -> Self {
+        Self {
+            institutions_version: OnceLock::from(version),
+            ..self
+        }
+    }
+
+    pub fn rest_client(&self) -> &RestClient {
+        &self.rest_client
+    }
+
+    /// A client for the institutions service.
+    ///
+    /// Unless configured with [`Self::with_institutions_version`], the API version is negotiated on first use.
+    #[cfg(feature = "institutions")]
+    #[cfg_attr(not(coverage), instrument(skip(self)))]
+    pub async fn institutions(&self) -> Result<InstitutionsServiceClient> {
+        if let Some(&version) = self.institutions_version.get() {
+            return Ok(InstitutionsServiceClient::with_version(
+                &self.rest_client,
+                version,
+            ));
+        }
+
+        let client = InstitutionsServiceClient::negotiate(&self.rest_client).await?;
+        // A concurrent negotiation may have finished first, with the same outcome.
+        let _ = self.institutions_version.set(client.version());
+        Ok(client)
+    }
+
+    /// A client for the hosted license provider service, for the configured identity code.
+    ///
+    /// Fails with [`ErrorKind::MissingConfigValue`] if no identity code is configured.
+    #[cfg(feature = "hosted-license-provider")]
+    pub fn hosted_license_provider(&self) -> Result<HostedLicenseProviderClient> {
+        let identity_code =
+            self.identity_code
+                .as_deref()
+                .ok_or_else(|| ErrorKind::MissingConfigValue {
+                    key: "hosted_license_provider_identity_code".into(),
+                })?;
+
+        Ok(HostedLicenseProviderClient::new(
+            &self.rest_client,
+            identity_code,
+        ))
+    }
+
+    /// A client for the license gateway service.
+    #[cfg(feature = "license-gateway")]
+    pub fn license_gateway(&self) -> LicenseGatewayClient {
+        LicenseGatewayClient::new(&self.rest_client)
+    }
+}
+
+/// The students of an institution with and without access to a method, as reported by
+/// [`Publisher::license_coverage`].
+#[cfg(all(feature = "institutions", feature = "hosted-license-provider"))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LicenseCoverage {
+    pub institution_id: BasispoortId,
+    pub method_id: String,
+    /// Students with access to the method, in ascending order.
+    pub licensed: Vec<BasispoortId>,
+    /// Students without access to the method, in ascending order.
+    pub unlicensed: Vec<BasispoortId>,
+}
+
+#[cfg(all(feature = "institutions", feature = "hosted-license-provider"))]
+impl LicenseCoverage {
+    fn new(
+        institution_id: BasispoortId,
+        method_id: impl Into<String>,
+        students: impl IntoIterator<Item = BasispoortId>,
+        method_users: &[BasispoortId],
+    ) -> Self {
+        let method_users: std::collections::HashSet<_> = method_users.iter().collect();
+        let (mut licensed, mut unlicensed): (Vec<_>, Vec<_>) = students
+            .into_iter()
+            .partition(|student| method_users.contains(student));
+        licensed.sort_unstable();
+        unlicensed.sort_unstable();
+
+        Self {
+            institution_id,
+            method_id: method_id.into(),
+            licensed,
+            unlicensed,
+        }
+    }
+
+    /// The share of students with access to the method, from `0.0` to `1.0`. `1.0` if there are no students.
+    pub fn ratio(&self) -> f64 {
+        let total = self.licensed.len() + self.unlicensed.len();
+        if total == 0 {
+            return 1.0;
+        }
+
+        self.licensed.len() as f64 / total as f64
+    }
+}
+
+#[cfg(all(feature = "institutions", feature = "hosted-license-provider"))]
+impl Publisher {
+    /// Compare the students on the institution's roster with the users with access to the method.
+    #[cfg_attr(not(coverage), instrument(skip(self)))]
+    pub async fn license_coverage(
+        &self,
+        institution_id: BasispoortId,
+        method_id: &str,
+    ) -> Result<LicenseCoverage> {
+        let students = self
+            .institutions()
+            .await?
+            .get_institution_students(institution_id)
+            .await?
+            .students;
+        let method_users = self
+            .hosted_license_provider()?
+            .get_method_user_ids(method_id)
+            .await?;
+
+        Ok(LicenseCoverage::new(
+            institution_id,
+            method_id,
+            students.iter().map(|student| student.id),
+            &method_users.users,
+        ))
+    }
+
+    /// Grant all students on the institution's roster access to the method, if they do not have it yet.
+    ///
+    /// Returns the coverage before granting access; its `unlicensed` students were granted access.
+    #[cfg_attr(not(coverage), instrument(skip(self)))]
+    pub async fn sync_roster_licenses(
+        &self,
+        institution_id: BasispoortId,
+        method_id: &str,
+    ) -> Result<LicenseCoverage> {
+        let coverage = self.license_coverage(institution_id, method_id).await?;
+        if !coverage.unlicensed.is_empty() {
+            self.hosted_license_provider()?
+                .add_method_user_ids(method_id, &UserIdList::from(coverage.unlicensed.clone()))
+                .await?;
+        }
+
+        Ok(coverage)
+    }
+}
+
+#[cfg(all(test, feature = "institutions", feature = "hosted-license-provider"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partitions_students_by_license() {
+        let ids =
+            |ids: &[u32]| -> Vec<_> { ids.iter().copied().map(BasispoortId::from_u32).collect() };
+
+        let coverage = LicenseCoverage::new(
+            BasispoortId::from_u32(1),
+            "method",
+            ids(&[30, 10, 20, 40]),
+            &ids(&[20, 10, 99]),
+        );
+        assert_eq!(coverage.licensed, ids(&[10, 20]));
+        assert_eq!(coverage.unlicensed, ids(&[30, 40]));
+        assert_eq!(coverage.ratio(), 0.5);
+    }
+}
@@ -0,0 +1,153 @@
+//! Client-side rate limiting.
+//!
+//! A [`RateLimiter`] is a token bucket: it allows bursts of up to `burst` requests,
+//! refilled at `requests_per_second`. Clones share the same budget, so a single limiter may be passed to
+//! several [`RestClientBuilder`][crate::rest::RestClientBuilder]s, e.g. one per environment or worker,
+//! to make them collectively respect Basispoort's fair-use limits.
+
+use std::num::NonZeroU32;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use tracing::trace;
+
+use crate::clock::{Clock, RuntimeSleeper, Sleeper, SystemClock};
+use crate::Result;
+
+/// A token bucket rate limiter, shared by all its clones.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    bucket: Arc<Mutex<Bucket>>,
+    clock: Arc<dyn Clock>,
+    sleeper: Arc<dyn Sleeper>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    refill_per_second: f64,
+    /// Available tokens. Negative when tokens have been reserved by waiting requests.
+    tokens: f64,
+    refilled_at: SystemTime,
+}
+
+impl RateLimiter {
+    /// Allow `requests_per_second` on average, with bursts of up to `burst` requests.
+    pub fn new(requests_per_second: NonZeroU32, burst: NonZeroU32) -> Self {
+        Self {
+            bucket: Arc::new(Mutex::new(Bucket {
+                capacity: burst.get().into(),
+                refill_per_second: requests_per_second.get().into(),
+                tokens: burst.get().into(),
+                refilled_at: SystemClock.now(),
+            })),
+            clock: Arc::new(SystemClock),
+            sleeper: Arc::new(RuntimeSleeper),
+        }
+    }
+
+    /// Refill the bucket by the time read from `clock`, e.g. a [`ManualClock`][crate::clock::ManualClock] in tests.
+    /// The bucket is full at the clock's current time.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.bucket().refilled_at = clock.now();
+        self.clock = clock;
+        self
+    }
+
+    /// Wait using `sleeper`, e.g. a [`RecordingSleeper`][crate::clock::RecordingSleeper] in tests.
+    pub fn with_sleeper(mut self, sleeper: Arc<dyn Sleeper>) -> Self {
+        self.sleeper = sleeper;
+        self
+    }
+
+    /// Wait until the budget allows another request. Fails only if the sleeper fails.
+    pub async fn acquire(&self) -> Result<()> {
+        let wait = self.bucket().reserve(self.clock.now());
+
+        if !wait.is_zero() {
+            trace!("Rate limited, waiting {wait:?}.");
+            self.sleeper.sleep(wait).await?;
+        }
+
+        Ok(())
+    }
+
+    fn bucket(&self) -> std::sync::MutexGuard<'_, Bucket> {
+        self.bucket
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Bucket {
+    /// Take a token, returning how long to wait until it becomes available.
+    fn reserve(&mut self, now: SystemTime) -> Duration {
+        // A clock set back refills nothing.
+        let elapsed = now.duration_since(self.refilled_at).unwrap_or_default();
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * self.refill_per_second).min(self.capacity);
+        self.refilled_at = now;
+
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.refill_per_second)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{ManualClock, RecordingSleeper};
+
+    #[test]
+    fn allows_burst_then_spaces_requests() {
+        let start = SystemTime::UNIX_EPOCH;
+        let mut bucket = Bucket {
+            capacity: 2.0,
+            refill_per_second: 4.0,
+            tokens: 2.0,
+            refilled_at: start,
+        };
+
+        assert_eq!(bucket.reserve(start), Duration::ZERO);
+        assert_eq!(bucket.reserve(start), Duration::ZERO);
+        assert_eq!(bucket.reserve(start), Duration::from_millis(250));
+        assert_eq!(bucket.reserve(start), Duration::from_millis(500));
+
+        // After a second, the two reserved tokens are paid off and two are available again.
+        let later = start + Duration::from_secs(1);
+        assert_eq!(bucket.reserve(later), Duration::ZERO);
+        assert_eq!(bucket.reserve(later), Duration::ZERO);
+        assert_eq!(bucket.reserve(later), Duration::from_millis(250));
+    }
+
+    #[tokio::test]
+    async fn waits_using_the_sleeper() {
+        let clock = ManualClock::new(SystemTime::UNIX_EPOCH);
+        let sleeper = RecordingSleeper::new();
+        let rate_limiter = RateLimiter::new(NonZeroU32::MIN, NonZeroU32::MIN)
+            .with_clock(Arc::new(clock.clone()))
+            .with_sleeper(Arc::new(sleeper.clone()));
+
+        // The recorded delays are not actually waited, so each request reserves another second.
+        for _ in 0..3 {
+            rate_limiter.acquire().await.unwrap();
+        }
+        // Three seconds later, the reserved tokens are paid off and one is available again.
+        clock.advance(Duration::from_secs(3));
+        rate_limiter.acquire().await.unwrap();
+        rate_limiter.acquire().await.unwrap();
+
+        assert_eq!(
+            sleeper.delays(),
+            [
+                Duration::from_secs(1),
+                Duration::from_secs(2),
+                Duration::from_secs(1)
+            ]
+        );
+    }
+}
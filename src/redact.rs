@@ -0,0 +1,203 @@
+//! Redacted summaries of request payloads, for attaching to error responses,
+//! and `Debug` helpers keeping spans, logs and error chains readable and free of personal data.
+
+use std::fmt::{self, Debug};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+#[cfg(feature = "hosted-license-provider")]
+/// Number of items shown by [`ListSummary`].
+const LIST_SUMMARY_ITEMS: usize = 5;
+
+#[cfg(feature = "hosted-license-provider")]
+/// Number of characters shown by [`Truncated`].
+const TRUNCATED_CHARS: usize = 32;
+
+#[cfg(feature = "hosted-license-provider")]
+/// Debug-formats a list by its first few items and the number of remaining items.
+pub(crate) struct ListSummary<'a, T>(pub(crate) &'a [T]);
+
+#[cfg(feature = "hosted-license-provider")]
+impl<T: Debug> Debug for ListSummary<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        list.entries(self.0.iter().take(LIST_SUMMARY_ITEMS));
+        if self.0.len() > LIST_SUMMARY_ITEMS {
+            list.entry(&format_args!(
+                "... {} more",
+                self.0.len() - LIST_SUMMARY_ITEMS
+            ));
+        }
+        list.finish()
+    }
+}
+
+#[cfg(feature = "hosted-license-provider")]
+/// Debug-formats a long string, such as a base64-encoded icon, by its beginning and length.
+pub(crate) struct Truncated<'a>(pub(crate) &'a str);
+
+#[cfg(feature = "hosted-license-provider")]
+impl Debug for Truncated<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.char_indices().nth(TRUNCATED_CHARS) {
+            Some((end, _)) => write!(f, "{:?}... ({} bytes)", &self.0[..end], self.0.len()),
+            None => Debug::fmt(self.0, f),
+        }
+    }
+}
+
+#[cfg(any(feature = "institutions", feature = "hosted-license-provider"))]
+/// Debug-formats personal data as redacted.
+pub(crate) struct Redacted;
+
+#[cfg(any(feature = "institutions", feature = "hosted-license-provider"))]
+impl Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+#[cfg(feature = "institutions")]
+/// Debug-formats an optional value as redacted, only showing whether it is present.
+pub(crate) fn redacted<T>(value: &Option<T>) -> Option<Redacted> {
+    value.as_ref().map(|_| Redacted)
+}
+
+/// Replaces personal data in raw JSON bodies.
+pub(crate) const REDACTED: &str = "[redacted]";
+
+/// JSON fields holding personal data, as used by the Basispoort API.
+const PERSONAL_DATA_FIELDS: [&str; 8] = [
+    "eckid",
+    "eckId",
+    "achternaam",
+    "voornaam",
+    "voorvoegsel",
+    "voorletters",
+    "emailadres",
+    "emailAdres",
+];
+
+/// Replace the values of personal data fields in `value`, at any depth, unless they are `null`.
+pub(crate) fn redact_personal_data(value: Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| {
+                    if PERSONAL_DATA_FIELDS.contains(&key.as_str()) && !value.is_null() {
+                        (key, Value::String(REDACTED.into()))
+                    } else {
+                        (key, redact_personal_data(value))
+                    }
+                })
+                .collect(),
+        ),
+        Value::Array(values) => {
+            Value::Array(values.into_iter().map(redact_personal_data).collect())
+        }
+        value => value,
+    }
+}
+
+/// Formats a raw response body with its personal data redacted, if it is JSON, or else as text.
+///
+/// The body is only parsed when formatted, so disabled log events cost nothing.
+pub(crate) struct RedactedBody<'a>(pub(crate) &'a [u8]);
+
+impl fmt::Display for RedactedBody<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match serde_json::from_slice(self.0) {
+            Ok(value) => fmt::Display::fmt(&redact_personal_data(value), f),
+            Err(_) => f.write_str(&String::from_utf8_lossy(self.0)),
+        }
+    }
+}
+
+impl Debug for RedactedBody<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Summarize `payload`, keeping only its structure, entity IDs and list lengths.
+///
+/// Strings and numbers are only kept for ID fields (`id`, `…Id`, `…_id`). Chain IDs (ECK iD) are personal data
+/// and always redacted, as are all other values except booleans and `null`. Lists are replaced by their length.
+pub(crate) fn summarize<P: Serialize + ?Sized>(payload: &P) -> String {
+    match serde_json::to_value(payload) {
+        Ok(value) => redact(value, false).to_string(),
+        Err(_) => "<unserializable payload>".into(),
+    }
+}
+
+fn redact(value: Value, is_id: bool) -> Value {
+    match value {
+        Value::Array(items) => Value::String(format!("<{} items>", items.len())),
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| {
+                    let is_id = is_id_key(&key);
+                    (key, redact(value, is_id))
+                })
+                .collect::<Map<_, _>>(),
+        ),
+        Value::String(_) | Value::Number(_) if !is_id => Value::String("<redacted>".into()),
+        value => value,
+    }
+}
+
+fn is_id_key(key: &str) -> bool {
+    !key.to_lowercase().contains("eck")
+        && (key == "id" || key.ends_with("Id") || key.ends_with("_id"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn keeps_ids_and_list_lengths_only() {
+        let payload = json!({
+            "id": "method-1",
+            "naam": "Jane Doe's method",
+            "icon": null,
+            "instellingId": 42,
+            "eckId": "https://ketenid.nl/123",
+            "gebruikers": [1, 2, 3],
+            "actief": true,
+        });
+
+        assert_eq!(
+            summarize(&payload),
+            r#"{"actief":true,"eckId":"<redacted>","gebruikers":"<3 items>","icon":null,"id":"method-1","instellingId":42,"naam":"<redacted>"}"#
+        );
+        assert_eq!(summarize(&[1, 2]), r#""<2 items>""#);
+    }
+
+    #[test]
+    fn redacts_personal_data_in_bodies() {
+        let body = br#"{"leerlingen":[{"id":1,"voornaam":"Anna","emailadres":null}]}"#;
+        assert_eq!(
+            RedactedBody(body).to_string(),
+            r#"{"leerlingen":[{"emailadres":null,"id":1,"voornaam":"[redacted]"}]}"#
+        );
+    }
+
+    #[cfg(feature = "hosted-license-provider")]
+    #[test]
+    fn summarizes_debug_output() {
+        assert_eq!(
+            format!("{:?}", ListSummary(&[1, 2, 3, 4, 5, 6, 7])),
+            "[1, 2, 3, 4, 5, ... 2 more]"
+        );
+        assert_eq!(
+            format!("{:?}", Truncated(&"a".repeat(40))),
+            format!("{:?}... (40 bytes)", "a".repeat(32))
+        );
+    }
+}
@@ -0,0 +1,125 @@
+//! Conditional `GET` requests, revalidating cached responses.
+//!
+//! Institution data rarely changes between sync runs. A [`ResponseCache`] passed to
+//! [`RestClientBuilder::response_cache`][crate::rest::RestClientBuilder::response_cache] stores the body of each
+//! `GET` response carrying an `ETag` or `Last-Modified` header. Repeated requests for the same URL send these
+//! validators as `If-None-Match` and `If-Modified-Since`, and the cached body is used if the server responds with
+//! `304 Not Modified`, saving the transfer of unchanged data.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use bytes::Bytes;
+use reqwest::{
+    header::{self, HeaderMap, HeaderValue},
+    RequestBuilder, Url,
+};
+
+/// Cached `GET` response bodies with their validators, by URL. Clones share their entries.
+///
+/// Bodies are held in memory until [`ResponseCache::clear`] is called, or replaced by a newer response.
+#[derive(Debug, Clone, Default)]
+pub struct ResponseCache {
+    entries: Arc<Mutex<HashMap<Url, CachedResponse>>>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct CachedResponse {
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+    pub(crate) body: Bytes,
+}
+
+impl ResponseCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of cached responses.
+    pub fn len(&self) -> usize {
+        self.entries().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries().is_empty()
+    }
+
+    /// Remove all cached responses.
+    pub fn clear(&self) {
+        self.entries().clear();
+    }
+
+    pub(crate) fn get(&self, url: &Url) -> Option<CachedResponse> {
+        self.entries().get(url).cloned()
+    }
+
+    /// Cache `body` if the response `headers` carry a validator, or else remove a stale entry.
+    pub(crate) fn store(&self, url: &Url, headers: &HeaderMap, body: Bytes) {
+        let etag = headers.get(header::ETAG).cloned();
+        let last_modified = headers.get(header::LAST_MODIFIED).cloned();
+
+        let mut entries = self.entries();
+        if etag.is_none() && last_modified.is_none() {
+            entries.remove(url);
+        } else {
+            entries.insert(
+                url.clone(),
+                CachedResponse {
+                    etag,
+                    last_modified,
+                    body,
+                },
+            );
+        }
+    }
+
+    fn entries(&self) -> MutexGuard<'_, HashMap<Url, CachedResponse>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl CachedResponse {
+    /// Add the validators to `request`, making it conditional.
+    pub(crate) fn revalidate(&self, request: RequestBuilder) -> RequestBuilder {
+        let request = match &self.etag {
+            Some(etag) => request.header(header::IF_NONE_MATCH, etag),
+            None => request,
+        };
+        match &self.last_modified {
+            Some(last_modified) => request.header(header::IF_MODIFIED_SINCE, last_modified),
+            None => request,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn revalidates_with_stored_validators() {
+        let cache = ResponseCache::new();
+        let url: Url = "https://example.com/rest/v2/instellingen".parse().unwrap();
+
+        cache.store(&url, &HeaderMap::new(), Bytes::from_static(b"[]"));
+        assert!(cache.get(&url).is_none());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ETAG, HeaderValue::from_static("\"v1\""));
+        cache.store(&url, &headers, Bytes::from_static(b"[1]"));
+        let cached = cache.get(&url).unwrap();
+        assert_eq!(cached.body, Bytes::from_static(b"[1]"));
+
+        let request = cached
+            .revalidate(reqwest::Client::new().get(url.clone()))
+            .build()
+            .unwrap();
+        assert_eq!(request.headers()[header::IF_NONE_MATCH], "\"v1\"");
+        assert!(!request.headers().contains_key(header::IF_MODIFIED_SINCE));
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
@@ -1,46 +1,184 @@
-use std::fmt::Debug;
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::hash_map::RandomState;
+use std::env;
+use std::fmt::{self, Debug};
+#[cfg(not(target_arch = "wasm32"))]
+use std::future::Future;
+#[cfg(not(target_arch = "wasm32"))]
+use std::hash::{BuildHasher, Hasher};
+use std::path::Path;
 use std::str::FromStr;
-use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Mutex;
+use std::sync::{Arc, RwLock};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+use std::time::{Duration, SystemTime};
 
 use bytes::Bytes;
-use reqwest::{Identity, Response, Url};
-use serde::{de::DeserializeOwned, Serialize};
+use reqwest::{
+    header::{self, HeaderMap, HeaderValue},
+    Request, RequestBuilder, Response, StatusCode, Url,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use thiserror::Error;
-use tokio::{fs::File, io::AsyncReadExt};
 #[cfg(not(coverage))]
 use tracing::instrument;
 use tracing::{debug, info, trace, warn};
 
+#[cfg(feature = "chaos")]
+use crate::chaos::{Fault, FaultInjection};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::debug_capture::{CaptureIndex, DebugCapture, Recorder};
+use crate::deprecation::{Deprecation, DeprecationWarnings};
+use crate::error_translation::ErrorTranslations;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::events::{Event, EventSink};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::identity::{IdentityFile, IdentityProvider, StaticIdentity};
+use crate::interceptor::RequestInterceptor;
+use crate::mutation_queue::{MutationMethod, MutationQueue, PendingMutation};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::rate_limit::RateLimiter;
+use crate::response_cache::ResponseCache;
+#[cfg(feature = "schema-drift")]
+use crate::schema_drift::SchemaDriftDetector;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::types::TlsVersion;
 use crate::{
-    error::{Error, ErrorResponse},
-    Result,
+    error::{ErrorKind, ErrorResponse},
+    redact, Result,
 };
 
 /// Build [`RestClient`] ergonomically.
+///
+/// On `wasm32` targets, the browser selects the client certificate and manages timeouts and TLS.
+/// The identity certificate file, timeouts and minimum TLS version are then ignored or unavailable.
 #[derive(Debug)]
-pub struct RestClientBuilder<'i> {
-    identity_cert_file: &'i str,
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub struct RestClientBuilder {
+    #[cfg(not(target_arch = "wasm32"))]
+    identity_provider: Arc<dyn IdentityProvider>,
     environment: Environment,
     connect_timeout: Duration,
     timeout: Duration,
-    min_tls_version: reqwest::tls::Version,
+    #[cfg(not(target_arch = "wasm32"))]
+    min_tls_version: TlsVersion,
+    #[cfg(all(feature = "danger-accept-invalid-certs", not(target_arch = "wasm32")))]
+    accept_invalid_certs: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    proxy: Option<ProxyConfig>,
+    #[cfg(not(target_arch = "wasm32"))]
+    compression: Compression,
+    #[cfg(not(target_arch = "wasm32"))]
+    rate_limiter: Option<RateLimiter>,
+    #[cfg(not(target_arch = "wasm32"))]
+    correlation_id_header: header::HeaderName,
+    payload_logging: PayloadLogging,
+    #[cfg(feature = "chaos")]
+    fault_injection: Option<FaultInjection>,
+    #[cfg(feature = "schema-drift")]
+    schema_drift_detector: Option<SchemaDriftDetector>,
+    mutation_queue: Option<MutationQueue>,
+    response_cache: Option<ResponseCache>,
+    error_translations: Option<Arc<ErrorTranslations>>,
+    interceptors: Vec<Arc<dyn RequestInterceptor>>,
+    service_paths: ServicePaths,
+    integration_tag: Option<String>,
+    #[cfg(not(target_arch = "wasm32"))]
+    event_sink: Option<Arc<dyn EventSink>>,
 }
 
-impl<'i> RestClientBuilder<'i> {
+impl RestClientBuilder {
+    /// Create a builder for a client authenticating with the identity certificate (PEM) file at `identity_cert_file`.
+    ///
+    /// Fails early if the file does not exist or cannot be opened for reading.
+    /// It is only read and parsed in [`RestClientBuilder::build`].
+    #[cfg_attr(not(coverage), instrument(skip(identity_cert_file), fields(identity_cert_file = ?identity_cert_file.as_ref())))]
+    #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+    pub fn new(identity_cert_file: impl AsRef<Path>, environment: Environment) -> Result<Self> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let identity_file = IdentityFile::new(identity_cert_file.as_ref());
+            identity_file.check_readable()?;
+            Ok(Self::from_identity_provider(identity_file, environment))
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            Ok(Self::from_environment(environment))
+        }
+    }
+
+    /// Create a builder for a client authenticating with the identity fetched from `identity_provider`.
+    #[cfg(not(target_arch = "wasm32"))]
     #[cfg_attr(not(coverage), instrument)]
-    pub fn new(identity_cert_file: &'i str, environment: Environment) -> Self {
-        info!(
-            "Configured environment: {environment:?}, connecting to '{}'.",
-            environment.base_url()
-        );
+    pub fn from_identity_provider(
+        identity_provider: impl IdentityProvider + 'static,
+        environment: Environment,
+    ) -> Self {
+        Self::from_environment(environment, Arc::new(identity_provider))
+    }
+
+    /// Create a builder for a client authenticating with the identity certificate (PEM) in `pem`,
+    /// containing both certificate and private key, e.g. as fetched from a secrets manager.
+    ///
+    /// Fails with [`ErrorKind::ParseIdentity`] if the PEM cannot be parsed.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(not(coverage), instrument(skip(pem)))]
+    pub fn from_identity_pem(pem: &[u8], environment: Environment) -> Result<Self> {
+        Ok(Self::from_identity_provider(
+            StaticIdentity::from_pem(pem)?,
+            environment,
+        ))
+    }
+
+    /// Create a builder for a client authenticating with `identity`.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(not(coverage), instrument(skip(identity)))]
+    pub fn from_identity(identity: reqwest::Identity, environment: Environment) -> Self {
+        Self::from_identity_provider(StaticIdentity::new(identity), environment)
+    }
+
+    fn from_environment(
+        environment: Environment,
+        #[cfg(not(target_arch = "wasm32"))] identity_provider: Arc<dyn IdentityProvider>,
+    ) -> Self {
+        info!("Configured environment: {environment:?}.");
 
         Self {
-            identity_cert_file,
+            #[cfg(not(target_arch = "wasm32"))]
+            identity_provider,
             environment,
             connect_timeout: Duration::from_secs(10),
             timeout: Duration::from_secs(30),
+            #[cfg(not(target_arch = "wasm32"))]
             // Basispoort does not support TLS 1.3 yet, so we cannot enforce it by default :(
-            min_tls_version: reqwest::tls::Version::TLS_1_2,
+            min_tls_version: TlsVersion::TLS_1_2,
+            #[cfg(all(feature = "danger-accept-invalid-certs", not(target_arch = "wasm32")))]
+            accept_invalid_certs: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            proxy: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            compression: Compression::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            rate_limiter: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            correlation_id_header: header::HeaderName::from_static(DEFAULT_CORRELATION_ID_HEADER),
+            payload_logging: PayloadLogging::default(),
+            #[cfg(feature = "chaos")]
+            fault_injection: None,
+            #[cfg(feature = "schema-drift")]
+            schema_drift_detector: None,
+            mutation_queue: None,
+            response_cache: None,
+            error_translations: None,
+            interceptors: Vec::new(),
+            service_paths: ServicePaths::default(),
+            integration_tag: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            event_sink: None,
         }
     }
 
@@ -57,62 +195,427 @@ impl<'i> RestClientBuilder<'i> {
     }
 
     /// Sets the minimum TLS version. At the time of writing, Basispoort does not yet support TLS 1.3.
-    pub fn min_tls_version(&mut self, version: reqwest::tls::Version) -> &mut Self {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn min_tls_version(&mut self, version: TlsVersion) -> &mut Self {
         self.min_tls_version = version;
         self
     }
 
+    /// Accepts invalid server certificates, e.g. self-signed certificates of local mock servers in integration tests.
+    ///
+    /// This disables the verification of the server's identity. [`RestClientBuilder::build`] refuses it
+    /// for the production environment, and for any environment whose base URL is overridden with the production URL.
+    #[cfg(all(feature = "danger-accept-invalid-certs", not(target_arch = "wasm32")))]
+    pub fn danger_accept_invalid_certs(&mut self) -> &mut Self {
+        self.accept_invalid_certs = true;
+        self
+    }
+
+    /// Sends all requests through the HTTP(S) proxy, instead of a proxy configured by the `HTTP_PROXY`,
+    /// `HTTPS_PROXY` and `NO_PROXY` environment variables.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn proxy(&mut self, proxy: ProxyConfig) -> &mut Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Sets the response compression algorithms offered in the `Accept-Encoding` header. Defaults to gzip and brotli.
+    ///
+    /// Compressed responses are decoded transparently. Disable compression e.g. to inspect raw responses on the wire.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn compression(&mut self, compression: Compression) -> &mut Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets the request header carrying each request's correlation ID. Defaults to `X-Correlation-ID`.
+    ///
+    /// The correlation ID is also recorded in the request's tracing span, and in [`ErrorKind::HttpResponse`],
+    /// to match failed requests against Basispoort support tickets.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn correlation_id_header(&mut self, name: header::HeaderName) -> &mut Self {
+        self.correlation_id_header = name;
+        self
+    }
+
+    /// Limits the request rate of the client. Clones of `rate_limiter` share their budget,
+    /// so the same limiter may be passed to several builders.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn rate_limiter(&mut self, rate_limiter: RateLimiter) -> &mut Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Controls how request and response payloads are recorded in `trace` and `debug` events.
+    ///
+    /// Defaults to [`PayloadLogging::Truncate`] at 1024 characters.
+    pub fn payload_logging(&mut self, payload_logging: PayloadLogging) -> &mut Self {
+        self.payload_logging = payload_logging;
+        self
+    }
+
+    /// Randomly injects faults into requests, for resilience testing. Never use in production.
+    #[cfg(feature = "chaos")]
+    pub fn fault_injection(&mut self, fault_injection: FaultInjection) -> &mut Self {
+        self.fault_injection = Some(fault_injection);
+        self
+    }
+
+    /// Records response fields unknown to the typed models, warning about changes to the Basispoort API schema.
+    /// Clones of `schema_drift_detector` share their records.
+    #[cfg(feature = "schema-drift")]
+    pub fn schema_drift_detector(
+        &mut self,
+        schema_drift_detector: SchemaDriftDetector,
+    ) -> &mut Self {
+        self.schema_drift_detector = Some(schema_drift_detector);
+        self
+    }
+
+    /// Records mutating requests in `mutation_queue` until the server has responded,
+    /// so requests failing due to an outage can be re-sent by [`RestClient::replay_pending`].
+    pub fn mutation_queue(&mut self, mutation_queue: MutationQueue) -> &mut Self {
+        self.mutation_queue = Some(mutation_queue);
+        self
+    }
+
+    /// Revalidates the `GET` responses cached in `response_cache`, instead of fetching unchanged data again.
+    /// Clones of `response_cache` share their entries, so the same cache may be passed to several builders.
+    pub fn response_cache(&mut self, response_cache: ResponseCache) -> &mut Self {
+        self.response_cache = Some(response_cache);
+        self
+    }
+
+    /// Sets the base paths the service clients request, e.g. for a gateway deployment remapping them.
+    ///
+    /// Defaults to the paths of the Basispoort environments.
+    pub fn service_paths(&mut self, service_paths: ServicePaths) -> &mut Self {
+        self.service_paths = service_paths;
+        self
+    }
+
+    /// Attaches the English translation of known Dutch error responses to [`ErrorKind::HttpResponse`] errors.
+    pub fn error_translations(&mut self, error_translations: ErrorTranslations) -> &mut Self {
+        self.error_translations = Some(Arc::new(error_translations));
+        self
+    }
+
+    /// Calls `interceptor` before sending each request and after receiving each response,
+    /// following the interceptors registered before.
+    pub fn interceptor(&mut self, interceptor: impl RequestInterceptor + 'static) -> &mut Self {
+        self.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    /// Emits a structured [`Event`] to `event_sink` for each request and each failed request.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn event_sink(&mut self, event_sink: Arc<dyn EventSink>) -> &mut Self {
+        self.event_sink = Some(event_sink);
+        self
+    }
+
+    /// Identifies the integration using this client, e.g. `"my-publisher-sync/2.3.0"`.
+    ///
+    /// The tag is prepended to the default `User-Agent` and the `X-Client-Version` header, so Basispoort
+    /// operations can tell which integration version sent a request.
+    pub fn integration_tag(&mut self, integration_tag: impl Into<String>) -> &mut Self {
+        self.integration_tag = Some(integration_tag.into());
+        self
+    }
+
+    /// The client version sent with each request, with the integration tag first, if set.
+    fn client_version(&self) -> String {
+        let client_version = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+        match &self.integration_tag {
+            Some(integration_tag) => format!("{integration_tag} {client_version}"),
+            None => client_version.to_owned(),
+        }
+    }
+
+    fn default_headers(&self) -> Result<HeaderMap> {
+        let client_version = HeaderValue::from_str(&self.client_version()).map_err(|_| {
+            ErrorKind::InvalidConfigValue {
+                key: "integration_tag".into(),
+                value: self.integration_tag.clone().unwrap_or_default(),
+            }
+        })?;
+
+        let mut headers = HeaderMap::new();
+        // In the browser, the user agent sets its own `User-Agent` header.
+        #[cfg(not(target_arch = "wasm32"))]
+        headers.insert(header::USER_AGENT, client_version.clone());
+        headers.insert(CLIENT_VERSION_HEADER, client_version);
+
+        Ok(headers)
+    }
+
     /// Build the configured [`RestClient`].
     ///
-    /// Note that this method is `async` and returns a `Result`, as it reads the client certificate from disk.
+    /// Note that this method is `async` and returns a `Result`, as it fetches the client identity from its provider.
     #[cfg_attr(not(coverage), instrument)]
     pub async fn build(self) -> Result<RestClient> {
-        let mut cert = Vec::new();
-        File::open(self.identity_cert_file)
-            .await
-            .map_err(|source| Error::OpenIdentityCertFile {
-                path: self.identity_cert_file.into(),
-                source,
-            })?
-            .read_to_end(&mut cert)
-            .await
-            .map_err(|source| Error::ReadIdentityCertFile {
-                path: self.identity_cert_file.into(),
-                source,
-            })?;
-        let identity =
-            Identity::from_pem(&cert).map_err(|source| Error::ParseIdentityCertFile {
-                path: self.identity_cert_file.into(),
-                source,
-            })?;
+        let base_url = self.environment.base_url_from_env()?;
+        #[cfg(all(feature = "danger-accept-invalid-certs", not(target_arch = "wasm32")))]
+        if self.accept_invalid_certs {
+            if targets_production(&self.environment, &base_url) {
+                return Err(ErrorKind::InvalidConfigValue {
+                    key: "danger_accept_invalid_certs".into(),
+                    value: Environment::Production.to_string(),
+                }
+                .into());
+            }
+            warn!("Accepting invalid server certificates for '{base_url}'.");
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let request_client_settings = Arc::new(RequestClientSettings {
+            identity_provider: self.identity_provider.clone(),
+            default_headers: self.default_headers()?,
+            connect_timeout: self.connect_timeout,
+            timeout: self.timeout,
+            min_tls_version: self.min_tls_version,
+            proxy: self.proxy.clone(),
+            compression: self.compression,
+            #[cfg(feature = "danger-accept-invalid-certs")]
+            accept_invalid_certs: self.accept_invalid_certs,
+        });
+        #[cfg(not(target_arch = "wasm32"))]
+        let client = request_client_settings.build().await?;
+        #[cfg(target_arch = "wasm32")]
+        let client = self.build_request_client()?;
+        info!("Connecting to '{base_url}'.");
 
-        let client = reqwest::ClientBuilder::new()
+        Ok(RestClient {
+            client: Arc::new(RwLock::new(client)),
+            #[cfg(not(target_arch = "wasm32"))]
+            request_client_settings,
+            base_url,
+            environment: self.environment,
+            #[cfg(not(target_arch = "wasm32"))]
+            rate_limiter: self.rate_limiter,
+            #[cfg(not(target_arch = "wasm32"))]
+            correlation_id_header: self.correlation_id_header,
+            #[cfg(not(target_arch = "wasm32"))]
+            quota: Arc::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            pool_counters: Arc::default(),
+            payload_logging: self.payload_logging,
+            #[cfg(feature = "chaos")]
+            fault_injection: self.fault_injection,
+            #[cfg(feature = "schema-drift")]
+            schema_drift_detector: self.schema_drift_detector,
+            mutation_queue: self.mutation_queue,
+            response_cache: self.response_cache,
+            error_translations: self.error_translations,
+            interceptors: self.interceptors.into(),
+            service_paths: self.service_paths,
+            #[cfg(not(target_arch = "wasm32"))]
+            event_sink: self.event_sink,
+            #[cfg(not(target_arch = "wasm32"))]
+            debug_recorder: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            request_timeout: None,
+            deprecation_warnings: DeprecationWarnings::default(),
+        })
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn build_request_client(&self) -> Result<reqwest::Client> {
+        // In the browser, the user agent provides the client certificate.
+        Ok(reqwest::ClientBuilder::new()
+            .default_headers(self.default_headers()?)
+            .build()
+            .map_err(ErrorKind::BuildRequestClient)?)
+    }
+}
+
+/// The settings of the inner `reqwest::Client`, kept to rebuild it with a new identity
+/// on [`RestClient::reload_identity`].
+#[cfg(not(target_arch = "wasm32"))]
+struct RequestClientSettings {
+    identity_provider: Arc<dyn IdentityProvider>,
+    default_headers: HeaderMap,
+    connect_timeout: Duration,
+    timeout: Duration,
+    min_tls_version: TlsVersion,
+    proxy: Option<ProxyConfig>,
+    compression: Compression,
+    #[cfg(feature = "danger-accept-invalid-certs")]
+    accept_invalid_certs: bool,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl RequestClientSettings {
+    /// Build a `reqwest::Client`, fetching the identity from the provider.
+    async fn build(&self) -> Result<reqwest::Client> {
+        let identity = self.identity_provider.fetch_identity().await?;
+
+        let builder = reqwest::ClientBuilder::new()
+            .default_headers(self.default_headers.clone())
             .identity(identity)
             .connect_timeout(self.connect_timeout)
             .timeout(self.timeout)
             .min_tls_version(self.min_tls_version)
+            .gzip(self.compression.gzip)
+            .brotli(self.compression.brotli);
+        let builder = match &self.proxy {
+            Some(proxy) => builder.proxy(proxy.to_reqwest()?),
+            None => builder,
+        };
+        #[cfg(feature = "danger-accept-invalid-certs")]
+        let builder = builder.danger_accept_invalid_certs(self.accept_invalid_certs);
+
+        builder
             .build()
-            .map_err(Error::BuildRequestClient)?;
+            .map_err(|source| ErrorKind::BuildRequestClient(source).into())
+    }
 
-        Ok(RestClient {
-            client,
-            base_url: self.environment.base_url(),
-        })
+    /// Build a `reqwest::Client` for downloads from other hosts, with the proxy and TLS settings,
+    /// but neither the identity nor the default headers.
+    fn build_download_client(&self) -> Result<reqwest::Client> {
+        let builder = reqwest::ClientBuilder::new()
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.timeout)
+            .min_tls_version(self.min_tls_version)
+            .gzip(self.compression.gzip)
+            .brotli(self.compression.brotli);
+        let builder = match &self.proxy {
+            Some(proxy) => builder.proxy(proxy.to_reqwest()?),
+            None => builder,
+        };
+        #[cfg(feature = "danger-accept-invalid-certs")]
+        let builder = builder.danger_accept_invalid_certs(self.accept_invalid_certs);
+
+        builder
+            .build()
+            .map_err(|source| ErrorKind::BuildRequestClient(source).into())
+    }
+}
+
+/// The response compression algorithms offered to the server, configured with [`RestClientBuilder::compression`].
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Compression {
+    pub gzip: bool,
+    pub brotli: bool,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Compression {
+    /// Offer no compression, receiving responses uncompressed.
+    pub const NONE: Self = Self {
+        gzip: false,
+        brotli: false,
+    };
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Default for Compression {
+    fn default() -> Self {
+        Self {
+            gzip: true,
+            brotli: true,
+        }
+    }
+}
+
+/// An HTTP(S) proxy, configured with [`RestClientBuilder::proxy`].
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone)]
+pub struct ProxyConfig {
+    url: Url,
+    basic_auth: Option<(String, String)>,
+    no_proxy: Vec<String>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ProxyConfig {
+    /// Proxy both HTTP and HTTPS requests through the proxy at `url`, e.g. `http://proxy.school.example:3128`.
+    pub fn new(url: Url) -> Self {
+        Self {
+            url,
+            basic_auth: None,
+            no_proxy: Vec::new(),
+        }
+    }
+
+    /// Authenticate with the proxy using HTTP basic authentication.
+    pub fn with_basic_auth(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.basic_auth = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Bypass the proxy for the hosts, domains (e.g. `.school.example`), IP addresses or CIDR ranges in `no_proxy`,
+    /// as in the `NO_PROXY` environment variable.
+    pub fn with_no_proxy(mut self, no_proxy: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.no_proxy = no_proxy.into_iter().map(Into::into).collect();
+        self
+    }
+
+    fn to_reqwest(&self) -> Result<reqwest::Proxy> {
+        let mut proxy =
+            reqwest::Proxy::all(self.url.clone()).map_err(ErrorKind::BuildRequestClient)?;
+        if let Some((username, password)) = &self.basic_auth {
+            proxy = proxy.basic_auth(username, password);
+        }
+        if !self.no_proxy.is_empty() {
+            proxy = proxy.no_proxy(reqwest::NoProxy::from_string(&self.no_proxy.join(",")));
+        }
+
+        Ok(proxy)
     }
 }
 
+// The password must not be logged.
+#[cfg(not(target_arch = "wasm32"))]
+impl Debug for ProxyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyConfig")
+            .field("url", &self.url.as_str())
+            .field(
+                "username",
+                &self.basic_auth.as_ref().map(|(username, _)| username),
+            )
+            .field("no_proxy", &self.no_proxy)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Request header identifying the client version, and the integration tag, if set.
+const CLIENT_VERSION_HEADER: &str = "x-client-version";
+
+/// Environment variable holding the [`Environment`] name.
+const ENVIRONMENT_VAR: &str = "ENVIRONMENT";
+
 /// A Basispoort environment.
 ///
 /// Environments can be parsed from string, e.g. from `.env` variables.
 ///
 /// Each environment has its own [`Environment::base_url`],
-/// which is used for all [`RestClient`]s [configured][`RestClientBuilder::new`] with this `Environment`.
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+/// which is used for all [`RestClient`]s [configured][`RestClientBuilder::new`] with this `Environment`,
+/// unless overridden by an environment variable, see [`Environment::base_url_from_env`].
+///
+/// [`Environment::Custom`] targets any other base URL, e.g. an internal mock or a self-hosted stub.
+/// It is parsed from an absolute `http` or `https` URL.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
 pub enum Environment {
     Test,
     Acceptance,
     Staging,
     Production,
+    /// A server at the given base URL, which is not overridden by environment variables.
+    Custom(Url),
+}
+
+impl fmt::Display for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 /// [`Environment`] parse error.
@@ -132,34 +635,661 @@ impl FromStr for Environment {
             "acceptance" => Self::Acceptance,
             "staging" => Self::Staging,
             "production" => Self::Production,
-            s => return Err(ParseEnvironmentError::InvalidEnvironmentString(s.into())),
+            s => match Url::parse(s) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => Self::Custom(url),
+                _ => return Err(ParseEnvironmentError::InvalidEnvironmentString(s.into())),
+            },
         })
     }
 }
 
+impl TryFrom<String> for Environment {
+    type Error = ParseEnvironmentError;
+
+    fn try_from(s: String) -> std::result::Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
 impl Environment {
+    /// The environment's name, or the base URL of a custom environment, as parsed by [`Environment::from_str`].
+    pub fn as_str(&self) -> &str {
+        match self {
+            Environment::Test => "test",
+            Environment::Acceptance => "acceptance",
+            Environment::Staging => "staging",
+            Environment::Production => "production",
+            Environment::Custom(base_url) => base_url.as_str(),
+        }
+    }
+
+    /// Read the environment from the `ENVIRONMENT` environment variable, as used in `.env.dist`.
+    pub fn from_env() -> Result<Self> {
+        let value = env::var(ENVIRONMENT_VAR).map_err(|_| ErrorKind::MissingConfigValue {
+            key: ENVIRONMENT_VAR.into(),
+        })?;
+
+        Ok(value.parse().map_err(|_| ErrorKind::InvalidConfigValue {
+            key: ENVIRONMENT_VAR.into(),
+            value,
+        })?)
+    }
+
+    /// The name of the environment variable overriding this environment's base URL,
+    /// e.g. `BASISPOORT_TEST_BASE_URL`, or `None` for a custom environment.
+    pub fn base_url_var(&self) -> Option<String> {
+        match self {
+            Environment::Custom(_) => None,
+            environment => Some(format!(
+                "BASISPOORT_{}_BASE_URL",
+                environment.as_str().to_uppercase()
+            )),
+        }
+    }
+
+    /// The environment's base URL, as overridden by the [`Environment::base_url_var`] environment variable,
+    /// e.g. to target a staging gateway or a local mock. Defaults to [`Environment::base_url`].
+    pub fn base_url_from_env(&self) -> Result<Url> {
+        let Some(key) = self.base_url_var() else {
+            return Ok(self.base_url());
+        };
+        match env::var(&key) {
+            Ok(value) => Ok(value
+                .parse()
+                .map_err(|_| ErrorKind::InvalidConfigValue { key, value })?),
+            Err(_) => Ok(self.base_url()),
+        }
+    }
+
+    /// The environment's default base URL.
     pub fn base_url(&self) -> Url {
         match self {
             Environment::Test => "https://test-rest.basispoort.nl/".parse().unwrap(),
             Environment::Acceptance => "https://acceptatie-rest.basispoort.nl/".parse().unwrap(),
             Environment::Staging => "https://staging-rest.basispoort.nl/".parse().unwrap(),
             Environment::Production => "https://rest.basispoort.nl/".parse().unwrap(),
+            Environment::Custom(base_url) => base_url.clone(),
+        }
+    }
+}
+
+/// Whether a client for `environment`, at `base_url`, targets production.
+fn targets_production(environment: &Environment, base_url: &Url) -> bool {
+    *environment == Environment::Production || *base_url == Environment::Production.base_url()
+}
+
+/// The base paths of the Basispoort services, relative to the environment's base URL.
+///
+/// Service clients read them from their [`RestClient`], configured with [`RestClientBuilder::service_paths`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServicePaths {
+    /// The hosted license provider service, followed by the identity code.
+    pub hosted_license_provider: String,
+    /// The institutions service, followed by the API version, e.g. `v2/`.
+    pub institutions: String,
+    /// The license gateway service.
+    pub license_gateway: String,
+}
+
+impl Default for ServicePaths {
+    fn default() -> Self {
+        Self {
+            hosted_license_provider: "/hosted-lika/management/lika/".into(),
+            institutions: "rest/".into(),
+            license_gateway: "rest/v2/licenties/".into(),
+        }
+    }
+}
+
+/// The server's requested back-off, from the `Retry-After` response header.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RetryAfter {
+    /// Retry after the given delay, counted from receiving the response.
+    Delay(Duration),
+    /// Retry at the given point in time.
+    At(SystemTime),
+}
+
+impl RetryAfter {
+    fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let value = headers.get(header::RETRY_AFTER)?.to_str().ok()?.trim();
+
+        match value.parse() {
+            Ok(seconds) => Some(Self::Delay(Duration::from_secs(seconds))),
+            Err(_) => httpdate::parse_http_date(value).ok().map(Self::At),
+        }
+    }
+
+    /// The remaining time to wait before retrying, from now. Zero if the point in time has passed.
+    pub fn remaining(&self) -> Duration {
+        match self {
+            Self::Delay(delay) => *delay,
+            Self::At(at) => at
+                .duration_since(SystemTime::now())
+                .unwrap_or(Duration::ZERO),
+        }
+    }
+}
+
+/// Rate limit state reported by the server, from `RateLimit-*` or `X-RateLimit-*` response headers.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct RateLimitHeaders {
+    /// The maximum number of requests in the current window.
+    pub limit: Option<u64>,
+    /// The number of requests remaining in the current window.
+    pub remaining: Option<u64>,
+    /// The time until the current window resets.
+    pub reset: Option<Duration>,
+}
+
+impl RateLimitHeaders {
+    fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let value = |name: &str| {
+            [format!("ratelimit-{name}"), format!("x-ratelimit-{name}")]
+                .iter()
+                .find_map(|header| headers.get(header)?.to_str().ok()?.trim().parse().ok())
+        };
+
+        let rate_limit = Self {
+            limit: value("limit"),
+            remaining: value("remaining"),
+            reset: value("reset").map(Duration::from_secs),
+        };
+
+        (rate_limit != Self::default()).then_some(rate_limit)
+    }
+}
+
+/// The most recent rate limit state reported by the server, as returned by [`RestClient::current_quota`].
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct QuotaInfo {
+    pub rate_limit: RateLimitHeaders,
+    /// When the response reporting the rate limit state was received.
+    pub received_at: Instant,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl QuotaInfo {
+    fn from_headers(headers: &HeaderMap, received_at: Instant) -> Option<Self> {
+        Some(Self {
+            rate_limit: RateLimitHeaders::from_headers(headers)?,
+            received_at,
+        })
+    }
+
+    /// The time until the current window resets, counted from now.
+    pub fn reset_in(&self) -> Option<Duration> {
+        self.rate_limit
+            .reset
+            .map(|reset| reset.saturating_sub(self.received_at.elapsed()))
+    }
+}
+
+/// The load of a [`RestClient`] and its clones, as returned by [`RestClient::pool_stats`].
+///
+/// The connection pool of the underlying HTTP client does not report its open and idle connections.
+/// At most [`PoolStats::in_flight`] connections are busy.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Serialize)]
+pub struct PoolStats {
+    /// Requests sent, awaiting their response headers.
+    pub in_flight: usize,
+    /// Requests waiting for the rate limiter, if configured.
+    pub queued: usize,
+}
+
+/// Counters behind [`PoolStats`], shared by all clones of a client.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Default)]
+struct PoolCounters {
+    in_flight: AtomicUsize,
+    queued: AtomicUsize,
+    shutting_down: AtomicBool,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PoolCounters {
+    fn stats(&self) -> PoolStats {
+        PoolStats {
+            in_flight: self.in_flight.load(Ordering::Relaxed),
+            queued: self.queued.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Increments a counter while alive, so cancelled requests are not counted forever.
+#[cfg(not(target_arch = "wasm32"))]
+struct CounterGuard<'a>(&'a AtomicUsize);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<'a> CounterGuard<'a> {
+    fn enter(counter: &'a AtomicUsize) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self(counter)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Drop for CounterGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Timings of a single request.
+///
+/// The connection pool of the underlying HTTP client does not report the durations of DNS resolution,
+/// connecting and the TLS handshake. For new connections, they are included in [`RequestTiming::time_to_headers`].
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct RequestTiming {
+    /// From sending the request until the response headers were received: network latency and server processing.
+    pub time_to_headers: Duration,
+    /// From sending the request until the response body was received.
+    pub total: Duration,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl RequestTiming {
+    /// The time spent receiving the response body.
+    pub fn body_transfer(&self) -> Duration {
+        self.total.saturating_sub(self.time_to_headers)
+    }
+}
+
+/// The default header carrying the correlation ID, see [`RestClientBuilder::correlation_id_header`].
+#[cfg(not(target_arch = "wasm32"))]
+const DEFAULT_CORRELATION_ID_HEADER: &str = "x-correlation-id";
+
+/// The correlation ID of a request, stored in the response extensions to be reported in errors.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+struct CorrelationId(String);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl CorrelationId {
+    /// Generate a random correlation ID, formatted as a version 4 UUID.
+    fn generate() -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let random = || {
+            // Each `RandomState` is seeded differently, and the counter keeps IDs unique within the process.
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+            hasher.finish()
+        };
+
+        let high = (random() & !0xf000) | 0x4000;
+        let low = (random() & !(0b11 << 62)) | (0b10 << 62);
+        Self(format!(
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            high >> 32,
+            (high >> 16) & 0xffff,
+            high & 0xffff,
+            low >> 48,
+            low & 0xffff_ffff_ffff
+        ))
+    }
+}
+
+/// When a request was sent, stored in the response extensions to measure its [`RequestTiming`].
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+struct RequestStarted {
+    #[cfg(feature = "metrics")]
+    labels: crate::metrics::RequestLabels,
+    at: Instant,
+    time_to_headers: Duration,
+}
+
+/// Metadata of a response, as returned by [`RestClient::get_with_metadata`].
+#[derive(Debug, Clone)]
+pub struct ResponseMetadata {
+    pub url: Url,
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    /// The deprecation of the endpoint, if announced in the headers.
+    pub deprecation: Option<Deprecation>,
+    /// Absent if the response was injected for resilience testing (feature `chaos`).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub timing: Option<RequestTiming>,
+}
+
+/// How request and response payloads are recorded in `trace` and `debug` events.
+///
+/// Payloads may be large, e.g. method details embedding base64-encoded icons.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PayloadLogging {
+    /// Do not record payloads.
+    Skip,
+    /// Record payloads, truncated to the given number of characters.
+    Truncate(usize),
+    /// Record complete payloads.
+    Full,
+}
+
+impl Default for PayloadLogging {
+    fn default() -> Self {
+        Self::Truncate(1024)
+    }
+}
+
+/// Formats a payload according to [`PayloadLogging`], only when an event is actually recorded.
+struct LoggedPayload<'p, P: ?Sized> {
+    payload: &'p P,
+    logging: PayloadLogging,
+}
+
+impl<P: Debug + ?Sized> Debug for LoggedPayload<'_, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.logging {
+            PayloadLogging::Skip => f.write_str("<skipped>"),
+            PayloadLogging::Full => self.payload.fmt(f),
+            PayloadLogging::Truncate(max_chars) => {
+                let formatted = format!("{:?}", self.payload);
+                match formatted.char_indices().nth(max_chars) {
+                    Some((end, _)) => write!(
+                        f,
+                        "{}... ({} bytes truncated)",
+                        &formatted[..end],
+                        formatted.len() - end
+                    ),
+                    None => f.write_str(&formatted),
+                }
+            }
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct RestClient {
-    client: reqwest::Client,
+    /// Shared by all clones, so [`RestClient::reload_identity`] affects all of them.
+    client: Arc<RwLock<reqwest::Client>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    request_client_settings: Arc<RequestClientSettings>,
     pub base_url: Url,
+    environment: Environment,
+    #[cfg(not(target_arch = "wasm32"))]
+    rate_limiter: Option<RateLimiter>,
+    #[cfg(not(target_arch = "wasm32"))]
+    correlation_id_header: header::HeaderName,
+    #[cfg(not(target_arch = "wasm32"))]
+    quota: Arc<Mutex<Option<QuotaInfo>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pool_counters: Arc<PoolCounters>,
+    payload_logging: PayloadLogging,
+    #[cfg(feature = "chaos")]
+    fault_injection: Option<FaultInjection>,
+    #[cfg(feature = "schema-drift")]
+    schema_drift_detector: Option<SchemaDriftDetector>,
+    mutation_queue: Option<MutationQueue>,
+    response_cache: Option<ResponseCache>,
+    error_translations: Option<Arc<ErrorTranslations>>,
+    interceptors: Arc<[Arc<dyn RequestInterceptor>]>,
+    service_paths: ServicePaths,
+    #[cfg(not(target_arch = "wasm32"))]
+    event_sink: Option<Arc<dyn EventSink>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    debug_recorder: Option<Recorder>,
+    /// Overrides the builder's timeout, see [`RestClient::with_timeout`].
+    #[cfg(not(target_arch = "wasm32"))]
+    request_timeout: Option<Duration>,
+    deprecation_warnings: DeprecationWarnings,
+}
+
+// The inner `reqwest::Client` debug-prints its full configuration, including default headers.
+impl Debug for RestClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("RestClient");
+        debug.field("base_url", &self.base_url.as_str());
+        #[cfg(not(target_arch = "wasm32"))]
+        debug.field("rate_limited", &self.rate_limiter.is_some());
+        debug.field("payload_logging", &self.payload_logging);
+        #[cfg(feature = "chaos")]
+        debug.field("fault_injection", &self.fault_injection.is_some());
+        #[cfg(feature = "schema-drift")]
+        debug.field(
+            "schema_drift_detection",
+            &self.schema_drift_detector.is_some(),
+        );
+        debug.field("mutation_queue", &self.mutation_queue.is_some());
+        debug.field("response_cache", &self.response_cache.is_some());
+        debug.field("error_translations", &self.error_translations.is_some());
+        debug.field("interceptors", &self.interceptors);
+        debug.field("service_paths", &self.service_paths);
+        #[cfg(not(target_arch = "wasm32"))]
+        debug.field("event_sink", &self.event_sink);
+        #[cfg(not(target_arch = "wasm32"))]
+        debug.field("debug_capture", &self.debug_recorder.is_some());
+        #[cfg(not(target_arch = "wasm32"))]
+        debug.field("request_timeout", &self.request_timeout);
+        debug.finish_non_exhaustive()
+    }
 }
 
 impl RestClient {
+    /// The rate limit state reported by the most recent response carrying `RateLimit-*` or `X-RateLimit-*` headers,
+    /// shared by all clones of this client. Schedulers may use it to pace large syncs.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn current_quota(&self) -> Option<QuotaInfo> {
+        *self
+            .quota
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The base paths of the service clients using this client.
+    pub fn service_paths(&self) -> &ServicePaths {
+        &self.service_paths
+    }
+
+    /// The environment the client was configured with. Its base URL may be overridden,
+    /// see [`Environment::base_url_from_env`].
+    pub fn environment(&self) -> &Environment {
+        &self.environment
+    }
+
+    /// Whether the client targets production: it is configured with [`Environment::Production`],
+    /// even if its base URL is overridden, or its base URL is the production URL.
+    pub fn is_production(&self) -> bool {
+        targets_production(&self.environment, &self.base_url)
+    }
+
+    /// Fetch the identity from the identity provider again, e.g. after the certificate file was rotated,
+    /// and authenticate all further requests of this client and its clones with it.
+    ///
+    /// Requests in flight complete using the previous identity. If the new identity cannot be fetched or used,
+    /// the client keeps using the previous one.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(not(coverage), instrument(skip(self)))]
+    pub async fn reload_identity(&self) -> Result<()> {
+        let client = self.request_client_settings.build().await?;
+        *self
+            .client
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = client;
+        info!("Reloaded the client identity.");
+
+        Ok(())
+    }
+
+    /// A `reqwest::Client` for downloads from hosts other than Basispoort, such as icons passed to
+    /// [`MethodDetails::with_icon_from_url`][crate::hosted_license_provider::MethodDetails::with_icon_from_url].
+    ///
+    /// It uses this client's proxy, TLS and timeout settings, so downloads pass the same proxy,
+    /// but it sends neither the identity certificate nor the default headers. Build it once and reuse it.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn download_client(&self) -> Result<reqwest::Client> {
+        self.request_client_settings.build_download_client()
+    }
+
+    /// A `reqwest::Client` for downloads from hosts other than Basispoort. In the browser,
+    /// the user agent applies its proxy and TLS settings.
+    #[cfg(target_arch = "wasm32")]
+    pub fn download_client(&self) -> Result<reqwest::Client> {
+        Ok(reqwest::ClientBuilder::new()
+            .build()
+            .map_err(ErrorKind::BuildRequestClient)?)
+    }
+
+    /// The current inner client. Cheap to clone, as it is reference counted.
+    fn client(&self) -> reqwest::Client {
+        self.client
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// The requests in flight and queued, counted over all clones of this client, e.g. for a health endpoint.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pool_stats(&self) -> PoolStats {
+        self.pool_counters.stats()
+    }
+
+    /// Stop accepting new requests, and wait up to `grace_period` for the requests in flight to receive
+    /// their response headers. Then flush the event sink, if configured.
+    ///
+    /// Applies to all clones of this client. Requests sent afterwards fail with [`ErrorKind::ShuttingDown`],
+    /// as do requests still queued by the rate limiter once it admits them. Failed requests are not retried.
+    /// Metrics are emitted synchronously, so there is nothing to flush.
+    ///
+    /// Returns the requests still in flight or queued when the grace period elapsed, or zeros if all were drained.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg_attr(not(coverage), instrument(skip(self)))]
+    pub async fn shutdown(&self, grace_period: Duration) -> PoolStats {
+        const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+        self.pool_counters
+            .shutting_down
+            .store(true, Ordering::Relaxed);
+        info!("Shutting down, draining requests.");
+
+        let deadline = Instant::now() + grace_period;
+        let mut stats = self.pool_stats();
+        while stats != PoolStats::default() && Instant::now() < deadline {
+            let slept = crate::runtime::sleep(
+                POLL_INTERVAL.min(deadline.saturating_duration_since(Instant::now())),
+            )
+            .await;
+            if let Err(error) = slept {
+                warn!("Stopped waiting for requests to drain: {error}");
+                break;
+            }
+            stats = self.pool_stats();
+        }
+
+        if stats == PoolStats::default() {
+            info!("Drained all requests.");
+        } else {
+            warn!(
+                ?stats,
+                "Grace period elapsed before all requests were drained."
+            );
+        }
+
+        if let Some(event_sink) = &self.event_sink {
+            event_sink.flush();
+        }
+
+        stats
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn record_quota(&self, headers: &HeaderMap) {
+        if let Some(quota) = QuotaInfo::from_headers(headers, Instant::now()) {
+            trace!(?quota, "Recording rate limit state.");
+            *self
+                .quota
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(quota);
+        }
+    }
+
+    /// A clone of this client, applying `timeout` to each of its requests instead of the builder's
+    /// [timeout][RestClientBuilder::timeout], e.g. for slow bulk operations.
+    ///
+    /// Pass the clone to a service client to apply the timeout to its requests only:
+    ///
+    /// ```no_run
+    /// # async fn example(rest_client: &basispoort_sync_client::rest::RestClient) -> basispoort_sync_client::Result<()> {
+    /// use std::time::Duration;
+    ///
+    /// use basispoort_sync_client::hosted_license_provider::{BulkRequest, HostedLicenseProviderClient};
+    ///
+    /// let slow_client = rest_client.with_timeout(Duration::from_secs(300));
+    /// HostedLicenseProviderClient::new(&slow_client, "my-identity-code")
+    ///     .bulk_grant_permissions(&BulkRequest::new())
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_timeout(&self, timeout: Duration) -> RestClient {
+        RestClient {
+            request_timeout: Some(timeout),
+            ..self.clone()
+        }
+    }
+
+    /// Run `calls` with a clone of this client, capturing each request it sends, with the raw response.
+    ///
+    /// Sensitive headers and personal data in JSON bodies are redacted. Requests sent by other clones of this client
+    /// are not captured.
+    ///
+    /// ```no_run
+    /// # async fn example(rest_client: &basispoort_sync_client::rest::RestClient) {
+    /// use basispoort_sync_client::hosted_license_provider::HostedLicenseProviderClient;
+    ///
+    /// let (methods, capture) = rest_client
+    ///     .debug_capture(|rest_client| async move {
+    ///         HostedLicenseProviderClient::new(&rest_client, "my-identity-code")
+    ///             .get_methods()
+    ///             .await
+    ///     })
+    ///     .await;
+    /// if methods.is_err() {
+    ///     eprintln!("{:#?}", capture.exchanges);
+    /// }
+    /// # }
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn debug_capture<F, Fut, T>(&self, calls: F) -> (T, DebugCapture)
+    where
+        F: FnOnce(RestClient) -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let recorder = Recorder::default();
+        let client = RestClient {
+            debug_recorder: Some(recorder.clone()),
+            ..self.clone()
+        };
+
+        let output = calls(client).await;
+
+        (output, recorder.finish())
+    }
+
+    /// Emit the event built by `event` to the event sink, if configured.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn emit_event(&self, event: impl FnOnce() -> Event) {
+        if let Some(event_sink) = &self.event_sink {
+            event_sink.emit(&event());
+        }
+    }
+
+    fn logged<'p, P: Debug + ?Sized>(&self, payload: &'p P) -> LoggedPayload<'p, P> {
+        LoggedPayload {
+            payload,
+            logging: self.payload_logging,
+        }
+    }
+
     // TODO: Unit test
     #[cfg_attr(not(coverage), instrument)]
     fn make_url(&self, path: &str) -> Result<Url> {
         self.base_url.join(path).map_err(|source| {
-            Error::ParseUrl {
+            ErrorKind::ParseUrl {
                 url: path.to_owned(),
                 source,
             }
@@ -175,19 +1305,51 @@ impl RestClient {
 
         match response.error_for_status_ref() {
             Err(source) => {
-                let response_bytes = response.bytes().await.map_err(Error::ReceiveResponseBody)?;
+                let retry_after = RetryAfter::from_headers(response.headers());
+                let rate_limit = RateLimitHeaders::from_headers(response.headers());
+                #[cfg(not(target_arch = "wasm32"))]
+                let correlation_id = response
+                    .extensions()
+                    .get::<CorrelationId>()
+                    .map(|correlation_id| correlation_id.0.clone());
+                #[cfg(target_arch = "wasm32")]
+                let correlation_id = None;
+
+                let response_bytes = response
+                    .bytes()
+                    .await
+                    .map_err(ErrorKind::ReceiveResponseBody)?;
 
                 let error_response = match serde_json::from_slice(&response_bytes) {
                     Ok(error_response) => ErrorResponse::JSON(error_response),
                     Err(_) => ErrorResponse::Plain(String::from_utf8_lossy(&response_bytes).into()),
                 };
 
-                warn!("HTTP {status} error response for URL '{url}': {error_response:#?}");
+                match &error_response {
+                    ErrorResponse::JSON(value) => warn!(
+                        "HTTP {status} error response for URL '{url}': {:#}",
+                        redact::redact_personal_data(value.clone())
+                    ),
+                    ErrorResponse::Plain(body) => {
+                        warn!("HTTP {status} error response for URL '{url}': {body}")
+                    }
+                }
+
+                let translation = self
+                    .error_translations
+                    .as_ref()
+                    .and_then(|translations| translations.translate(&error_response))
+                    .cloned();
 
-                Err(Error::HttpResponse {
+                Err(ErrorKind::HttpResponse {
                     url: url.to_owned(),
                     status,
                     error_response,
+                    request_summary: None,
+                    translation,
+                    retry_after,
+                    rate_limit,
+                    correlation_id,
                     source,
                 }
                 .into())
@@ -196,102 +1358,689 @@ impl RestClient {
         }
     }
 
+    #[cfg_attr(not(coverage), instrument(skip(self, request), fields(correlation_id)))]
+    async fn send(&self, url: &Url, request: RequestBuilder) -> Result<Response> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.pool_counters.shutting_down.load(Ordering::Relaxed) {
+            return Err(ErrorKind::ShuttingDown.into());
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(rate_limiter) = &self.rate_limiter {
+            let _queued = CounterGuard::enter(&self.pool_counters.queued);
+            rate_limiter.acquire().await?;
+            if self.pool_counters.shutting_down.load(Ordering::Relaxed) {
+                return Err(ErrorKind::ShuttingDown.into());
+            }
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let request = match self.request_timeout {
+            Some(timeout) => request.timeout(timeout),
+            None => request,
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        let correlation_id = CorrelationId::generate();
+        #[cfg(not(target_arch = "wasm32"))]
+        tracing::Span::current().record("correlation_id", correlation_id.0.as_str());
+        #[cfg(not(target_arch = "wasm32"))]
+        let request = request.header(&self.correlation_id_header, correlation_id.0.as_str());
+        let mut request = request.build().map_err(ErrorKind::HttpRequest)?;
+        for interceptor in self.interceptors.iter() {
+            interceptor.before_send(&mut request);
+        }
+        let method = request.method().clone();
+
+        #[cfg(feature = "chaos")]
+        if let Some(fault) = self.fault_injection.as_ref().and_then(FaultInjection::roll) {
+            return self.inject_fault(fault, url, request).await;
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let capture_index = self
+            .debug_recorder
+            .as_ref()
+            .map(|recorder| recorder.record_request(&request));
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let started = Instant::now();
+        #[cfg(not(target_arch = "wasm32"))]
+        self.emit_event(|| Event::RequestStarted {
+            method: method.to_string(),
+            url: url.to_string(),
+        });
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let in_flight = CounterGuard::enter(&self.pool_counters.in_flight);
+        let response = self.execute(request).await;
+        #[cfg(not(target_arch = "wasm32"))]
+        drop(in_flight);
+        #[cfg(not(target_arch = "wasm32"))]
+        let mut response = response;
+        #[cfg(not(target_arch = "wasm32"))]
+        if let (Some(recorder), Some(index), Ok(response)) =
+            (&self.debug_recorder, capture_index, &mut response)
+        {
+            recorder.record_response(index, response);
+            response.extensions_mut().insert(index);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        let response = response.map(|mut response| {
+            response.extensions_mut().insert(correlation_id.clone());
+            response.extensions_mut().insert(RequestStarted {
+                #[cfg(feature = "metrics")]
+                labels: crate::metrics::RequestLabels::new(
+                    &method,
+                    &self.base_url,
+                    &self.service_paths,
+                    url,
+                ),
+                at: started,
+                time_to_headers: started.elapsed(),
+            });
+            response
+        });
+
+        #[cfg(not(target_arch = "wasm32"))]
+        self.emit_event(|| Event::RequestFinished {
+            method: method.to_string(),
+            url: url.to_string(),
+            status: response
+                .as_ref()
+                .ok()
+                .map(|response| response.status().as_u16()),
+            duration_ms: Event::duration_ms(started.elapsed()),
+        });
+
+        let result = match response {
+            Ok(response) => {
+                for interceptor in self.interceptors.iter() {
+                    interceptor.after_receive(&method, &response);
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                self.record_quota(response.headers());
+
+                self.error_status(url, response).await
+            }
+            Err(error) => Err(error),
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Err(error) = &result {
+            self.emit_event(|| Event::Error {
+                url: url.to_string(),
+                message: error.to_string(),
+            });
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let (Some(recorder), Some(index), Err(error)) =
+            (&self.debug_recorder, capture_index, &result)
+        {
+            recorder.record_error(index, error);
+        }
+
+        result
+    }
+
+    #[cfg(not(all(feature = "metrics", not(target_arch = "wasm32"))))]
+    async fn execute(&self, request: Request) -> Result<Response> {
+        self.client()
+            .execute(request)
+            .await
+            .map_err(|source| ErrorKind::HttpRequest(source).into())
+    }
+
+    #[cfg(all(feature = "metrics", not(target_arch = "wasm32")))]
+    async fn execute(&self, request: Request) -> Result<Response> {
+        let labels = crate::metrics::RequestLabels::new(
+            request.method(),
+            &self.base_url,
+            &self.service_paths,
+            request.url(),
+        );
+        let started = std::time::Instant::now();
+
+        let response = self.client().execute(request).await;
+
+        crate::metrics::record_request(
+            &labels,
+            response.as_ref().ok().map(Response::status),
+            started.elapsed(),
+        );
+
+        response.map_err(|source| ErrorKind::HttpRequest(source).into())
+    }
+
+    #[cfg(feature = "chaos")]
+    #[cfg_attr(not(coverage), instrument(skip(self, request)))]
+    async fn inject_fault(&self, fault: Fault, url: &Url, request: Request) -> Result<Response> {
+        warn!("Injecting fault {fault:?} into request for '{url}'.");
+
+        let response = match fault {
+            Fault::Timeout => return Err(ErrorKind::InjectedTimeout { url: url.clone() }.into()),
+            Fault::ServerError => http::Response::builder()
+                .status(reqwest::StatusCode::INTERNAL_SERVER_ERROR)
+                .body("Injected fault")
+                .expect("static response parts are valid")
+                .into(),
+            Fault::MalformedBody => {
+                let response = self.execute(request).await?;
+                let status = response.status();
+                let body = response
+                    .bytes()
+                    .await
+                    .map_err(ErrorKind::ReceiveResponseBody)?;
+
+                // Truncate the body, breaking its JSON syntax. Empty bodies become an unterminated object.
+                let malformed = match body.len() {
+                    0 => Bytes::from_static(b"{"),
+                    len => body.slice(..len / 2),
+                };
+
+                http::Response::builder()
+                    .status(status)
+                    .body(malformed)
+                    .expect("response parts taken from a valid response")
+                    .into()
+            }
+        };
+
+        self.error_status(url, response).await
+    }
+
     #[cfg_attr(not(coverage), instrument(skip(self, response)))]
+    /// Receive the response body, measuring the request's timing.
+    async fn receive(&self, response: Response) -> Result<(Bytes, ResponseMetadata)> {
+        #[cfg(not(target_arch = "wasm32"))]
+        let started = response.extensions().get::<RequestStarted>().cloned();
+        #[cfg(not(target_arch = "wasm32"))]
+        let capture_index = response.extensions().get::<CaptureIndex>().copied();
+        let url = response.url().clone();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let deprecation = Deprecation::from_headers(&headers);
+        if let Some(deprecation) = &deprecation {
+            self.deprecation_warnings.warn_once(&url, deprecation);
+        }
+
+        let payload_raw = response
+            .bytes()
+            .await
+            .map_err(ErrorKind::ReceiveResponseBody)?;
+        trace!(payload_raw = ?self.logged(&redact::RedactedBody(&payload_raw)));
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let (Some(recorder), Some(index)) = (&self.debug_recorder, capture_index) {
+            recorder.record_response_body(index, &payload_raw);
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let timing = started.map(|started| {
+            let timing = RequestTiming {
+                time_to_headers: started.time_to_headers,
+                total: started.at.elapsed(),
+            };
+            #[cfg(feature = "metrics")]
+            crate::metrics::record_response(&started.labels, timing.total);
+            trace!(?timing, "Received response.");
+            timing
+        });
+
+        Ok((
+            payload_raw,
+            ResponseMetadata {
+                url,
+                status,
+                headers,
+                deprecation,
+                #[cfg(not(target_arch = "wasm32"))]
+                timing,
+            },
+        ))
+    }
+
     async fn deserialize<T: DeserializeOwned + Debug>(&self, response: Response) -> Result<T> {
-        let payload_raw = response.bytes().await.map_err(Error::ReceiveResponseBody)?;
-        trace!(?payload_raw);
+        Ok(self.deserialize_with_metadata(response).await?.0)
+    }
+
+    async fn deserialize_with_metadata<T: DeserializeOwned + Debug>(
+        &self,
+        response: Response,
+    ) -> Result<(T, ResponseMetadata)> {
+        let (payload_raw, metadata) = self.receive(response).await?;
+        self.deserialize_body(payload_raw, metadata)
+    }
 
+    fn deserialize_body<T: DeserializeOwned + Debug>(
+        &self,
+        payload_raw: Bytes,
+        metadata: ResponseMetadata,
+    ) -> Result<(T, ResponseMetadata)> {
         // Replace empty responses by valid JSON, deserializable into `T = ()`.
         let payload_raw = match payload_raw.len() {
             0 => Bytes::from_static(b"null"),
             _ => payload_raw,
         };
 
+        #[cfg(feature = "schema-drift")]
+        let payload_deserialized = match &self.schema_drift_detector {
+            Some(detector) => detector.deserialize(&metadata.url, &payload_raw),
+            None => serde_json::from_slice(&payload_raw),
+        };
+        #[cfg(not(feature = "schema-drift"))]
+        let payload_deserialized = serde_json::from_slice(&payload_raw);
+
         let payload_deserialized =
-            serde_json::from_slice(&payload_raw).map_err(Error::DeserializeResponseBody)?;
-        debug!(?payload_deserialized);
+            payload_deserialized.map_err(ErrorKind::DeserializeResponseBody)?;
+        debug!(payload_deserialized = ?self.logged(&payload_deserialized));
 
-        Ok(payload_deserialized)
+        Ok((payload_deserialized, metadata))
+    }
+
+    /// Send a `GET` request, revalidating the cached response if the client has a [`ResponseCache`].
+    ///
+    /// If the server responds with `304 Not Modified`, the cached body is returned with the metadata of the `304`
+    /// response.
+    async fn get_cached(&self, url: &Url) -> Result<(Bytes, ResponseMetadata)> {
+        let Some(cache) = &self.response_cache else {
+            let response = self.send(url, self.client().get(url.clone())).await?;
+            return self.receive(response).await;
+        };
+
+        let cached = cache.get(url);
+        let request = match &cached {
+            Some(cached) => cached.revalidate(self.client().get(url.clone())),
+            None => self.client().get(url.clone()),
+        };
+        let (payload_raw, metadata) = self.receive(self.send(url, request).await?).await?;
+
+        match cached {
+            Some(cached) if metadata.status == StatusCode::NOT_MODIFIED => {
+                debug!("Using the cached response, as it was not modified.");
+                Ok((cached.body, metadata))
+            }
+            _ => {
+                cache.store(url, &metadata.headers, payload_raw.clone());
+                Ok((payload_raw, metadata))
+            }
+        }
     }
 
     #[cfg_attr(not(coverage), instrument)]
-    pub async fn get<T: DeserializeOwned + Debug + ?Sized>(&self, path: &str) -> Result<T> {
+    pub async fn get<T: DeserializeOwned + Debug>(&self, path: &str) -> Result<T> {
         let url = self.make_url(path)?;
         trace!("GET {}", url.as_str());
 
-        let response = self
-            .client
-            .get(url.clone())
-            .send()
-            .await
-            .map_err(Error::HttpRequest)?;
+        let (payload_raw, metadata) = self.get_cached(&url).await?;
+        Ok(self.deserialize_body(payload_raw, metadata)?.0)
+    }
 
-        let response = self.error_status(&url, response).await?;
-        self.deserialize(response).await
+    /// Like [`RestClient::get`], also returning the response's [`ResponseMetadata`], e.g. its headers and timing.
+    #[cfg_attr(not(coverage), instrument)]
+    pub async fn get_with_metadata<T: DeserializeOwned + Debug>(
+        &self,
+        path: &str,
+    ) -> Result<(T, ResponseMetadata)> {
+        let url = self.make_url(path)?;
+        trace!("GET {}", url.as_str());
+
+        let (payload_raw, metadata) = self.get_cached(&url).await?;
+        self.deserialize_body(payload_raw, metadata)
+    }
+
+    /// Get the raw response body, e.g. for deserializing into borrowing models.
+    #[cfg_attr(not(coverage), instrument)]
+    pub async fn get_bytes(&self, path: &str) -> Result<Bytes> {
+        let url = self.make_url(path)?;
+        trace!("GET {}", url.as_str());
+
+        Ok(self.get_cached(&url).await?.0)
     }
 
     #[cfg_attr(not(coverage), instrument(skip(payload)))]
-    pub async fn post<P: Serialize + Debug + ?Sized, T: DeserializeOwned + Debug + ?Sized>(
+    pub async fn post<P: Serialize + Debug + ?Sized, T: DeserializeOwned + Debug>(
         &self,
         path: &str,
         payload: &P,
     ) -> Result<T> {
         let url = self.make_url(path)?;
-        trace!(?payload, "POST {}", url.as_str());
+        trace!(payload = ?self.logged(payload), "POST {}", url.as_str());
 
         let response = self
-            .client
-            .post(url.clone())
-            .json(payload)
-            .send()
+            .send_mutation(
+                MutationMethod::Post,
+                path,
+                &url,
+                self.client().post(url.clone()).json(payload),
+                Some(payload),
+            )
             .await
-            .map_err(Error::HttpRequest)?;
-
-        let response = self.error_status(&url, response).await?;
+            .map_err(|error| with_request_summary(error, payload))?;
         self.deserialize(response).await
     }
 
     #[cfg_attr(not(coverage), instrument(skip(payload)))]
-    pub async fn put<P: Serialize + Debug + ?Sized, T: DeserializeOwned + Debug + ?Sized>(
+    pub async fn put<P: Serialize + Debug + ?Sized, T: DeserializeOwned + Debug>(
         &self,
         path: &str,
         payload: &P,
     ) -> Result<T> {
         let url = self.make_url(path)?;
-        trace!(?payload, "PUT {}", url.as_str());
+        trace!(payload = ?self.logged(payload), "PUT {}", url.as_str());
 
         let response = self
-            .client
-            .put(url.clone())
-            .json(payload)
-            .send()
+            .send_mutation(
+                MutationMethod::Put,
+                path,
+                &url,
+                self.client().put(url.clone()).json(payload),
+                Some(payload),
+            )
             .await
-            .map_err(Error::HttpRequest)?;
-
-        let response = self.error_status(&url, response).await?;
+            .map_err(|error| with_request_summary(error, payload))?;
         self.deserialize(response).await
     }
 
     #[cfg_attr(not(coverage), instrument)]
-    pub async fn delete<T: DeserializeOwned + Debug + ?Sized>(&self, path: &str) -> Result<T> {
+    pub async fn delete<T: DeserializeOwned + Debug>(&self, path: &str) -> Result<T> {
         let url = self.make_url(path)?;
         trace!("DELETE {}", url.as_str());
 
         let response = self
-            .client
-            .delete(url.clone())
-            .send()
-            .await
-            .map_err(Error::HttpRequest)?;
-
-        let response = self.error_status(&url, response).await?;
+            .send_mutation(
+                MutationMethod::Delete,
+                path,
+                &url,
+                self.client().delete(url.clone()),
+                None::<&()>,
+            )
+            .await?;
         self.deserialize(response).await
     }
+
+    /// Send a mutating request, recording it in the mutation queue, if configured, until the server has responded.
+    async fn send_mutation<P: Serialize + ?Sized>(
+        &self,
+        method: MutationMethod,
+        path: &str,
+        url: &Url,
+        request: RequestBuilder,
+        payload: Option<&P>,
+    ) -> Result<Response> {
+        let Some(queue) = &self.mutation_queue else {
+            return self.send(url, request).await;
+        };
+
+        let key = queue
+            .push(&PendingMutation {
+                method,
+                path: path.to_owned(),
+                payload: payload
+                    .map(serde_json::to_value)
+                    .transpose()
+                    .map_err(ErrorKind::EncodePayload)?,
+                recorded_at: SystemTime::now(),
+            })
+            .await?;
+
+        let result = self.send(url, request).await;
+        match &result {
+            Err(error) if error.is_retryable() => {
+                warn!("{method:?} '{url}' failed due to an outage, queued for replay as '{key}'.");
+            }
+            _ => queue.remove(&key).await?,
+        }
+
+        result
+    }
+
+    /// Re-send the mutating requests queued during an outage, in their original order, returning their number.
+    ///
+    /// Stops at the first failing request. A request failing due to another outage stays queued;
+    /// a request rejected by the server is removed from the queue. Without a mutation queue, this does nothing.
+    #[cfg_attr(not(coverage), instrument(skip(self)))]
+    pub async fn replay_pending(&self) -> Result<usize> {
+        let Some(queue) = &self.mutation_queue else {
+            return Ok(0);
+        };
+
+        let entries = queue.entries().await?;
+        let pending = entries.len();
+        info!("Replaying {pending} pending mutations.");
+
+        for (replayed, (key, mutation)) in entries.into_iter().enumerate() {
+            let url = self.make_url(&mutation.path)?;
+            trace!(payload = ?self.logged(&mutation.payload), "Replaying {:?} {}", mutation.method, url.as_str());
+
+            let mut request = self.client().request(mutation.method.into(), url.clone());
+            if let Some(payload) = &mutation.payload {
+                request = request.json(payload);
+            }
+
+            if let Err(error) = self.send(&url, request).await {
+                if !error.is_retryable() {
+                    warn!("Removing rejected mutation '{key}' from the queue.");
+                    queue.remove(&key).await?;
+                }
+                info!("Replayed {replayed} pending mutations.");
+                return Err(error);
+            }
+            queue.remove(&key).await?;
+        }
+
+        Ok(pending)
+    }
+}
+
+/// Attach a redacted summary of `payload` to errors rejecting it as invalid.
+fn with_request_summary<P: Serialize + ?Sized>(
+    mut error: crate::error::Error,
+    payload: &P,
+) -> crate::error::Error {
+    if let ErrorKind::HttpResponse {
+        status: StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY,
+        request_summary,
+        ..
+    } = error.kind_mut()
+    {
+        *request_summary = Some(redact::summarize(payload));
+    }
+
+    error
 }
 
 #[cfg(test)]
 mod tests {
-    // use super::*;
+    use super::*;
 
     // TODO: Test make_url
+
+    #[cfg(all(feature = "danger-accept-invalid-certs", not(target_arch = "wasm32")))]
+    #[tokio::test]
+    async fn refuses_accepting_invalid_certs_in_production() {
+        let mut builder = RestClientBuilder::from_identity_provider(
+            IdentityFile::new("missing.pem"),
+            Environment::Production,
+        );
+        builder.danger_accept_invalid_certs();
+
+        let error = builder.build().await.unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::InvalidConfigValue { key, .. } if key == "danger_accept_invalid_certs"
+        ));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn counts_requests_while_guarded() {
+        let counters = PoolCounters::default();
+
+        let in_flight = CounterGuard::enter(&counters.in_flight);
+        let queued = CounterGuard::enter(&counters.queued);
+        assert_eq!(
+            counters.stats(),
+            PoolStats {
+                in_flight: 1,
+                queued: 1
+            }
+        );
+
+        drop((in_flight, queued));
+        assert_eq!(counters.stats(), PoolStats::default());
+    }
+
+    #[test]
+    fn round_trips_environment_names() {
+        for environment in [
+            Environment::Test,
+            Environment::Acceptance,
+            Environment::Staging,
+            Environment::Production,
+            Environment::Custom("http://localhost:8080/".parse().unwrap()),
+        ] {
+            assert_eq!(
+                environment.to_string().parse::<Environment>().ok(),
+                Some(environment)
+            );
+        }
+        assert!("ftp://localhost/".parse::<Environment>().is_err());
+        assert!("localhost".parse::<Environment>().is_err());
+    }
+
+    #[test]
+    fn prepends_integration_tag_to_client_version() -> Result<()> {
+        let mut builder = RestClientBuilder::from_identity_provider(
+            IdentityFile::new("identity.pem"),
+            Environment::Test,
+        );
+        assert_eq!(
+            builder.client_version(),
+            concat!("basispoort-sync-client/", env!("CARGO_PKG_VERSION"))
+        );
+
+        builder.integration_tag("my-sync/2.3.0");
+        let headers = builder.default_headers()?;
+        assert_eq!(
+            headers[CLIENT_VERSION_HEADER],
+            concat!(
+                "my-sync/2.3.0 basispoort-sync-client/",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+        assert_eq!(headers[header::USER_AGENT], headers[CLIENT_VERSION_HEADER]);
+
+        builder.integration_tag("invalid\n");
+        assert!(builder.default_headers().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn redacts_proxy_password() -> Result<()> {
+        let proxy = ProxyConfig::new("http://proxy.school.example:3128".parse().unwrap())
+            .with_basic_auth("sync", "secret")
+            .with_no_proxy([".school.example", "10.0.0.0/8"]);
+        proxy.to_reqwest()?;
+
+        let debug = format!("{proxy:?}");
+        assert!(debug.contains("sync"));
+        assert!(!debug.contains("secret"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn generates_unique_correlation_ids() {
+        let first = CorrelationId::generate().0;
+        let second = CorrelationId::generate().0;
+        assert_ne!(first, second);
+        assert_eq!(first.len(), 36);
+        assert_eq!(&first[14..15], "4");
+        assert!(HeaderValue::from_str(&first).is_ok());
+    }
+
+    #[test]
+    fn targets_production_by_environment_or_url() {
+        let local: Url = "http://localhost/".parse().unwrap();
+        assert!(targets_production(&Environment::Production, &local));
+        assert!(targets_production(
+            &Environment::Custom(local.clone()),
+            &Environment::Production.base_url()
+        ));
+        assert!(!targets_production(&Environment::Test, &local));
+    }
+
+    #[test]
+    fn names_base_url_override_variables() {
+        assert_eq!(
+            Environment::Test.base_url_var().as_deref(),
+            Some("BASISPOORT_TEST_BASE_URL")
+        );
+        assert_eq!(
+            Environment::Production.base_url_var().as_deref(),
+            Some("BASISPOORT_PRODUCTION_BASE_URL")
+        );
+        assert_eq!(
+            Environment::Custom("http://localhost/".parse().unwrap()).base_url_var(),
+            None
+        );
+    }
+
+    #[test]
+    fn parses_retry_after_and_rate_limit_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RETRY_AFTER, "120".parse().unwrap());
+        headers.insert("X-RateLimit-Remaining", "0".parse().unwrap());
+        headers.insert("X-RateLimit-Reset", "60".parse().unwrap());
+
+        assert_eq!(
+            RetryAfter::from_headers(&headers),
+            Some(RetryAfter::Delay(Duration::from_secs(120)))
+        );
+        assert_eq!(
+            RateLimitHeaders::from_headers(&headers),
+            Some(RateLimitHeaders {
+                limit: None,
+                remaining: Some(0),
+                reset: Some(Duration::from_secs(60)),
+            })
+        );
+
+        headers.insert(
+            header::RETRY_AFTER,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(
+            RetryAfter::from_headers(&headers).map(|retry_after| retry_after.remaining()),
+            Some(Duration::ZERO)
+        );
+
+        let received_at = Instant::now() - Duration::from_secs(15);
+        let quota = QuotaInfo::from_headers(&headers, received_at).unwrap();
+        assert!(quota.reset_in().unwrap() <= Duration::from_secs(45));
+        assert!(QuotaInfo::from_headers(&HeaderMap::new(), received_at).is_none());
+    }
+
+    #[test]
+    fn truncates_logged_payload() {
+        let payload = "a".repeat(10);
+        let logged = |logging| {
+            format!(
+                "{:?}",
+                LoggedPayload {
+                    payload: &payload,
+                    logging
+                }
+            )
+        };
+
+        assert_eq!(logged(PayloadLogging::Skip), "<skipped>");
+        assert_eq!(logged(PayloadLogging::Full), format!("{payload:?}"));
+        assert_eq!(logged(PayloadLogging::Truncate(20)), format!("{payload:?}"));
+        assert_eq!(
+            logged(PayloadLogging::Truncate(4)),
+            "\"aaa... (8 bytes truncated)"
+        );
+    }
 }
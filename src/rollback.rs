@@ -0,0 +1,126 @@
+//! Compensation of completed steps when a multi-step operation fails.
+//!
+//! After each successful step of a composite operation, register the action undoing it with
+//! [`Rollback::on_failure`]. If a later step fails, [`Rollback::compensate`] runs the registered actions
+//! in reverse order, e.g. deleting what was created, leaving the remote state consistent.
+//! Once all steps have succeeded, [`Rollback::commit`] discards the actions.
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+
+#[cfg(not(coverage))]
+use tracing::instrument;
+use tracing::{info, warn};
+
+use crate::{error::Error, Result};
+
+type Compensation<'a> = Box<dyn FnOnce() -> Pin<Box<dyn Future<Output = Result<()>> + 'a>> + 'a>;
+
+/// Compensating actions for the completed steps of a multi-step operation.
+#[derive(Default)]
+pub struct Rollback<'a> {
+    steps: Vec<(String, Compensation<'a>)>,
+}
+
+/// A compensating action which failed, leaving its step in place.
+#[derive(Debug)]
+pub struct CompensationFailure {
+    /// The description of the step, as passed to [`Rollback::on_failure`].
+    pub step: String,
+    pub error: Error,
+}
+
+impl<'a> Rollback<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `compensate` as undoing the completed step described by `step`.
+    pub fn on_failure<F, Fut>(&mut self, step: impl Into<String>, compensate: F)
+    where
+        F: FnOnce() -> Fut + 'a,
+        Fut: Future<Output = Result<()>> + 'a,
+    {
+        self.steps.push((
+            step.into(),
+            Box::new(move || -> Pin<Box<dyn Future<Output = Result<()>> + 'a>> {
+                Box::pin(compensate())
+            }),
+        ));
+    }
+
+    /// The number of completed steps.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// All steps have succeeded; discard the compensating actions.
+    pub fn commit(self) {}
+
+    /// Run the compensating actions in reverse order of their steps.
+    ///
+    /// A failing action does not stop the others. Returns the failures, which need manual attention.
+    #[cfg_attr(not(coverage), instrument(skip(self), fields(steps.len = self.steps.len())))]
+    pub async fn compensate(self) -> Vec<CompensationFailure> {
+        let mut failures = Vec::new();
+
+        for (step, compensate) in self.steps.into_iter().rev() {
+            info!("Compensating step '{step}'.");
+            if let Err(error) = compensate().await {
+                warn!("Failed to compensate step '{step}': {error}");
+                failures.push(CompensationFailure { step, error });
+            }
+        }
+
+        failures
+    }
+}
+
+impl fmt::Debug for Rollback<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Rollback")
+            .field(
+                "steps",
+                &self.steps.iter().map(|(step, _)| step).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[tokio::test]
+    async fn compensates_in_reverse_order() {
+        let undone = Mutex::new(Vec::new());
+        let mut rollback = Rollback::new();
+
+        for step in ["create method", "create product", "assign users"] {
+            let undone = &undone;
+            rollback.on_failure(step, move || async move {
+                if step == "create product" {
+                    return Err(ErrorKind::UnknownEnvironment {
+                        label: "test".into(),
+                    }
+                    .into());
+                }
+                undone.lock().unwrap().push(step);
+                Ok(())
+            });
+        }
+
+        let failures = rollback.compensate().await;
+        assert_eq!(*undone.lock().unwrap(), ["assign users", "create method"]);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].step, "create product");
+    }
+}
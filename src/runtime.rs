@@ -0,0 +1,218 @@
+//! Minimal async runtime abstraction.
+//!
+//! The crate uses `tokio` by default. With the `async-io` feature (and without `tokio`),
+//! the `async-io` reactor is used instead, which is what `async-std` and `smol` run on.
+//! If neither feature is enabled, files are read using blocking `std::fs` calls,
+//! and timers are driven by a single shared helper thread.
+
+use std::{io, path::Path, time::Duration};
+
+/// Failure reading a file, distinguishing between opening and reading.
+#[derive(Debug)]
+pub(crate) enum ReadFileError {
+    Open(io::Error),
+    Read(io::Error),
+}
+
+/// Read the entire contents of the file at `path`.
+#[cfg(feature = "tokio")]
+pub(crate) async fn read_file(path: &Path) -> Result<Vec<u8>, ReadFileError> {
+    use tokio::{fs::File, io::AsyncReadExt};
+
+    let mut data = Vec::new();
+    File::open(path)
+        .await
+        .map_err(ReadFileError::Open)?
+        .read_to_end(&mut data)
+        .await
+        .map_err(ReadFileError::Read)?;
+
+    Ok(data)
+}
+
+/// Read the entire contents of the file at `path`.
+#[cfg(all(feature = "async-io", not(feature = "tokio")))]
+pub(crate) async fn read_file(path: &Path) -> Result<Vec<u8>, ReadFileError> {
+    use async_fs::File;
+    use futures_lite::AsyncReadExt;
+
+    let mut data = Vec::new();
+    File::open(path)
+        .await
+        .map_err(ReadFileError::Open)?
+        .read_to_end(&mut data)
+        .await
+        .map_err(ReadFileError::Read)?;
+
+    Ok(data)
+}
+
+/// Read the entire contents of the file at `path`.
+///
+/// Without a runtime feature, this blocks the current thread.
+/// Identity certificates and icons are small, so this is acceptable as a fallback.
+#[cfg(not(any(feature = "tokio", feature = "async-io")))]
+pub(crate) async fn read_file(path: &Path) -> Result<Vec<u8>, ReadFileError> {
+    use std::{fs::File, io::Read};
+
+    let mut data = Vec::new();
+    File::open(path)
+        .map_err(ReadFileError::Open)?
+        .read_to_end(&mut data)
+        .map_err(ReadFileError::Read)?;
+
+    Ok(data)
+}
+
+/// Wait until `duration` has elapsed.
+#[cfg(feature = "tokio")]
+pub(crate) async fn sleep(duration: Duration) -> crate::Result<()> {
+    tokio::time::sleep(duration).await;
+
+    Ok(())
+}
+
+/// Wait until `duration` has elapsed.
+#[cfg(all(feature = "async-io", not(feature = "tokio")))]
+pub(crate) async fn sleep(duration: Duration) -> crate::Result<()> {
+    async_io::Timer::after(duration).await;
+
+    Ok(())
+}
+
+/// Wait until `duration` has elapsed.
+///
+/// Without a runtime feature, a single shared helper thread named `basispoort-timer` wakes the waiting tasks.
+/// Fails with [`ErrorKind::StartTimer`] if the thread cannot be spawned.
+#[cfg(not(any(feature = "tokio", feature = "async-io")))]
+pub(crate) async fn sleep(duration: Duration) -> crate::Result<()> {
+    let (waker, woken) = futures_channel::oneshot::channel();
+    timer::schedule(timer::Timer {
+        deadline: std::time::Instant::now() + duration,
+        waker,
+    })?;
+
+    // The timer thread never drops a timer without waking it.
+    let _ = woken.await;
+
+    Ok(())
+}
+
+/// The timer thread driving [`sleep`] without a runtime feature.
+#[cfg(not(any(feature = "tokio", feature = "async-io")))]
+mod timer {
+    use std::cmp::{Ordering, Reverse};
+    use std::collections::BinaryHeap;
+    use std::sync::{mpsc, Mutex};
+    use std::time::Instant;
+
+    use futures_channel::oneshot;
+
+    use crate::{error::ErrorKind, Result};
+
+    /// The timer thread's queue, started by the first timer.
+    static TIMERS: Mutex<Option<mpsc::Sender<Timer>>> = Mutex::new(None);
+
+    /// Wakes its task once the deadline has passed.
+    pub(super) struct Timer {
+        pub(super) deadline: Instant,
+        pub(super) waker: oneshot::Sender<()>,
+    }
+
+    impl PartialEq for Timer {
+        fn eq(&self, other: &Self) -> bool {
+            self.deadline == other.deadline
+        }
+    }
+
+    impl Eq for Timer {}
+
+    impl PartialOrd for Timer {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Timer {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.deadline.cmp(&other.deadline)
+        }
+    }
+
+    /// Pass `timer` to the timer thread, spawning it if not running yet.
+    pub(super) fn schedule(timer: Timer) -> Result<()> {
+        let mut timers = TIMERS
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let timer = match timers.as_ref() {
+            Some(sender) => match sender.send(timer) {
+                Ok(()) => return Ok(()),
+                // The thread has stopped, e.g. by panicking. Start another one.
+                Err(mpsc::SendError(timer)) => timer,
+            },
+            None => timer,
+        };
+
+        let (sender, receiver) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("basispoort-timer".into())
+            .spawn(move || run(receiver))
+            .map_err(ErrorKind::StartTimer)?;
+        // The receiver lives as long as the new thread, which has just been started.
+        let _ = sender.send(timer);
+        *timers = Some(sender);
+
+        Ok(())
+    }
+
+    /// Wake each timer once its deadline has passed, receiving new timers in the meantime.
+    fn run(receiver: mpsc::Receiver<Timer>) {
+        let mut timers = BinaryHeap::new();
+        loop {
+            let now = Instant::now();
+            while timers
+                .peek()
+                .is_some_and(|Reverse(timer): &Reverse<Timer>| timer.deadline <= now)
+            {
+                if let Some(Reverse(timer)) = timers.pop() {
+                    let _ = timer.waker.send(());
+                }
+            }
+
+            let received = match timers.peek() {
+                Some(Reverse(timer)) => receiver.recv_timeout(timer.deadline - now),
+                None => receiver
+                    .recv()
+                    .map_err(|mpsc::RecvError| mpsc::RecvTimeoutError::Disconnected),
+            };
+            match received {
+                Ok(timer) => timers.push(Reverse(timer)),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    }
+}
+
+#[cfg(all(test, not(any(feature = "tokio", feature = "async-io"))))]
+mod tests {
+    use std::time::Instant;
+
+    use futures_lite::future;
+
+    use super::*;
+
+    #[test]
+    fn sleeps_on_a_shared_timer_thread() {
+        let started = Instant::now();
+        future::block_on(future::zip(
+            sleep(Duration::from_millis(20)),
+            sleep(Duration::from_millis(10)),
+        ))
+        .0
+        .unwrap();
+
+        assert!(started.elapsed() >= Duration::from_millis(20));
+    }
+}
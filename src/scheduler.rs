@@ -0,0 +1,363 @@
+//! Distribute per-institution sync tasks over a pool of workers.
+//!
+//! A [`Scheduler`] runs its workers concurrently within the calling task, so it works with any async runtime.
+//! Idle workers take the next task from a shared queue, which is ordered largest first by [`SyncTask::weight`]
+//! (e.g. the number of students). Long-running large schools thereby start early, while the many small ones
+//! fill the gaps, balancing the load over the workers. Each worker may be paced by its own [`RateLimiter`].
+//!
+//! Failed tasks can be recorded in a [`DeadLetterQueue`], and retried later with [`Scheduler::retry_dead_letters`].
+//!
+//! Each worker runs in a `scheduler.worker` span, and each task in a `scheduler.task` span, so a stuck sync can be
+//! traced to its worker and institution. The crate spawns no tasks of its own: a scheduler shows up in
+//! `tokio-console` as the task calling [`Scheduler::run`]. Enable the `tokio-console` feature and build with
+//! `RUSTFLAGS="--cfg tokio_unstable"` to emit tokio's task instrumentation, then install the
+//! [`console-subscriber`](https://docs.rs/console-subscriber) layer. Prefer spawning the calling task with a name,
+//! e.g. using `tokio::task::Builder`, to tell concurrent sync runs apart.
+
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::num::{NonZeroU32, NonZeroUsize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+#[cfg(not(coverage))]
+use tracing::instrument;
+use tracing::{debug, info, info_span, warn, Instrument};
+
+use crate::{
+    concurrency::join_all,
+    dead_letter::DeadLetterQueue,
+    events::{Event, EventSink},
+    progress::{ProgressReporter, ProgressSender},
+    rate_limit::RateLimiter,
+    BasispoortId, Result,
+};
+
+/// An institution to be synced.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SyncTask {
+    pub institution_id: BasispoortId,
+    /// The expected size of the sync, e.g. the institution's number of students. Larger tasks are started first.
+    pub weight: u64,
+}
+
+/// The worker running a task, passed to the sync function.
+#[derive(Debug, Clone)]
+pub struct Worker {
+    /// The worker's index, from `0` to the number of workers.
+    pub index: usize,
+    /// The worker's own rate limiter, if configured. A token is acquired before each task.
+    pub rate_limiter: Option<RateLimiter>,
+}
+
+/// The outcome of a single [`SyncTask`].
+#[derive(Debug)]
+pub struct TaskOutcome<T> {
+    pub task: SyncTask,
+    /// The index of the worker which ran the task.
+    pub worker: usize,
+    pub elapsed: Duration,
+    pub result: Result<T>,
+}
+
+/// The consolidated outcome of all tasks, in order of completion.
+#[derive(Debug)]
+pub struct SyncReport<T> {
+    pub outcomes: Vec<TaskOutcome<T>>,
+    pub elapsed: Duration,
+}
+
+impl<T> SyncReport<T> {
+    /// Outcomes of tasks which succeeded.
+    pub fn succeeded(&self) -> impl Iterator<Item = &TaskOutcome<T>> {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.result.is_ok())
+    }
+
+    /// Outcomes of tasks which failed.
+    pub fn failed(&self) -> impl Iterator<Item = &TaskOutcome<T>> {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.result.is_err())
+    }
+}
+
+/// Runs [`SyncTask`]s on a pool of workers.
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    workers: NonZeroUsize,
+    rate_limit: Option<(NonZeroU32, NonZeroU32)>,
+    dead_letters: Option<DeadLetterQueue>,
+    event_sink: Option<Arc<dyn EventSink>>,
+}
+
+impl Scheduler {
+    pub fn new(workers: NonZeroUsize) -> Self {
+        Self {
+            workers,
+            rate_limit: None,
+            dead_letters: None,
+            event_sink: None,
+        }
+    }
+
+    /// Give each worker its own [`RateLimiter`], allowing `requests_per_second` with bursts of up to `burst`.
+    pub fn rate_limit_per_worker(
+        mut self,
+        requests_per_second: NonZeroU32,
+        burst: NonZeroU32,
+    ) -> Self {
+        self.rate_limit = Some((requests_per_second, burst));
+        self
+    }
+
+    /// Record failed tasks in `dead_letters`. A task's record is removed once it succeeds.
+    pub fn dead_letters(mut self, dead_letters: DeadLetterQueue) -> Self {
+        self.dead_letters = Some(dead_letters);
+        self
+    }
+
+    /// Emit an [`Event::SyncTaskFinished`] to `event_sink` for each finished task.
+    pub fn event_sink(mut self, event_sink: Arc<dyn EventSink>) -> Self {
+        self.event_sink = Some(event_sink);
+        self
+    }
+
+    /// Run `sync` for each of `tasks`, reporting progress to `progress`, if given.
+    ///
+    /// A failing task does not stop the others; all outcomes are collected in the returned [`SyncReport`].
+    #[cfg_attr(not(coverage), instrument(skip(tasks, sync, progress)))]
+    pub async fn run<T, F, Fut>(
+        &self,
+        tasks: impl IntoIterator<Item = SyncTask>,
+        sync: F,
+        progress: Option<&ProgressSender>,
+    ) -> SyncReport<T>
+    where
+        F: Fn(SyncTask, Worker) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let started = Instant::now();
+
+        let mut tasks: Vec<_> = tasks.into_iter().collect();
+        tasks.sort_by_key(|task| Reverse(task.weight));
+        info!(
+            "Running {} sync tasks on {} workers.",
+            tasks.len(),
+            self.workers
+        );
+
+        let queue = Mutex::new(VecDeque::from(tasks));
+        let outcomes = Mutex::new(Vec::new());
+        let reporter = Mutex::new(ProgressReporter::start(
+            progress,
+            "scheduler.run",
+            Some(
+                queue
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .len(),
+            ),
+        ));
+
+        let workers = (0..self.workers.get())
+            .map(|index| Worker {
+                index,
+                rate_limiter: self.rate_limit.map(|(requests_per_second, burst)| {
+                    RateLimiter::new(requests_per_second, burst)
+                }),
+            })
+            .map(|worker| {
+                let span = info_span!("scheduler.worker", worker = worker.index);
+                run_worker(
+                    worker,
+                    &queue,
+                    &outcomes,
+                    &reporter,
+                    self.event_sink.as_deref(),
+                    &sync,
+                )
+                .instrument(span)
+            })
+            .collect();
+
+        join_all(workers).await;
+
+        let outcomes = outcomes
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(dead_letters) = &self.dead_letters {
+            update_dead_letters(dead_letters, &outcomes).await;
+        }
+
+        reporter
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .finish();
+
+        SyncReport {
+            outcomes,
+            elapsed: started.elapsed(),
+        }
+    }
+
+    /// Run `sync` for each task recorded in the dead-letter queue, as [`Scheduler::run`] does.
+    ///
+    /// Without a dead-letter queue, there is nothing to retry, and the returned report is empty.
+    #[cfg_attr(not(coverage), instrument(skip(sync, progress)))]
+    pub async fn retry_dead_letters<T, F, Fut>(
+        &self,
+        sync: F,
+        progress: Option<&ProgressSender>,
+    ) -> Result<SyncReport<T>>
+    where
+        F: Fn(SyncTask, Worker) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let tasks = match &self.dead_letters {
+            Some(dead_letters) => dead_letters.list::<SyncTask>().await?,
+            None => Vec::new(),
+        };
+        info!("Retrying {} dead-lettered sync tasks.", tasks.len());
+
+        Ok(self
+            .run(
+                tasks.into_iter().map(|dead_letter| dead_letter.item),
+                sync,
+                progress,
+            )
+            .await)
+    }
+}
+
+/// Record failed tasks in `dead_letters`, and remove succeeded ones.
+///
+/// Failing to update the queue must not hide the sync results, so such errors are logged only.
+async fn update_dead_letters<T>(dead_letters: &DeadLetterQueue, outcomes: &[TaskOutcome<T>]) {
+    for outcome in outcomes {
+        let key = outcome.task.institution_id.to_string();
+        let updated = match &outcome.result {
+            Ok(_) => dead_letters.remove(&key).await,
+            Err(error) => dead_letters.record(&key, outcome.task, error).await,
+        };
+        if let Err(error) = updated {
+            warn!("Failed to update dead letter of institution {key}: {error}");
+        }
+    }
+}
+
+/// Take tasks from `queue` until it is empty, collecting their outcomes in `outcomes`.
+async fn run_worker<T, F, Fut>(
+    worker: Worker,
+    queue: &Mutex<VecDeque<SyncTask>>,
+    outcomes: &Mutex<Vec<TaskOutcome<T>>>,
+    reporter: &Mutex<ProgressReporter>,
+    event_sink: Option<&dyn EventSink>,
+    sync: &F,
+) where
+    F: Fn(SyncTask, Worker) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    loop {
+        // Bind the task first, so the lock is released before awaiting.
+        let task = queue
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .pop_front();
+        let Some(task) = task else {
+            break;
+        };
+
+        let rate_limited = match &worker.rate_limiter {
+            Some(rate_limiter) => rate_limiter.acquire().await,
+            None => Ok(()),
+        };
+
+        debug!(
+            "Worker {} syncing institution {}.",
+            worker.index, task.institution_id
+        );
+        let started = Instant::now();
+        let result = match rate_limited {
+            Ok(()) => {
+                sync(task, worker.clone())
+                    .instrument(info_span!(
+                        "scheduler.task",
+                        institution_id = %task.institution_id
+                    ))
+                    .await
+            }
+            Err(error) => Err(error),
+        };
+        let elapsed = started.elapsed();
+
+        if let Some(event_sink) = event_sink {
+            event_sink.emit(&Event::SyncTaskFinished {
+                institution_id: task.institution_id,
+                worker: worker.index,
+                duration_ms: Event::duration_ms(elapsed),
+                error: result.as_ref().err().map(ToString::to_string),
+            });
+        }
+
+        let mut reporter = reporter
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        match &result {
+            Ok(_) => reporter.item_completed(),
+            Err(error) => {
+                warn!(
+                    "Sync of institution {} failed: {error}",
+                    task.institution_id
+                );
+                reporter.item_failed();
+            }
+        }
+
+        outcomes
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(TaskOutcome {
+                task,
+                worker: worker.index,
+                elapsed,
+                result,
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn balances_tasks_largest_first() {
+        let tasks = [1, 8, 2, 3].map(|weight| SyncTask {
+            institution_id: BasispoortId::from_u32(weight as u32),
+            weight,
+        });
+
+        let report = Scheduler::new(NonZeroUsize::new(2).unwrap())
+            .run(
+                tasks,
+                |task, _| async move {
+                    tokio::time::sleep(Duration::from_millis(task.weight * 10)).await;
+                    Ok(task.weight)
+                },
+                None,
+            )
+            .await;
+
+        // The largest task occupies one worker, while the other works through the small ones.
+        let workers: Vec<_> = report
+            .outcomes
+            .iter()
+            .map(|outcome| (outcome.task.weight, outcome.worker))
+            .collect();
+        assert_eq!(workers, [(3, 1), (2, 1), (1, 1), (8, 0)]);
+        assert_eq!(report.succeeded().count(), 4);
+    }
+}
@@ -0,0 +1,170 @@
+//! Detection of response fields unknown to the typed models.
+//!
+//! Basispoort may add fields to its responses at any time. Deserialization silently ignores them,
+//! so publishers would only learn about new fields once they matter. A [`SchemaDriftDetector`], passed to
+//! [`RestClientBuilder::schema_drift_detector`][crate::rest::RestClientBuilder::schema_drift_detector],
+//! records the ignored fields per endpoint. It emits a `warn` event when a field is first seen,
+//! and counts all occurrences for [`SchemaDriftDetector::report`].
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_ignored::Path;
+use tracing::warn;
+
+use crate::deprecation::endpoint;
+use crate::Url;
+
+/// Records response fields unknown to the typed models, shared by all its clones.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaDriftDetector {
+    unknown_fields: Arc<Mutex<BTreeMap<(String, String), u64>>>,
+}
+
+/// A response field unknown to the typed model, as reported by [`SchemaDriftDetector::report`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnknownField {
+    /// The endpoint path, with numeric path segments replaced by `{id}`.
+    pub endpoint: String,
+    /// The field path within the response, e.g. `leerlingen[].nieuwVeld`.
+    pub field: String,
+    /// The number of responses containing the field.
+    pub count: u64,
+}
+
+impl SchemaDriftDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All unknown fields seen so far, ordered by endpoint and field.
+    pub fn report(&self) -> Vec<UnknownField> {
+        self.unknown_fields
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .map(|((endpoint, field), count)| UnknownField {
+                endpoint: endpoint.clone(),
+                field: field.clone(),
+                count: *count,
+            })
+            .collect()
+    }
+
+    /// Deserialize the JSON `payload` received from `url`, recording fields ignored by `T`.
+    pub(crate) fn deserialize<T: DeserializeOwned>(
+        &self,
+        url: &Url,
+        payload: &[u8],
+    ) -> serde_json::Result<T> {
+        let mut fields = Vec::new();
+        let mut deserializer = serde_json::Deserializer::from_slice(payload);
+        let value = serde_ignored::deserialize(&mut deserializer, |path| {
+            fields.push(field_path(&path));
+        })?;
+        deserializer.end()?;
+
+        if !fields.is_empty() {
+            self.record(&endpoint(url), fields);
+        }
+
+        Ok(value)
+    }
+
+    fn record(&self, endpoint: &str, mut fields: Vec<String>) {
+        // Fields in list items are reported once per item; count them once per response.
+        fields.sort_unstable();
+        fields.dedup();
+
+        let mut unknown_fields = self
+            .unknown_fields
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let new_fields: Vec<_> = fields
+            .into_iter()
+            .filter(|field| {
+                let count = unknown_fields
+                    .entry((endpoint.to_owned(), field.clone()))
+                    .or_default();
+                *count += 1;
+                *count == 1
+            })
+            .collect();
+
+        if !new_fields.is_empty() {
+            warn!(
+                endpoint,
+                fields = ?new_fields,
+                "Response contains fields unknown to the model. The Basispoort API schema may have changed."
+            );
+        }
+    }
+}
+
+/// The field path, with list indices replaced by `[]`, so a field is counted once for all list items.
+fn field_path(path: &Path<'_>) -> String {
+    match path {
+        Path::Root => String::new(),
+        Path::Seq { parent, .. } => format!("{}[]", field_path(parent)),
+        Path::Map { parent, key } => match field_path(parent) {
+            parent if parent.is_empty() => key.clone(),
+            parent => format!("{parent}.{key}"),
+        },
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => field_path(parent),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Roster {
+        #[serde(rename = "leerlingen")]
+        _students: Vec<Student>,
+    }
+
+    #[derive(Deserialize)]
+    struct Student {
+        #[serde(rename = "id")]
+        _id: i64,
+    }
+
+    #[test]
+    fn reports_unknown_fields_per_endpoint() -> serde_json::Result<()> {
+        let detector = SchemaDriftDetector::new();
+        let url: Url = "https://rest.basispoort.nl/instellingen/42/leerlingen"
+            .parse()
+            .unwrap();
+        let payload =
+            br#"{"leerlingen": [{"id": 1, "nieuw": 1}, {"id": 2, "nieuw": 2}], "totaal": 2}"#;
+
+        detector.deserialize::<Roster>(&url, payload)?;
+        detector.deserialize::<Roster>(&url, payload)?;
+
+        let endpoint = "/instellingen/{id}/leerlingen".to_owned();
+        assert_eq!(
+            detector.report(),
+            [
+                UnknownField {
+                    endpoint: endpoint.clone(),
+                    field: "leerlingen[].nieuw".into(),
+                    count: 2,
+                },
+                UnknownField {
+                    endpoint,
+                    field: "totaal".into(),
+                    count: 2,
+                },
+            ]
+        );
+
+        Ok(())
+    }
+}
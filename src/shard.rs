@@ -0,0 +1,122 @@
+//! Deterministic partitioning of institutions over independent sync workers.
+//!
+//! Each worker is configured with its own [`Shard`], e.g. from its replica index, and only syncs the institutions
+//! the shard [contains][Shard::contains]. No coordination between the workers is required: the assignment of an
+//! institution to a shard depends only on its ID and the number of shards, and is stable across processes,
+//! platforms and releases of this crate.
+//!
+//! Institutions are assigned by jump consistent hashing (Lamping & Veach, 2014). When the number of shards grows
+//! from `n` to `n + 1`, only about `1 / (n + 1)` of the institutions move, all of them to the new shard.
+
+use std::num::NonZeroU32;
+
+use crate::{error::ErrorKind, BasispoortId, Result};
+
+/// One of a fixed number of shards.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Shard {
+    index: u32,
+    count: NonZeroU32,
+}
+
+impl Shard {
+    /// Select shard `index` of `count`, failing if `index` is not below `count`.
+    pub fn new(index: u32, count: NonZeroU32) -> Result<Self> {
+        if index >= count.get() {
+            return Err(ErrorKind::InvalidShard {
+                index,
+                count: count.get(),
+            }
+            .into());
+        }
+
+        Ok(Self { index, count })
+    }
+
+    /// The shard containing `institution_id`, out of `count` shards.
+    pub fn of(institution_id: BasispoortId, count: NonZeroU32) -> Self {
+        Self {
+            index: jump_consistent_hash(institution_id.get() as u64, count.get()),
+            count,
+        }
+    }
+
+    /// All `count` shards.
+    pub fn all(count: NonZeroU32) -> impl Iterator<Item = Shard> {
+        (0..count.get()).map(move |index| Shard { index, count })
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn count(&self) -> NonZeroU32 {
+        self.count
+    }
+
+    /// Whether `institution_id` is assigned to this shard.
+    pub fn contains(&self, institution_id: BasispoortId) -> bool {
+        Self::of(institution_id, self.count) == *self
+    }
+
+    /// The institutions of `institution_ids` which are assigned to this shard.
+    pub fn select(
+        self,
+        institution_ids: impl IntoIterator<Item = BasispoortId>,
+    ) -> impl Iterator<Item = BasispoortId> {
+        institution_ids
+            .into_iter()
+            .filter(move |institution_id| self.contains(*institution_id))
+    }
+}
+
+/// Jump consistent hash of `key` into `buckets` buckets, as published by Lamping & Veach.
+///
+/// The published algorithm uses the key as a linear congruential generator's state. Since IDs are sequential,
+/// the key is mixed first (SplitMix64 finalizer), to spread neighbouring IDs over the buckets.
+fn jump_consistent_hash(key: u64, buckets: u32) -> u32 {
+    let mut key = key;
+    key = (key ^ (key >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    key = (key ^ (key >> 27)).wrapping_mul(0x94d049bb133111eb);
+    key ^= key >> 31;
+
+    let mut bucket: i64 = -1;
+    let mut jump: i64 = 0;
+    while jump < i64::from(buckets) {
+        bucket = jump;
+        key = key.wrapping_mul(2862933555777941757).wrapping_add(1);
+        jump = ((bucket + 1) as f64 * ((1u64 << 31) as f64 / ((key >> 33) + 1) as f64)) as i64;
+    }
+
+    bucket as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partitions_stably_and_consistently() -> Result<()> {
+        let count = |count| NonZeroU32::new(count).unwrap();
+        let ids: Vec<_> = (1..=1000).map(BasispoortId::from_u32).collect();
+
+        // Every institution is in exactly one shard, and the shards are roughly balanced.
+        let sizes: Vec<_> = Shard::all(count(4))
+            .map(|shard| shard.select(ids.iter().copied()).count())
+            .collect();
+        assert_eq!(sizes.iter().sum::<usize>(), 1000);
+        assert!(sizes.iter().all(|size| (200..300).contains(size)));
+
+        // Adding a shard only moves institutions to the new shard.
+        for id in &ids {
+            let before = Shard::of(*id, count(4));
+            let after = Shard::of(*id, count(5));
+            assert!(after.index() == before.index() || after.index() == 4);
+        }
+
+        assert!(Shard::new(4, count(4)).is_err());
+        assert_eq!(Shard::new(1, count(4))?.count(), count(4));
+
+        Ok(())
+    }
+}
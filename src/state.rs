@@ -0,0 +1,104 @@
+//! Persistent state for sync processes, e.g. leases.
+//!
+//! Implement [`StateStore`] on top of a database or key-value store to share state between processes.
+//! [`MemoryStateStore`] keeps state in memory, which is useful for tests and single-process deployments.
+
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::Result;
+
+/// A key-value store for JSON state records.
+#[async_trait]
+pub trait StateStore: Debug + Send + Sync {
+    /// Get the record stored at `key`.
+    async fn get(&self, key: &str) -> Result<Option<Value>>;
+
+    /// Store `value` at `key`, replacing any previous record.
+    async fn put(&self, key: &str, value: Value) -> Result<()>;
+
+    /// Remove the record stored at `key`, if any.
+    async fn delete(&self, key: &str) -> Result<()>;
+
+    /// Atomically replace the record at `key` by `new`, if the stored record equals `current`.
+    ///
+    /// `None` stands for an absent record. Returns whether the record was replaced.
+    async fn compare_and_swap(
+        &self,
+        key: &str,
+        current: Option<&Value>,
+        new: Option<Value>,
+    ) -> Result<bool>;
+
+    /// List all keys starting with `prefix`, in ascending order.
+    async fn keys(&self, prefix: &str) -> Result<Vec<String>>;
+}
+
+/// A [`StateStore`] keeping its records in memory.
+#[derive(Debug, Default)]
+pub struct MemoryStateStore {
+    records: Mutex<BTreeMap<String, Value>>,
+}
+
+impl MemoryStateStore {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    fn records(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Value>> {
+        // A panic while holding the lock cannot leave the map in an inconsistent state.
+        self.records
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl StateStore for MemoryStateStore {
+    async fn get(&self, key: &str) -> Result<Option<Value>> {
+        Ok(self.records().get(key).cloned())
+    }
+
+    async fn put(&self, key: &str, value: Value) -> Result<()> {
+        self.records().insert(key.to_owned(), value);
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.records().remove(key);
+        Ok(())
+    }
+
+    async fn compare_and_swap(
+        &self,
+        key: &str,
+        current: Option<&Value>,
+        new: Option<Value>,
+    ) -> Result<bool> {
+        let mut records = self.records();
+        if records.get(key) != current {
+            return Ok(false);
+        }
+
+        match new {
+            Some(value) => records.insert(key.to_owned(), value),
+            None => records.remove(key),
+        };
+
+        Ok(true)
+    }
+
+    async fn keys(&self, prefix: &str) -> Result<Vec<String>> {
+        Ok(self
+            .records()
+            .range(prefix.to_owned()..)
+            .map(|(key, _)| key)
+            .take_while(|key| key.starts_with(prefix))
+            .cloned()
+            .collect())
+    }
+}
@@ -0,0 +1,238 @@
+//! Reproducible datasets in the hosted license provider service, for load and integration testing.
+//!
+//! A [`Seed`] describes a number of synthetic methods, each with a number of products, generated icons
+//! and optional user assignments. [`Seed::create`] creates them through a [`HostedLicenseProviderClient`],
+//! and [`SeededDataset::tear_down`] deletes them again. The same seed always generates the same IDs and content,
+//! so runs are comparable.
+//!
+//! Seed the acceptance or test environment only. Seeding the production environment is refused.
+
+use tracing::info;
+#[cfg(not(coverage))]
+use tracing::instrument;
+
+use crate::{
+    error::ErrorKind,
+    hosted_license_provider::{
+        HostedLicenseProviderClient, MethodDetails, ProductDetails, UserIdList,
+    },
+    rest::Environment,
+    rollback::Rollback,
+    Result,
+};
+
+/// A description of a synthetic dataset.
+#[derive(Debug)]
+pub struct Seed {
+    prefix: String,
+    methods: usize,
+    products_per_method: usize,
+    users: UserIdList,
+}
+
+/// The methods and products created by [`Seed::create`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeededDataset {
+    pub method_ids: Vec<String>,
+    /// The IDs of the products, with the IDs of their methods.
+    pub product_ids: Vec<(String, String)>,
+}
+
+impl Seed {
+    /// Describe `methods` methods with `products_per_method` products each.
+    ///
+    /// All IDs start with `prefix`, which keeps the dataset apart from other data in the environment.
+    pub fn new(prefix: impl Into<String>, methods: usize, products_per_method: usize) -> Self {
+        Self {
+            prefix: prefix.into(),
+            methods,
+            products_per_method,
+            users: UserIdList::default(),
+        }
+    }
+
+    /// Grant `users` access to every method and product.
+    pub fn with_users(self, users: UserIdList) -> Self {
+        Self { users, ..self }
+    }
+
+    /// The generated methods.
+    pub fn methods(&self) -> Result<Vec<MethodDetails>> {
+        (0..self.methods)
+            .map(|index| {
+                MethodDetails::new(
+                    self.method_id(index),
+                    format!("{} method {index}", self.prefix),
+                )
+                .with_icon_bytes(icon(index), "image/svg+xml")
+                .map(MethodDetails::into_teacher_application)
+            })
+            .collect()
+    }
+
+    /// The generated products of the method at `method_index`.
+    pub fn products(&self, method_index: usize) -> Result<Vec<ProductDetails>> {
+        let method_id = self.method_id(method_index);
+
+        (0..self.products_per_method)
+            .map(|index| {
+                let product_id = format!("{method_id}-product-{index}");
+                ProductDetails::new(
+                    product_id.as_str(),
+                    format!("{} product {method_index}.{index}", self.prefix),
+                    format!("https://www.example.com/{product_id}"),
+                )?
+                .with_icon_bytes(
+                    icon(method_index * self.products_per_method + index),
+                    "image/svg+xml",
+                )
+            })
+            .collect()
+    }
+
+    /// Create the dataset.
+    ///
+    /// If a step fails, the methods and products created so far are deleted again, and the error is returned.
+    #[cfg_attr(not(coverage), instrument(name = "test_support.create", skip(client)))]
+    pub async fn create(&self, client: &HostedLicenseProviderClient) -> Result<SeededDataset> {
+        if client.transport().is_production() {
+            return Err(ErrorKind::InvalidConfigValue {
+                key: "environment".into(),
+                value: Environment::Production.to_string(),
+            }
+            .into());
+        }
+
+        let methods = self.methods()?;
+        let products = (0..self.methods)
+            .map(|index| self.products(index))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut rollback = Rollback::new();
+        match self
+            .create_steps(client, &methods, &products, &mut rollback)
+            .await
+        {
+            Ok(()) => rollback.commit(),
+            Err(error) => {
+                rollback.compensate().await;
+                return Err(error);
+            }
+        }
+
+        info!(
+            "Seeded {} methods with {} products each.",
+            self.methods, self.products_per_method
+        );
+
+        Ok(SeededDataset {
+            method_ids: methods.into_iter().map(|method| method.id).collect(),
+            product_ids: products
+                .into_iter()
+                .enumerate()
+                .flat_map(|(index, products)| {
+                    let method_id = self.method_id(index);
+                    products
+                        .into_iter()
+                        .map(move |product| (method_id.clone(), product.id))
+                })
+                .collect(),
+        })
+    }
+
+    async fn create_steps<'r>(
+        &'r self,
+        client: &'r HostedLicenseProviderClient,
+        methods: &'r [MethodDetails],
+        products: &'r [Vec<ProductDetails>],
+        rollback: &mut Rollback<'r>,
+    ) -> Result<()> {
+        for (method, products) in methods.iter().zip(products) {
+            let method_id = method.id.as_str();
+
+            client.create_method(method).await?;
+            rollback.on_failure(format!("create method {method_id}"), move || {
+                client.delete_method(method_id)
+            });
+
+            for product in products {
+                client.create_product(method_id, product).await?;
+                rollback.on_failure(
+                    format!("create product {} of method {method_id}", product.id),
+                    move || client.delete_product(method_id, product.id.as_str()),
+                );
+
+                if !self.users.users.is_empty() {
+                    client
+                        .set_product_user_ids(method_id, product.id.as_str(), &self.users)
+                        .await?;
+                }
+            }
+
+            if !self.users.users.is_empty() {
+                client.set_method_user_ids(method_id, &self.users).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn method_id(&self, index: usize) -> String {
+        format!("{}-method-{index}", self.prefix)
+    }
+}
+
+impl SeededDataset {
+    /// Delete all products and methods of the dataset.
+    ///
+    /// Attempts every deletion, returning the first error.
+    #[cfg_attr(
+        not(coverage),
+        instrument(name = "test_support.tear_down", skip(self, client))
+    )]
+    pub async fn tear_down(self, client: &HostedLicenseProviderClient) -> Result<()> {
+        let mut result = Ok(());
+
+        for (method_id, product_id) in &self.product_ids {
+            let deleted = client.delete_product(method_id, product_id).await;
+            result = result.and(deleted);
+        }
+        for method_id in &self.method_ids {
+            let deleted = client.delete_method(method_id).await;
+            result = result.and(deleted);
+        }
+
+        result
+    }
+}
+
+/// A square SVG icon, with its color derived from `index`.
+fn icon(index: usize) -> String {
+    let hue = index * 137 % 360;
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 64 64"><rect width="64" height="64" fill="hsl({hue},60%,50%)"/><text x="32" y="40" font-size="20" text-anchor="middle" fill="white">{index}</text></svg>"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_reproducible_datasets() -> Result<()> {
+        let seed = Seed::new("load-test", 2, 3);
+
+        let methods = seed.methods()?;
+        assert_eq!(methods.len(), 2);
+        assert_eq!(methods[1].id, "load-test-method-1");
+        assert_eq!(methods, seed.methods()?);
+        assert_ne!(methods[0].icon, methods[1].icon);
+
+        let products = seed.products(1)?;
+        assert_eq!(products.len(), 3);
+        assert_eq!(products[2].id, "load-test-method-1-product-2");
+        assert_eq!(products, seed.products(1)?);
+
+        Ok(())
+    }
+}
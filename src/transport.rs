@@ -0,0 +1,363 @@
+//! The requests the service clients make, abstracted from HTTP.
+//!
+//! The service clients, such as
+//! [`HostedLicenseProviderClient`][crate::hosted_license_provider::HostedLicenseProviderClient], are generic over
+//! a [`Transport`], defaulting to [`RestClient`], and own a clone of it. Unit tests of code using the service clients may pass an in-memory
+//! fake instead, responding to paths with canned payloads.
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use serde::{de::DeserializeOwned, Serialize};
+use url::Url;
+
+use crate::{
+    rest::{Environment, RestClient, ServicePaths},
+    Result,
+};
+
+/// Sends requests relative to a base URL, (de)serializing JSON payloads.
+///
+/// Paths are relative to [`Transport::base_url`] and start with one of the [`Transport::service_paths`].
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait Transport: Debug + Clone {
+    /// The URL the request paths are relative to.
+    fn base_url(&self) -> &Url;
+
+    /// Whether requests are sent to the production environment, blocking destructive operations.
+    /// Defaults to whether [`Transport::base_url`] is the production URL.
+    fn is_production(&self) -> bool {
+        *self.base_url() == Environment::Production.base_url()
+    }
+
+    /// The base paths of the services.
+    fn service_paths(&self) -> &ServicePaths;
+
+    async fn get<T: DeserializeOwned + Debug + Send>(&self, path: &str) -> Result<T>;
+
+    /// Get the raw response body.
+    async fn get_bytes(&self, path: &str) -> Result<Bytes>;
+
+    async fn post<P: Serialize + Debug + Sync + ?Sized, T: DeserializeOwned + Debug + Send>(
+        &self,
+        path: &str,
+        payload: &P,
+    ) -> Result<T>;
+
+    async fn put<P: Serialize + Debug + Sync + ?Sized, T: DeserializeOwned + Debug + Send>(
+        &self,
+        path: &str,
+        payload: &P,
+    ) -> Result<T>;
+
+    async fn delete<T: DeserializeOwned + Debug + Send>(&self, path: &str) -> Result<T>;
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Transport for RestClient {
+    fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    fn is_production(&self) -> bool {
+        RestClient::is_production(self)
+    }
+
+    fn service_paths(&self) -> &ServicePaths {
+        RestClient::service_paths(self)
+    }
+
+    async fn get<T: DeserializeOwned + Debug + Send>(&self, path: &str) -> Result<T> {
+        RestClient::get(self, path).await
+    }
+
+    async fn get_bytes(&self, path: &str) -> Result<Bytes> {
+        RestClient::get_bytes(self, path).await
+    }
+
+    async fn post<P: Serialize + Debug + Sync + ?Sized, T: DeserializeOwned + Debug + Send>(
+        &self,
+        path: &str,
+        payload: &P,
+    ) -> Result<T> {
+        RestClient::post(self, path, payload).await
+    }
+
+    async fn put<P: Serialize + Debug + Sync + ?Sized, T: DeserializeOwned + Debug + Send>(
+        &self,
+        path: &str,
+        payload: &P,
+    ) -> Result<T> {
+        RestClient::put(self, path, payload).await
+    }
+
+    async fn delete<T: DeserializeOwned + Debug + Send>(&self, path: &str) -> Result<T> {
+        RestClient::delete(self, path).await
+    }
+}
+
+#[cfg(all(test, feature = "hosted-license-provider"))]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::{
+        error::{Error, ErrorKind},
+        hosted_license_provider::{HostedLicenseProviderClient, Limits},
+        progress::ProgressEvent,
+        BasispoortId,
+    };
+
+    /// Responds to `GET` requests with canned payloads, by path. Fails any other request.
+    #[derive(Debug, Clone)]
+    struct FakeTransport {
+        base_url: Url,
+        service_paths: ServicePaths,
+        responses: HashMap<String, Value>,
+    }
+
+    impl FakeTransport {
+        fn respond(&self, path: &str) -> Result<Value> {
+            self.responses
+                .get(path)
+                .cloned()
+                .ok_or_else(|| unexpected_request("GET", path))
+        }
+    }
+
+    fn unexpected_request(method: &str, path: &str) -> Error {
+        ErrorKind::DeserializeResponseBody(serde::de::Error::custom(format!(
+            "unexpected {method} request to '{path}'"
+        )))
+        .into()
+    }
+
+    #[async_trait]
+    impl Transport for FakeTransport {
+        fn base_url(&self) -> &Url {
+            &self.base_url
+        }
+
+        fn service_paths(&self) -> &ServicePaths {
+            &self.service_paths
+        }
+
+        async fn get<T: DeserializeOwned + Debug + Send>(&self, path: &str) -> Result<T> {
+            Ok(serde_json::from_value(self.respond(path)?).unwrap())
+        }
+
+        async fn get_bytes(&self, path: &str) -> Result<Bytes> {
+            Ok(self.respond(path)?.to_string().into())
+        }
+
+        async fn post<P: Serialize + Debug + Sync + ?Sized, T: DeserializeOwned + Debug + Send>(
+            &self,
+            path: &str,
+            _payload: &P,
+        ) -> Result<T> {
+            Err(unexpected_request("POST", path))
+        }
+
+        async fn put<P: Serialize + Debug + Sync + ?Sized, T: DeserializeOwned + Debug + Send>(
+            &self,
+            path: &str,
+            _payload: &P,
+        ) -> Result<T> {
+            Err(unexpected_request("PUT", path))
+        }
+
+        async fn delete<T: DeserializeOwned + Debug + Send>(&self, path: &str) -> Result<T> {
+            Err(unexpected_request("DELETE", path))
+        }
+    }
+
+    #[tokio::test]
+    async fn service_clients_request_through_the_transport() -> Result<()> {
+        let transport = FakeTransport {
+            base_url: "http://localhost/".parse().unwrap(),
+            service_paths: ServicePaths::default(),
+            responses: HashMap::from([(
+                "/hosted-lika/management/lika/publisher/methode/method/gebruiker".into(),
+                json!({ "gebruikers": [1, 2] }),
+            )]),
+        };
+
+        let users = HostedLicenseProviderClient::new(&transport, "publisher")
+            .get_method_user_ids("method")
+            .await?;
+        assert_eq!(
+            users.users,
+            [BasispoortId::from_u32(1), BasispoortId::from_u32(2)]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn dry_runs_plan_mutations_without_sending_them() -> Result<()> {
+        let transport = FakeTransport {
+            base_url: "http://localhost/".parse().unwrap(),
+            service_paths: ServicePaths::default(),
+            responses: HashMap::from([(
+                "/hosted-lika/management/lika/publisher/methode/method/gebruiker".into(),
+                json!({ "gebruikers": [1, 2] }),
+            )]),
+        };
+
+        let client = HostedLicenseProviderClient::new(&transport, "publisher").dry_run(true);
+        let sync = client
+            .sync_method_user_ids(
+                "method",
+                &[BasispoortId::from_u32(2), BasispoortId::from_u32(3)],
+                None,
+            )
+            .await?;
+        assert_eq!(sync.added, [BasispoortId::from_u32(3)]);
+
+        let planned = client.planned_requests();
+        assert_eq!(planned.len(), 2);
+        assert_eq!(planned[0].method, reqwest::Method::POST);
+        assert_eq!(
+            planned[0].path,
+            "/hosted-lika/management/lika/publisher/methode/method/gebruiker/addlist"
+        );
+        assert_eq!(planned[0].payload, Some(json!({ "gebruikers": [3] })));
+        assert!(planned[1].path.ends_with("/gebruiker/removelist"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn clients_validate_mutations_against_their_limits() -> Result<()> {
+        let transport = FakeTransport {
+            base_url: "http://localhost/".parse().unwrap(),
+            service_paths: ServicePaths::default(),
+            responses: HashMap::from([(
+                "/hosted-lika/management/lika/publisher/methode/method/gebruiker".into(),
+                json!({ "gebruikers": [] }),
+            )]),
+        };
+
+        let client = HostedLicenseProviderClient::new(&transport, "publisher")
+            .with_limits(Limits::default().with_max_users_per_list(2))
+            .dry_run(true);
+        let users: Vec<_> = (1..=5).map(BasispoortId::from_u32).collect();
+
+        let error = client
+            .add_method_user_ids("method", &users.clone().into())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::LimitExceeded {
+                limit: "max_users_per_list",
+                max: 2,
+                actual: 5,
+            }
+        ));
+        assert!(client.planned_requests().is_empty());
+
+        let (progress, mut events) = futures_channel::mpsc::channel(8);
+        client
+            .sync_method_user_ids("method", &users, Some(&progress))
+            .await?;
+        let events = std::iter::from_fn(|| events.try_recv().ok()).collect::<Vec<_>>();
+        assert_eq!(
+            events.last(),
+            Some(&ProgressEvent::Finished {
+                operation: "hosted_license_provider.sync_method_user_ids",
+                completed: 3,
+                failed: 0,
+            })
+        );
+
+        let payloads: Vec<_> = client
+            .planned_requests()
+            .into_iter()
+            .map(|planned| planned.payload)
+            .collect();
+        assert_eq!(
+            payloads,
+            [
+                Some(json!({ "gebruikers": [1, 2] })),
+                Some(json!({ "gebruikers": [3, 4] })),
+                Some(json!({ "gebruikers": [5] })),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn dry_runs_leave_the_icon_cache_unchanged() -> Result<()> {
+        use crate::hosted_license_provider::{IconCache, MethodDetails};
+
+        let transport = FakeTransport {
+            base_url: "http://localhost/".parse().unwrap(),
+            service_paths: ServicePaths::default(),
+            responses: HashMap::from([(
+                "/hosted-lika/management/lika/publisher/methode/method".into(),
+                json!({ "id": "method", "naam": "Method", "icon": "image/png,AAAA", "tags": [] }),
+            )]),
+        };
+
+        let client = HostedLicenseProviderClient::new(&transport, "publisher").dry_run(true);
+        let icon_cache = IconCache::new();
+        let unchanged = MethodDetails::new("method", "Method").with_icon("image/png,AAAA");
+        let changed = MethodDetails::new("method", "Method").with_icon("image/png,BBBB");
+        client
+            .update_method_with_icon_cache(&unchanged, &icon_cache)
+            .await?;
+        client
+            .update_method_with_icon_cache(&changed, &icon_cache)
+            .await?;
+        client
+            .update_method_with_icon_cache(&changed, &icon_cache)
+            .await?;
+
+        let icons: Vec<_> = client
+            .planned_requests()
+            .into_iter()
+            .map(|planned| planned.payload.unwrap()["icon"].clone())
+            .collect();
+        assert_eq!(
+            icons,
+            [
+                Value::Null,
+                json!("image/png,BBBB"),
+                json!("image/png,BBBB")
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "license-gateway")]
+    #[tokio::test]
+    async fn license_gateway_clients_plan_mutations_in_dry_runs() -> Result<()> {
+        use crate::license_gateway::LicenseGatewayClient;
+
+        let transport = FakeTransport {
+            base_url: "http://localhost/".parse().unwrap(),
+            service_paths: ServicePaths::default(),
+            responses: HashMap::new(),
+        };
+
+        let client = LicenseGatewayClient::new(&transport).dry_run(true);
+        client.post::<_, ()>("methode", &json!({ "id": 1 })).await?;
+        client.delete::<()>("methode/1").await?;
+
+        let planned = client.planned_requests();
+        assert_eq!(planned.len(), 2);
+        assert_eq!(planned[0].path, "rest/v2/licenties/methode");
+        assert_eq!(planned[0].payload, Some(json!({ "id": 1 })));
+        assert_eq!(planned[1].method, reqwest::Method::DELETE);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,15 @@
+//! Foreign types appearing in the public API.
+//!
+//! Use these re-exports instead of depending on `bytes`, `reqwest`, `url`, `chrono` or `time` directly,
+//! so their versions always match the ones used by this crate.
+
+pub use bytes::Bytes;
+#[cfg(not(target_arch = "wasm32"))]
+pub use reqwest::{tls::Version as TlsVersion, Identity};
+pub use reqwest::{Method, StatusCode};
+pub use url::{ParseError as UrlParseError, Url};
+
+#[cfg(feature = "chrono")]
+pub use chrono::{DateTime, NaiveDate, Utc};
+#[cfg(feature = "time")]
+pub use time::{Date, OffsetDateTime};
@@ -4,6 +4,7 @@ use color_eyre::{
     eyre::{bail, WrapErr},
     Result,
 };
+use futures_lite::StreamExt;
 use itertools::Itertools;
 #[cfg(not(coverage))]
 use tracing::instrument;
@@ -22,6 +23,16 @@ use util::*;
 
 mod util;
 
+const fn ids<const N: usize>(ids: [u32; N]) -> [BasispoortId; N] {
+    let mut basispoort_ids = [BasispoortId::from_u32(0); N];
+    let mut i = 0;
+    while i < N {
+        basispoort_ids[i] = BasispoortId::from_u32(ids[i]);
+        i += 1;
+    }
+    basispoort_ids
+}
+
 const METHOD_ID: &str = "lifecycle_integration_test_method";
 
 const METHOD_CREATE_NAME: &str = "Test method (POST)";
@@ -31,10 +42,10 @@ const METHOD_UPDATE_NAME: &str = "Test method (PUT)";
 const METHOD_UPDATE_CODE: &str = "method-update";
 
 // The three-digit user IDs do not exist.
-const METHOD_SET_USER_IDS: [BasispoortId; 4] = [123, 128684, 128683, 456];
-const METHOD_SET_USER_IDS_EXPECTED: [BasispoortId; 2] = [128683, 128684];
-const METHOD_ADD_USER_IDS: [BasispoortId; 4] = [123, 128691, 128690, 456];
-const METHOD_ADD_USER_IDS_EXPECTED: [BasispoortId; 2] = [128690, 128691];
+const METHOD_SET_USER_IDS: [BasispoortId; 4] = ids([123, 128684, 128683, 456]);
+const METHOD_SET_USER_IDS_EXPECTED: [BasispoortId; 2] = ids([128683, 128684]);
+const METHOD_ADD_USER_IDS: [BasispoortId; 4] = ids([123, 128691, 128690, 456]);
+const METHOD_ADD_USER_IDS_EXPECTED: [BasispoortId; 2] = ids([128690, 128691]);
 
 const PRODUCT_ID: &str = "lifecycle_integration_test_product";
 
@@ -45,14 +56,15 @@ const PRODUCT_UPDATE_NAME: &str = "Test product (PUT)";
 const PRODUCT_UPDATE_CODE: &str = "product-update";
 
 // TODO
-const PRODUCT_SET_USER_IDS: [BasispoortId; 3] = [127660, 127665, 127666];
-const PRODUCT_ADD_USER_IDS: [BasispoortId; 2] = [157478, 157480];
+const PRODUCT_SET_USER_IDS: [BasispoortId; 3] = ids([127660, 127665, 127666]);
+const PRODUCT_ADD_USER_IDS: [BasispoortId; 2] = ids([157478, 157480]);
 
 const BULK_GRANT_USER_IDS: [BasispoortId; 11] =
-    [1, 2, 127664, 3, 4, 127663, 127667, 5, 6, 128690, 128683];
-const BULK_GRANT_USER_IDS_EXPECTED: [BasispoortId; 5] = [127663, 127664, 127667, 128683, 128690];
+    ids([1, 2, 127664, 3, 4, 127663, 127667, 5, 6, 128690, 128683]);
+const BULK_GRANT_USER_IDS_EXPECTED: [BasispoortId; 5] =
+    ids([127663, 127664, 127667, 128683, 128690]);
 const BULK_REVOKE_USER_IDS: [BasispoortId; 10] =
-    [1, 2, 127663, 127667, 5, 6, 128690, 128689, 128692, 128693];
+    ids([1, 2, 127663, 127667, 5, 6, 128690, 128689, 128692, 128693]);
 
 const APPLICATION_CREATE_ICON_URL: &str =
     "https://www.example.com/path/icon.svg?query=value#anchor";
@@ -150,6 +162,10 @@ async fn hosted_license_provider_application_lifecycle() -> Result<()> {
         .into_iter()
         .any(|method| method.id == METHOD_ID));
 
+    debug!("Stream methods (confirm contained).");
+    let methods: Vec<MethodDetails> = client.stream_methods().try_collect().await?;
+    assert!(methods.into_iter().any(|method| method.id == METHOD_ID));
+
     debug!("Fetch method (confirm created).");
     let method = get_method(&client).await?;
     assert_eq!(method.id, METHOD_ID);
@@ -234,6 +250,10 @@ async fn hosted_license_provider_application_lifecycle() -> Result<()> {
         .into_iter()
         .any(|product| product.id == PRODUCT_ID));
 
+    debug!("Stream method's products (confirm contained).");
+    let products: Vec<ProductDetails> = client.stream_products(METHOD_ID).try_collect().await?;
+    assert!(products.into_iter().any(|product| product.id == PRODUCT_ID));
+
     debug!("Fetch product (confirm created).");
     let product = get_product(&client).await?;
     assert_eq!(product.id, PRODUCT_ID);
@@ -387,19 +407,14 @@ async fn hosted_license_provider_application_lifecycle() -> Result<()> {
 #[cfg_attr(not(coverage), instrument)]
 fn make_hosted_license_provider_service_client(
     rest_client: &RestClient,
-) -> Result<HostedLicenseProviderClient<'_>> {
-    Ok(HostedLicenseProviderClient::new(
-        rest_client,
-        &env::var("HOSTED_LICENSE_PROVIDER_IDENTITY_CODE").wrap_err(
-            "could not get environment variable `HOSTED_LICENSE_PROVIDER_IDENTITY_CODE`",
-        )?,
-    ))
+) -> Result<HostedLicenseProviderClient> {
+    Ok(HostedLicenseProviderClient::new_from_env(rest_client)?)
 }
 
 // == Method ==
 
 #[cfg_attr(not(coverage), instrument)]
-async fn get_methods(client: &HostedLicenseProviderClient<'_>) -> Result<MethodDetailsList> {
+async fn get_methods(client: &HostedLicenseProviderClient) -> Result<MethodDetailsList> {
     debug!("Getting all methods...");
     let methods_list = client.get_methods().await?;
 
@@ -410,7 +425,7 @@ async fn get_methods(client: &HostedLicenseProviderClient<'_>) -> Result<MethodD
 }
 
 #[cfg_attr(not(coverage), instrument)]
-async fn get_method(client: &HostedLicenseProviderClient<'_>) -> Result<MethodDetails> {
+async fn get_method(client: &HostedLicenseProviderClient) -> Result<MethodDetails> {
     debug!("Getting method '{METHOD_ID}'...");
     let method = client.get_method(METHOD_ID).await?;
 
@@ -421,7 +436,7 @@ async fn get_method(client: &HostedLicenseProviderClient<'_>) -> Result<MethodDe
 }
 
 #[cfg_attr(not(coverage), instrument)]
-async fn create_method(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+async fn create_method(client: &HostedLicenseProviderClient) -> Result<()> {
     debug!("Creating method '{METHOD_ID}'...");
 
     let method = MethodDetails::new(METHOD_ID, METHOD_CREATE_NAME)
@@ -450,7 +465,7 @@ async fn create_method(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
 }
 
 #[cfg_attr(not(coverage), instrument)]
-async fn update_method(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+async fn update_method(client: &HostedLicenseProviderClient) -> Result<()> {
     debug!("Updating (or creating) method '{METHOD_ID}'...");
 
     let method = MethodDetails::new(METHOD_ID, METHOD_UPDATE_NAME)
@@ -479,7 +494,7 @@ async fn update_method(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
 }
 
 #[cfg_attr(not(coverage), instrument)]
-async fn delete_method(client: &HostedLicenseProviderClient<'_>) -> crate::Result<()> {
+async fn delete_method(client: &HostedLicenseProviderClient) -> crate::Result<()> {
     debug!("Deleting method '{METHOD_ID}'...");
 
     client.delete_method(METHOD_ID).await?;
@@ -492,7 +507,7 @@ async fn delete_method(client: &HostedLicenseProviderClient<'_>) -> crate::Resul
 // == Method users (classic ID) ==
 
 #[cfg_attr(not(coverage), instrument)]
-async fn get_method_user_ids(client: &HostedLicenseProviderClient<'_>) -> Result<UserIdList> {
+async fn get_method_user_ids(client: &HostedLicenseProviderClient) -> Result<UserIdList> {
     debug!("Getting user IDs with access to method '{METHOD_ID}'...");
 
     let users = client.get_method_user_ids(METHOD_ID).await?;
@@ -504,7 +519,7 @@ async fn get_method_user_ids(client: &HostedLicenseProviderClient<'_>) -> Result
 }
 
 #[cfg_attr(not(coverage), instrument)]
-async fn set_method_user_ids(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+async fn set_method_user_ids(client: &HostedLicenseProviderClient) -> Result<()> {
     let user_ids = Vec::from(METHOD_SET_USER_IDS);
     let user_ids_fmt = user_ids.iter().join(", ");
     debug!("Granting access to method '{METHOD_ID}' exclusively to user IDs {user_ids_fmt}...");
@@ -525,7 +540,7 @@ async fn set_method_user_ids(client: &HostedLicenseProviderClient<'_>) -> Result
 }
 
 #[cfg_attr(not(coverage), instrument)]
-async fn add_method_user_ids(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+async fn add_method_user_ids(client: &HostedLicenseProviderClient) -> Result<()> {
     let user_ids = Vec::from(METHOD_ADD_USER_IDS);
     let user_ids_fmt = user_ids.iter().join(", ");
     debug!("Granting access to method '{METHOD_ID}' to additional user IDs {user_ids_fmt}...");
@@ -546,7 +561,7 @@ async fn add_method_user_ids(client: &HostedLicenseProviderClient<'_>) -> Result
 }
 
 #[cfg_attr(not(coverage), instrument)]
-async fn remove_method_user_ids(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+async fn remove_method_user_ids(client: &HostedLicenseProviderClient) -> Result<()> {
     let user_ids = Vec::from(METHOD_SET_USER_IDS);
     let user_ids_fmt = user_ids.iter().join(", ");
     debug!("Revoking access to method '{METHOD_ID}' from user IDs {user_ids_fmt}...");
@@ -567,7 +582,7 @@ async fn remove_method_user_ids(client: &HostedLicenseProviderClient<'_>) -> Res
 }
 
 #[cfg_attr(not(coverage), instrument)]
-async fn delete_method_user_ids(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+async fn delete_method_user_ids(client: &HostedLicenseProviderClient) -> Result<()> {
     debug!("Revoking all access to method '{METHOD_ID}'...");
 
     client.delete_method_user_ids(METHOD_ID).await?;
@@ -582,7 +597,7 @@ async fn delete_method_user_ids(client: &HostedLicenseProviderClient<'_>) -> Res
 // TODO: Implement chain ID  tests when / if switch to EckId is really happening.
 
 // #[cfg_attr(not(coverage), instrument)]
-// async fn get_method_user_chain_ids(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+// async fn get_method_user_chain_ids(client: &HostedLicenseProviderClient) -> Result<()> {
 //     let users = client.get_method_user_chain_ids(METHOD_ID).await?;
 
 //     println!("users: {users:#?}");
@@ -591,12 +606,9 @@ async fn delete_method_user_ids(client: &HostedLicenseProviderClient<'_>) -> Res
 // }
 
 // #[cfg_attr(not(coverage), instrument)]
-// async fn set_method_user_chain_ids(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+// async fn set_method_user_chain_ids(client: &HostedLicenseProviderClient) -> Result<()> {
 //     // TODO: How do valid chain IDs look?
-//     let users: UserChainIdList = vec![UserChainId {
-//         institution_id: 123,
-//         chain_id: "https://ketenid.nl/abc".into(),
-//     }]
+//     let users: UserChainIdList = vec![UserChainId::new(123, "https://ketenid.nl/abc")]
 //     .into();
 //     println!("{users:#?}");
 
@@ -604,12 +616,9 @@ async fn delete_method_user_ids(client: &HostedLicenseProviderClient<'_>) -> Res
 // }
 
 // #[cfg_attr(not(coverage), instrument)]
-// async fn add_method_user_chain_ids(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+// async fn add_method_user_chain_ids(client: &HostedLicenseProviderClient) -> Result<()> {
 //     // TODO: How do valid chain IDs look?
-//     let users: UserChainIdList = vec![UserChainId {
-//         institution_id: 123,
-//         chain_id: "https://ketenid.nl/def".into(),
-//     }]
+//     let users: UserChainIdList = vec![UserChainId::new(123, "https://ketenid.nl/def")]
 //     .into();
 //     println!("{users:#?}");
 
@@ -617,12 +626,9 @@ async fn delete_method_user_ids(client: &HostedLicenseProviderClient<'_>) -> Res
 // }
 
 // #[cfg_attr(not(coverage), instrument)]
-// async fn remove_method_user_chain_ids(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+// async fn remove_method_user_chain_ids(client: &HostedLicenseProviderClient) -> Result<()> {
 //     // TODO: How do valid chain IDs look?
-//     let users: UserChainIdList = vec![UserChainId {
-//         institution_id: 123,
-//         chain_id: "https://ketenid.nl/def".into(),
-//     }]
+//     let users: UserChainIdList = vec![UserChainId::new(123, "https://ketenid.nl/def")]
 //     .into();
 //     println!("{users:#?}");
 
@@ -630,14 +636,14 @@ async fn delete_method_user_ids(client: &HostedLicenseProviderClient<'_>) -> Res
 // }
 
 // #[cfg_attr(not(coverage), instrument)]
-// async fn delete_method_user_chain_ids(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+// async fn delete_method_user_chain_ids(client: &HostedLicenseProviderClient) -> Result<()> {
 //     client.delete_method_user_chain_ids(METHOD_ID).await
 // }
 
 // == Product ==
 
 #[cfg_attr(not(coverage), instrument)]
-async fn get_products(client: &HostedLicenseProviderClient<'_>) -> Result<ProductDetailsList> {
+async fn get_products(client: &HostedLicenseProviderClient) -> Result<ProductDetailsList> {
     debug!("Getting all products of method '{METHOD_ID}'...");
     let products_list = client.get_products(METHOD_ID).await?;
 
@@ -648,7 +654,7 @@ async fn get_products(client: &HostedLicenseProviderClient<'_>) -> Result<Produc
 }
 
 #[cfg_attr(not(coverage), instrument)]
-async fn get_product(client: &HostedLicenseProviderClient<'_>) -> Result<ProductDetails> {
+async fn get_product(client: &HostedLicenseProviderClient) -> Result<ProductDetails> {
     debug!("Getting product '{PRODUCT_ID}' of method '{METHOD_ID}'...");
     let product = client.get_product(METHOD_ID, PRODUCT_ID).await?;
 
@@ -659,7 +665,7 @@ async fn get_product(client: &HostedLicenseProviderClient<'_>) -> Result<Product
 }
 
 #[cfg_attr(not(coverage), instrument)]
-async fn create_product(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+async fn create_product(client: &HostedLicenseProviderClient) -> Result<()> {
     debug!("Creating product '{PRODUCT_ID}' in '{METHOD_ID}'...");
 
     let product = ProductDetails::new(
@@ -692,7 +698,7 @@ async fn create_product(client: &HostedLicenseProviderClient<'_>) -> Result<()>
 }
 
 #[cfg_attr(not(coverage), instrument)]
-async fn update_product(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+async fn update_product(client: &HostedLicenseProviderClient) -> Result<()> {
     debug!("Updating (or creating) product '{PRODUCT_ID}' in '{METHOD_ID}'...");
 
     let product = ProductDetails::new(
@@ -725,7 +731,7 @@ async fn update_product(client: &HostedLicenseProviderClient<'_>) -> Result<()>
 }
 
 #[cfg_attr(not(coverage), instrument)]
-async fn delete_product(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+async fn delete_product(client: &HostedLicenseProviderClient) -> Result<()> {
     debug!("Deleting product '{PRODUCT_ID}' of method '{METHOD_ID}'...");
 
     client.delete_product(METHOD_ID, PRODUCT_ID).await?;
@@ -738,7 +744,7 @@ async fn delete_product(client: &HostedLicenseProviderClient<'_>) -> Result<()>
 // == Product users (classic ID) ==
 
 #[cfg_attr(not(coverage), instrument)]
-async fn get_product_user_ids(client: &HostedLicenseProviderClient<'_>) -> Result<UserIdList> {
+async fn get_product_user_ids(client: &HostedLicenseProviderClient) -> Result<UserIdList> {
     debug!("Getting user IDs with access to product '{PRODUCT_ID}' of method '{METHOD_ID}'...");
 
     let users = client.get_product_user_ids(METHOD_ID, PRODUCT_ID).await?;
@@ -750,7 +756,7 @@ async fn get_product_user_ids(client: &HostedLicenseProviderClient<'_>) -> Resul
 }
 
 #[cfg_attr(not(coverage), instrument)]
-async fn set_product_user_ids(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+async fn set_product_user_ids(client: &HostedLicenseProviderClient) -> Result<()> {
     let user_ids = Vec::from(PRODUCT_SET_USER_IDS);
     let user_ids_fmt = user_ids.iter().join(", ");
     debug!("Granting access to product '{PRODUCT_ID}' of method '{METHOD_ID}' exclusively to user IDs {user_ids_fmt}...");
@@ -773,7 +779,7 @@ async fn set_product_user_ids(client: &HostedLicenseProviderClient<'_>) -> Resul
 }
 
 #[cfg_attr(not(coverage), instrument)]
-async fn add_product_user_ids(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+async fn add_product_user_ids(client: &HostedLicenseProviderClient) -> Result<()> {
     let user_ids = Vec::from(PRODUCT_ADD_USER_IDS);
     let user_ids_fmt = user_ids.iter().join(", ");
     debug!("Granting access to product '{PRODUCT_ID}' of method '{METHOD_ID}' to additional user IDs {user_ids_fmt}...");
@@ -796,7 +802,7 @@ async fn add_product_user_ids(client: &HostedLicenseProviderClient<'_>) -> Resul
 }
 
 #[cfg_attr(not(coverage), instrument)]
-async fn remove_product_user_ids(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+async fn remove_product_user_ids(client: &HostedLicenseProviderClient) -> Result<()> {
     let user_ids = Vec::from(PRODUCT_SET_USER_IDS);
     let user_ids_fmt = user_ids.iter().join(", ");
     debug!("Revoking access to product '{PRODUCT_ID}' of method '{METHOD_ID}' from user IDs {user_ids_fmt}...");
@@ -819,7 +825,7 @@ async fn remove_product_user_ids(client: &HostedLicenseProviderClient<'_>) -> Re
 }
 
 #[cfg_attr(not(coverage), instrument)]
-async fn delete_product_user_ids(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+async fn delete_product_user_ids(client: &HostedLicenseProviderClient) -> Result<()> {
     debug!("Revoking all access to product '{PRODUCT_ID}' of method '{METHOD_ID}'...");
 
     client
@@ -836,7 +842,7 @@ async fn delete_product_user_ids(client: &HostedLicenseProviderClient<'_>) -> Re
 // TODO: Implement chain ID  tests when / if switch to EckId is really happening.
 
 // #[cfg_attr(not(coverage), instrument)]
-// async fn get_product_user_chain_ids(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+// async fn get_product_user_chain_ids(client: &HostedLicenseProviderClient) -> Result<()> {
 //     let users = client
 //         .get_product_user_chain_ids(METHOD_ID, PRODUCT_ID)
 //         .await?;
@@ -847,12 +853,9 @@ async fn delete_product_user_ids(client: &HostedLicenseProviderClient<'_>) -> Re
 // }
 
 // #[cfg_attr(not(coverage), instrument)]
-// async fn set_product_user_chain_ids(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+// async fn set_product_user_chain_ids(client: &HostedLicenseProviderClient) -> Result<()> {
 //     // TODO: How do valid chain IDs look?
-//     let users: UserChainIdList = vec![UserChainId {
-//         institution_id: 123,
-//         chain_id: "https://ketenid.nl/abc".into(),
-//     }]
+//     let users: UserChainIdList = vec![UserChainId::new(123, "https://ketenid.nl/abc")]
 //     .into();
 //     println!("{users:#?}");
 
@@ -862,12 +865,9 @@ async fn delete_product_user_ids(client: &HostedLicenseProviderClient<'_>) -> Re
 // }
 
 // #[cfg_attr(not(coverage), instrument)]
-// async fn add_product_user_chain_ids(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+// async fn add_product_user_chain_ids(client: &HostedLicenseProviderClient) -> Result<()> {
 //     // TODO: How do valid chain IDs look?
-//     let users: UserChainIdList = vec![UserChainId {
-//         institution_id: 123,
-//         chain_id: "https://ketenid.nl/def".into(),
-//     }]
+//     let users: UserChainIdList = vec![UserChainId::new(123, "https://ketenid.nl/def")]
 //     .into();
 //     println!("{users:#?}");
 
@@ -877,12 +877,9 @@ async fn delete_product_user_ids(client: &HostedLicenseProviderClient<'_>) -> Re
 // }
 
 // #[cfg_attr(not(coverage), instrument)]
-// async fn remove_product_user_chain_ids(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+// async fn remove_product_user_chain_ids(client: &HostedLicenseProviderClient) -> Result<()> {
 //     // TODO: How do valid chain IDs look?
-//     let users: UserChainIdList = vec![UserChainId {
-//         institution_id: 123,
-//         chain_id: "https://ketenid.nl/def".into(),
-//     }]
+//     let users: UserChainIdList = vec![UserChainId::new(123, "https://ketenid.nl/def")]
 //     .into();
 //     println!("{users:#?}");
 
@@ -892,7 +889,7 @@ async fn delete_product_user_ids(client: &HostedLicenseProviderClient<'_>) -> Re
 // }
 
 // #[cfg_attr(not(coverage), instrument)]
-// async fn delete_product_user_chain_ids(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+// async fn delete_product_user_chain_ids(client: &HostedLicenseProviderClient) -> Result<()> {
 //     client
 //         .delete_product_user_chain_ids(METHOD_ID, PRODUCT_ID)
 //         .await
@@ -901,27 +898,20 @@ async fn delete_product_user_ids(client: &HostedLicenseProviderClient<'_>) -> Re
 // == Method and product users (bulk request) ==
 
 #[cfg_attr(not(coverage), instrument)]
-async fn bulk_grant_permissions(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+async fn bulk_grant_permissions(client: &HostedLicenseProviderClient) -> Result<()> {
     let user_ids = Vec::from(BULK_GRANT_USER_IDS);
     let user_ids_fmt = user_ids.iter().join(", ");
     debug!("Granting access to product '{PRODUCT_ID}' and method '{METHOD_ID}' to bulk user IDs {user_ids_fmt}...");
 
-    let bulk_request = BulkRequest {
-        method_ids: vec![METHOD_ID.into()],
-        product_ids: vec![PRODUCT_ID.into()],
-        user_ids,
-        user_chain_ids: vec![
-            // TODO: Implement chain ID  tests when / if switch to EckId is really happening.
-            // UserChainId {
-            //     institution_id: 123,
-            //     chain_id: "https://ketenid.nl/abc".into(),
-            // },
-            // UserChainId {
-            //     institution_id: 123,
-            //     chain_id: "https://ketenid.nl/def".into(),
-            // },
-        ],
-    };
+    let bulk_request = BulkRequest::new()
+        .with_method_ids([METHOD_ID])
+        .with_product_ids([PRODUCT_ID])
+        .with_user_ids(user_ids);
+    // TODO: Implement chain ID  tests when / if switch to EckId is really happening.
+    // .with_user_chain_ids([
+    //     UserChainId::new(123, "https://ketenid.nl/abc"),
+    //     UserChainId::new(123, "https://ketenid.nl/def"),
+    // ]);
 
     trace!("BulkRequest (Debug): {bulk_request:#?}");
     debug!(
@@ -937,27 +927,20 @@ async fn bulk_grant_permissions(client: &HostedLicenseProviderClient<'_>) -> Res
 }
 
 #[cfg_attr(not(coverage), instrument)]
-async fn bulk_revoke_permissions(client: &HostedLicenseProviderClient<'_>) -> Result<()> {
+async fn bulk_revoke_permissions(client: &HostedLicenseProviderClient) -> Result<()> {
     let user_ids = Vec::from(BULK_REVOKE_USER_IDS);
     let user_ids_fmt = user_ids.iter().join(", ");
     debug!("Revoking access to product '{PRODUCT_ID}' and method '{METHOD_ID}' from bulk user IDs {user_ids_fmt}...");
 
-    let bulk_request = BulkRequest {
-        method_ids: vec![METHOD_ID.into()],
-        product_ids: vec![PRODUCT_ID.into()],
-        user_ids,
-        user_chain_ids: vec![
-            // TODO: Implement chain ID  tests when / if switch to EckId is really happening.
-            // UserChainId {
-            //     institution_id: 123,
-            //     chain_id: "https://ketenid.nl/abc".into(),
-            // },
-            // UserChainId {
-            //     institution_id: 123,
-            //     chain_id: "https://ketenid.nl/123".into(),
-            // },
-        ],
-    };
+    let bulk_request = BulkRequest::new()
+        .with_method_ids([METHOD_ID])
+        .with_product_ids([PRODUCT_ID])
+        .with_user_ids(user_ids);
+    // TODO: Implement chain ID  tests when / if switch to EckId is really happening.
+    // .with_user_chain_ids([
+    //     UserChainId::new(123, "https://ketenid.nl/abc"),
+    //     UserChainId::new(123, "https://ketenid.nl/123"),
+    // ]);
 
     trace!("BulkRequest (Debug): {bulk_request:#?}");
     debug!(
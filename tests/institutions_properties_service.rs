@@ -1,3 +1,5 @@
+use std::num::NonZeroUsize;
+
 use color_eyre::Result;
 #[cfg(not(coverage))]
 use tracing::instrument;
@@ -52,7 +54,7 @@ async fn institution_properties_service() -> Result<()> {
 }
 
 #[cfg_attr(not(coverage), instrument)]
-async fn get_institution_ids(client: &InstitutionsServiceClient<'_>) -> Result<Vec<BasispoortId>> {
+async fn get_institution_ids(client: &InstitutionsServiceClient) -> Result<Vec<BasispoortId>> {
     debug!("Getting all institution IDs...");
     let institution_ids = client.get_institution_ids().await?;
 
@@ -64,19 +66,28 @@ async fn get_institution_ids(client: &InstitutionsServiceClient<'_>) -> Result<V
 
 #[cfg_attr(not(coverage), instrument)]
 async fn get_institutions_details(
-    client: &InstitutionsServiceClient<'_>,
+    client: &InstitutionsServiceClient,
     institution_ids: &Vec<BasispoortId>,
 ) -> Result<Vec<(BasispoortId, InstitutionDetails)>> {
     debug!("Getting all institutions details...");
 
     let mut institutions_details = Vec::with_capacity(institution_ids.len());
 
-    for institution_id in institution_ids {
-        debug!("Getting institution {institution_id} details...");
-        let institution_details = client.get_institution_details(*institution_id).await?;
-        trace!("Institution details: {:#?}", institution_details);
+    for (institution_id, institution_details) in client
+        .get_institutions_details_concurrent(
+            institution_ids.iter().copied(),
+            NonZeroUsize::new(8).unwrap(),
+            None,
+        )
+        .await
+    {
+        let institution_details = institution_details?;
+        trace!(
+            "Institution {institution_id} details: {:#?}",
+            institution_details
+        );
 
-        institutions_details.push((*institution_id, institution_details));
+        institutions_details.push((institution_id, institution_details));
     }
 
     debug!("Got all institutions details.");
@@ -86,7 +97,7 @@ async fn get_institutions_details(
 
 #[cfg_attr(not(coverage), instrument)]
 async fn get_institutions_overviews(
-    client: &InstitutionsServiceClient<'_>,
+    client: &InstitutionsServiceClient,
     institution_ids: &Vec<BasispoortId>,
 ) -> Result<()> {
     debug!("Getting all institutions overviews...");
@@ -104,7 +115,7 @@ async fn get_institutions_overviews(
 
 #[cfg_attr(not(coverage), instrument)]
 async fn get_institutions_groups(
-    client: &InstitutionsServiceClient<'_>,
+    client: &InstitutionsServiceClient,
     institution_ids: &Vec<BasispoortId>,
 ) -> Result<()> {
     debug!("Getting all institutions groups...");
@@ -122,7 +133,7 @@ async fn get_institutions_groups(
 
 #[cfg_attr(not(coverage), instrument)]
 async fn get_institutions_students(
-    client: &InstitutionsServiceClient<'_>,
+    client: &InstitutionsServiceClient,
     institution_ids: &Vec<BasispoortId>,
 ) -> Result<()> {
     debug!("Getting all institutions students...");
@@ -168,7 +179,7 @@ async fn get_institutions_students(
 
 #[cfg_attr(not(coverage), instrument)]
 async fn get_institutions_staff(
-    client: &InstitutionsServiceClient<'_>,
+    client: &InstitutionsServiceClient,
     institution_ids: &Vec<BasispoortId>,
 ) -> Result<()> {
     debug!("Getting all institutions staff...");
@@ -186,7 +197,7 @@ async fn get_institutions_staff(
 
 #[cfg_attr(not(coverage), instrument)]
 async fn get_institutions_shortcut_references(
-    client: &InstitutionsServiceClient<'_>,
+    client: &InstitutionsServiceClient,
     institution_ids: &Vec<BasispoortId>,
 ) -> Result<()> {
     debug!("Getting all institutions shortcut references...");
@@ -209,7 +220,7 @@ async fn get_institutions_shortcut_references(
 
 #[cfg_attr(not(coverage), instrument)]
 async fn get_institutions_synchronization_permissions(
-    client: &InstitutionsServiceClient<'_>,
+    client: &InstitutionsServiceClient,
     institution_ids: &Vec<BasispoortId>,
 ) -> Result<()> {
     debug!("Getting all institutions synchronization permissions...");
@@ -232,7 +243,7 @@ async fn get_institutions_synchronization_permissions(
 
 #[cfg_attr(not(coverage), instrument)]
 async fn search_institutions_by_brin_code(
-    client: &InstitutionsServiceClient<'_>,
+    client: &InstitutionsServiceClient,
     institutions_details: &Vec<(BasispoortId, InstitutionDetails)>,
 ) -> Result<()> {
     debug!("Searching for institutions per BRIN code without branch code...");
@@ -271,4 +282,3 @@ async fn search_institutions_by_brin_code(
 
     Ok(())
 }
-
@@ -26,7 +26,7 @@ async fn synchronization_permissions_mutations() -> Result<()> {
 
 #[cfg_attr(not(coverage), instrument)]
 async fn get_synchronization_permissions_mutations(
-    client: &InstitutionsServiceClient<'_>,
+    client: &InstitutionsServiceClient,
 ) -> Result<()> {
     let local: DateTime<Local> = Local::now();
     let date = NaiveDate::from_ymd_opt(local.year(), local.month(), local.day()).unwrap();
@@ -15,7 +15,7 @@ use tracing_subscriber::prelude::*;
 
 use basispoort_sync_client::{
     institutions::InstitutionsServiceClient,
-    rest::{RestClient, RestClientBuilder},
+    rest::{Environment, RestClient, RestClientBuilder},
 };
 
 // == Setup ==
@@ -68,16 +68,14 @@ async fn make_rest_client() -> Result<RestClient> {
     Ok(RestClientBuilder::new(
         &env::var("IDENTITY_CERT_FILE")
             .wrap_err("could not get environment variable `IDENTITY_CERT_FILE`")?,
-        env::var("ENVIRONMENT")
-            .wrap_err("could not get environment variable `ENVIRONMENT`")?
-            .parse()?,
-    )
+        Environment::from_env()?,
+    )?
     .build()
     .await?)
 }
 
 #[allow(dead_code)] // This function is not used in the `hosted_license_provider_lifecycle` integration test.
 #[cfg_attr(not(coverage), instrument)]
-pub fn make_institutions_service_client(rest_client: &RestClient) -> InstitutionsServiceClient<'_> {
+pub fn make_institutions_service_client(rest_client: &RestClient) -> InstitutionsServiceClient {
     InstitutionsServiceClient::new(rest_client)
 }